@@ -1,509 +1,9205 @@
 pub mod payments
 {
-    use std::collections::{HashMap, HashSet};
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
     use std::fs::File;
     use std::io::{BufWriter, BufReader};
+    use std::path::PathBuf;
     use regex::Regex;
     use serde::{Serialize, Deserialize};
+    use crate::clock::{self, Clock, SystemClock};
+    use crate::ids::{IdSource, RandomIds};
+    use crate::money::{ExactSum, Money};
 
     type PaymentResult = Result<(), String>;
-    
-    #[derive(Debug, Deserialize, Serialize)]
-    struct Participant
+    type Percents = BTreeMap<String, u32>;
+
+    // prints a line of a command's output to wherever the ledger sends it
+    macro_rules! say
     {
-        pub name: String,
-        pub tasks: HashSet<String>,
-        pub paid_tasks: HashSet<String>,
-        pub sum: Option<f32>,
+        ($pay:expr) => { $pay.output.print (format_args! ("\n")) };
+        ($pay:expr, $($arg:tt)*) => { $pay.output.print (format_args! ("{}\n", format_args! ($($arg)*))) };
     }
-    
-    #[derive(Debug, Deserialize, Serialize)]
-    struct Task
+
+    // how every command is called, one line for each way of calling it. the
+    // first word of a line is the command
+    pub const USAGE: &[&str] = &[
+        "add NAME[:KEY=VALUE...]...",
+        "part TASK PARTICIPANT[:WEIGHT|=AMOUNT|=PERCENT%]...|all",
+        "unpart TASK PARTICIPANT...",
+        "group NAME PARTICIPANT...|none",
+        "groups",
+        "recur TASK daily|weekly|monthly|yearly|none",
+        "tick",
+        "recurring",
+        "pay PARTICIPANT TASK AMOUNT [CURRENCY] [--date DATE] [--tip PERCENT] [--tip-split proportional|equal] [--day N] [--field FIELD=VALUE]... [--confirm]",
+        "pay PARTICIPANT TASK +AMOUNT [--confirm]",
+        "uneven TASK PARTICIPANT=AMOUNT... --payer NAME [--confirm]",
+        "cashbox in AMOUNT [--from NAME] [MEMO] [--confirm]",
+        "cashbox out AMOUNT [MEMO] [--confirm]",
+        "cashbox count AMOUNT",
+        "cashbox",
+        "dues AMOUNT/day|week|month|year all|PARTICIPANT...",
+        "dues paid PARTICIPANT [AMOUNT]",
+        "dues [remind]",
+        "dues none",
+        "invoice PARTICIPANT [FILE] [--sign]",
+        "invoice set name|address|instructions TEXT|none",
+        "notify statements --due DATE [--send] [DIR]",
+        "payment FROM TO AMOUNT [CURRENCY] [MEMO] [--for TASK] [--date DATE] [--confirm]",
+        "item TASK NAME COST PARTICIPANT...|none",
+        "tag TASK TAG|-TAG...|none",
+        "note TASK TEXT|none",
+        "refund TASK AMOUNT [MEMO]|none",
+        "discount TASK AMOUNT|PERCENT%|none",
+        "edit part NAME FIELD VALUE|none",
+        "edit task TASK FIELD VALUE|none",
+        "field NAME|-NAME...",
+        "fields",
+        "rename OLD NEW",
+        "remove NAME|TASK",
+        "remove TASK PARTICIPANT",
+        "print [-v] [-a|-t|-u|NAME|TASK...]",
+        "print [-v] --tag TAG",
+        "print --by-tag",
+        "print [-v] --by-day|--day N",
+        "print [-v] --archived",
+        "print --reimbursed",
+        "archive TASK...",
+        "settle [--by-currency] [--tag TAG] [--apply]",
+        "explain NAME|TASK",
+        "owed-by NAME",
+        "owed-to NAME",
+        "save [FILE]",
+        "load [--merge] FILE",
+        "open last|recent N",
+        "import contacts|matrix FILE",
+        "import bundle [--merge] FILE",
+        "in LEDGER COMMAND...",
+        "stats --usage|--memory",
+        "limit participants|tasks N|none",
+        "limit amount AMOUNT|none",
+        "budget PARTICIPANT AMOUNT|none",
+        "interest RATE% daily|weekly|monthly|yearly|DAYS [after DAYS]|none",
+        "rate CURRENCY AMOUNT|none",
+        "rate base CURRENCY",
+        "convert FROM TO RATE",
+        "set currency CURRENCY|none",
+        "set rounding owner|largest|round-robin|none",
+        "compact",
+        "prune --before DATE [--keep-balances]",
+        "double-entry on|off",
+        "journal",
+        "paths",
+        "set strict on|off",
+        "set dates iso|day-first|month-first|none",
+        "rule min-participants N",
+        "rule max-cost AMOUNT [except TASK|TAG...]",
+        "rule include-all TASK",
+        "rule remove N",
+        "rules",
+        "export [--anonymize [--perturb]] [FILE]",
+        "export ledgerlog FILE",
+        "export arrow DIR",
+        "export schema FILE",
+        "export bundle FILE",
+        "report [--since-last-settlement] --template FILE [OUTPUT [--sign]]",
+        "report --since-last-settlement",
+        "help [COMMAND]",
+        "parse COMMAND...",
+        "verify FILE [KEY]",
+    ];
+
+    // how many single character edits turn one string into the other
+    fn edit_distance (a: &str, b: &str) -> usize
     {
-        pub name: String,
-        pub owner: String,
-        pub participants: HashSet<String>,
-        pub cost: i32,
+        let b = b.chars ().collect::<Vec<char>> ();
+        let mut row = (0..=b.len ()).collect::<Vec<usize>> ();
+        for (i, ca) in a.chars ().enumerate ()
+        {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, cb) in b.iter ().enumerate ()
+            {
+                let above = row[j + 1];
+                row[j + 1] = (above + 1).min (row[j] + 1).min (diagonal + (ca != *cb) as usize);
+                diagonal = above;
+            }
+        }
+        row[b.len ()]
     }
 
-    #[derive(Debug, Deserialize, Serialize)]
-    pub struct Payment
+    // the candidate closest to what was typed, if any is close enough to
+    // have been a typo
+    fn closest<'a> (typed: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str>
     {
-        participants: HashMap<String, Participant>,
-        tasks: HashMap<String, Task>,
+        candidates
+            .map (|c| (edit_distance (typed, c), c))
+            .filter (|(d, _)| *d <= 2 && *d < typed.chars ().count ())
+            .min ()
+            .map (|(_, c)| c)
     }
 
-    impl Payment
+    // text made safe to put inside html
+    fn escape_html (text: &str) -> String
     {
-        pub fn new () -> Self
+        text.replace ('&', "&amp;").replace ('<', "&lt;").replace ('>', "&gt;").replace ('"', "&quot;")
+    }
+
+    // an html table with a header row, every cell escaped
+    fn html_table (header: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String
+    {
+        let cells = |row: &[String], tag: &str| row.iter ()
+            .map (|c| format! ("<{tag}>{}</{tag}>", escape_html (c)))
+            .collect::<String> ();
+        let header = header.iter ().map (|h| String::from (*h)).collect::<Vec<String>> ();
+        let mut html = format! ("<table>\n<tr>{}</tr>\n", cells (&header, "th"));
+        for row in rows
         {
-            Self {
-                participants: HashMap::new (),
-                tasks: HashMap::new ()
-            }
+            html += &format! ("<tr>{}</tr>\n", cells (&row, "td"));
         }
+        html + "</table>\n"
+    }
 
-        pub fn command (&mut self, com: &str) -> PaymentResult
+    // shows html in an evcxr notebook, which looks for these markers in
+    // whatever evcxr_display prints
+    fn evcxr_html (html: &str)
+    {
+        println! ("EVCXR_BEGIN_CONTENT text/html\n{html}EVCXR_END_CONTENT");
+    }
+
+    // picks one of several names that all start with what the user typed, or
+    // None to give up. set one with Payment::set_disambiguator to turn on
+    // prefix matching of names
+    type Choose = dyn Fn (&str, &[String]) -> Option<String> + Send + Sync;
+
+    #[derive(Clone)]
+    pub struct Disambiguator (Arc<Choose>);
+
+    impl Disambiguator
+    {
+        pub fn new (f: impl Fn (&str, &[String]) -> Option<String> + Send + Sync + 'static) -> Self
         {
-            let parts = Regex::new (r"\s+")
-                .unwrap ()
-                .split (com)
-                .collect::<Vec<&str>> ();
-            let end = &parts[1..parts.len () - 1];
-            match parts.get (0)
-            {
-                Some (&"add")   => return self.add (end),
-                Some (&"part")  => return self.part (end),
-                Some (&"pay")   => return self.pay (end),
-                Some (&"print") => self.print (end),
-                Some (&"save")  => return self.save (end),
-                Some (&"load")  => return self.load (end),
-                Some (&"rename")=> return self.rename (end),
-                Some (&"remove")=> return self.remove (end),
-                Some (&a)       => return Err (format! ("{} is not recognized as a command", a)),
-                None            => return Err (String::from ("syntax error")),
-            }
-            Ok (())
+            Self (Arc::new (f))
         }
+    }
 
-        fn load (&mut self, args: &[&str]) -> PaymentResult
+    // the clock the ledger reads the time from, shared so a test can keep
+    // moving it after handing it over
+    #[derive(Clone)]
+    struct LedgerClock (Arc<dyn Clock>);
+
+    impl Default for LedgerClock
+    {
+        fn default () -> Self
         {
-            let filename = match args.get (0)
-            {
-                Some (&f) => f,
-                None => return Err (String::from ("Not enough arguments")),
-            };
-            let file = match File::open (filename)
-            {
-                Ok (f) => f,
-                Err (_) => return Err (format! ("Unable to open file {}", filename)),
-            };
-            let payment: Payment = match serde_json::from_reader (BufReader::new (file))
-            {
-                Ok (pay) => pay,
-                Err (e) => return Err (format! ("Error deserializing file:\n{}", e)),
-            };
-            self.participants = payment.participants;
-            self.tasks = payment.tasks;
-            Ok (())
+            Self (Arc::new (SystemClock))
         }
+    }
+
+    impl std::fmt::Debug for LedgerClock
+    {
+        fn fmt (&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            f.write_str ("LedgerClock")
+        }
+    }
 
+    // where ids and random numbers come from, swapped out for a predictable
+    // source when the output has to be reproducible
+    #[derive(Clone)]
+    struct LedgerIds (Arc<dyn IdSource>);
 
-        fn save (&mut self, args: &[&str]) -> PaymentResult
+    impl Default for LedgerIds
+    {
+        fn default () -> Self
         {
-            self.calculate ();
-            match args.get (0)
-            {
-                Some (&s) => return self.save_file (s),
-                None => return self.save_string (),
-            }
+            Self (Arc::new (RandomIds))
         }
+    }
 
-        fn save_file (&self, filename: &str) -> PaymentResult
+    impl std::fmt::Debug for LedgerIds
+    {
+        fn fmt (&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
         {
-            let file = match File::create (filename)
-            {
-                Ok (f) => f,
-                Err (_) => return Err (format! ("Unable to open file {}", filename)),
-            };
-            let write = BufWriter::new (file);
-            match serde_json::to_writer_pretty (write, &self)
+            f.write_str ("LedgerIds")
+        }
+    }
+
+    // where commands print to, stdout unless the output is being captured for
+    // a caller that shows it some other way, like a server
+    #[derive(Clone, Default)]
+    struct LedgerOutput (Option<Arc<Mutex<String>>>);
+
+    impl LedgerOutput
+    {
+        fn print (&self, text: std::fmt::Arguments)
+        {
+            match &self.0
             {
-                Ok (_) => Ok (()),
-                Err (e) => Err (format! ("Error serializing the object:\n{}", e)),
+                Some (buffer) =>
+                {
+                    use std::fmt::Write;
+                    let mut buffer = buffer.lock ().unwrap_or_else (|e| e.into_inner ());
+                    let _ = buffer.write_fmt (text);
+                },
+                None => print! ("{text}"),
             }
         }
-        
-        fn save_string (&self) -> PaymentResult
+    }
+
+    impl std::fmt::Debug for LedgerOutput
+    {
+        fn fmt (&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
         {
-            let json = match serde_json::to_string_pretty (&self)
-            {
-                Ok (val) => val,
-                Err (e) => return Err (format! ("Something went wrong serializing the object:\n{}", e)),
-            };
-            println! ("{}", json);
-            Ok (())
+            f.write_str ("LedgerOutput")
         }
+    }
 
-        fn rename (&mut self, args: &[&str]) -> PaymentResult
+    // asked whether to go ahead with something that looks like a mistake,
+    // like an amount far bigger than usual. set one with
+    // Payment::set_confirmer, without one such commands fail instead
+    type Ask = dyn Fn (&str) -> bool + Send + Sync;
+
+    #[derive(Clone)]
+    pub struct Confirmer (Arc<Ask>);
+
+    impl Confirmer
+    {
+        pub fn new (f: impl Fn (&str) -> bool + Send + Sync + 'static) -> Self
         {
-            if args.len () != 2
-            {
-                return Err (String::from ("remove must be called with 2 arguments"));
+            Self (Arc::new (f))
+        }
+    }
+
+    impl std::fmt::Debug for Confirmer
+    {
+        fn fmt (&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            f.write_str ("Confirmer")
+        }
+    }
+
+    impl std::fmt::Debug for Disambiguator
+    {
+        fn fmt (&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            f.write_str ("Disambiguator")
+        }
+    }
+    
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Participant
+    {
+        pub name: String,
+        pub tasks: BTreeSet<String>,
+        pub paid_tasks: BTreeSet<String>,
+        #[serde(with = "crate::money::decimal")]
+        #[cfg_attr (feature = "schema", schemars (with = "Option<f64>"))]
+        pub sum: Option<Money>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub email: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub phone: Option<String>,
+        // the interest in sum, owed by them or to them
+        #[serde(skip)]
+        pub interest: Option<Money>,
+        // how much their shares may add up to before they are warned
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub budget: Option<Money>,
+        // the weight they get when they join a task without one being given
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub weight: Option<u32>,
+    }
+
+    impl Participant
+    {
+        fn new (name: &str) -> Self
+        {
+            Self {
+                name: String::from (name),
+                tasks: BTreeSet::new (),
+                paid_tasks: BTreeSet::new (),
+                sum: None,
+                email: None,
+                phone: None,
+                interest: None,
+                budget: None,
+                weight: None,
             }
-            if self.participants.contains_key (args[1]) || self.tasks.contains_key (args[1])
+        }
+
+        // sets one of the participant's details by name, checking the value.
+        // a value of none clears the detail
+        fn set_attribute (&mut self, key: &str, value: &str) -> PaymentResult
+        {
+            match (key, value)
             {
-                return Err (format! ("{} already exists", args[1]));
+                ("email", "none") => self.email = None,
+                ("phone", "none") => self.phone = None,
+                ("weight", "none") => self.weight = None,
+                _ => return self.set_checked_attribute (key, value),
             }
-            // see if we are renaming a participant
-            if let Some (mut part) = self.participants.remove (args[0])
+            Ok (())
+        }
+
+        fn set_checked_attribute (&mut self, key: &str, value: &str) -> PaymentResult
+        {
+            match key
             {
-                // renaming a participant
-                for task_name in &part.tasks
+                "email" =>
                 {
-                    let task = self.tasks.get_mut (task_name).unwrap ();
-                    task.participants.remove (&part.name);
-                    task.participants.insert (String::from (args[1]));
-                    if part.paid_tasks.contains (task_name)
+                    if !value.contains ('@')
                     {
-                        task.owner = String::from (args[1]);
+                        return Err (format! ("{value} is not a valid email address"));
                     }
-                }
-                part.name = String::from (args[1]);
-                self.participants.insert (String::from (args[1]), part);
-
-                return Ok (())
-            }
-            // check if there is a task with this name
-            if let Some (mut task) = self.tasks.remove (args[0])
-            {
-                for name in &task.participants
+                    self.email = Some (String::from (value));
+                },
+                "phone" =>
                 {
-                    let part = self.participants.get_mut (name).unwrap ();
-                    part.tasks.remove (&task.name);
-                    part.tasks.insert (String::from (args[1]));
-                    if part.paid_tasks.remove (&task.name)
+                    if value.is_empty () || !value.chars ().all (|c| c.is_ascii_digit () || "+-()".contains (c))
                     {
-                        part.paid_tasks.insert (String::from (args[1]));
+                        return Err (format! ("{value} is not a valid phone number"));
                     }
-                }
-                task.name = String::from (args[1]);
-                self.tasks.insert (String::from (args[1]), task);
-
-                return Ok (());
+                    self.phone = Some (String::from (value));
+                },
+                "weight" => match value.parse::<u32> ()
+                {
+                    Ok (w) if w > 0 => self.weight = Some (w).filter (|&w| w != 1),
+                    _ => return Err (format! ("{value} is not a valid weight")),
+                },
+                k => return Err (format! ("{k} is not something a participant has")),
             }
-            // nothing can be renamed, return error
-            return Err (format! ("No task or participant found named {}", args[0]));
+            Ok (())
         }
+    }
+    
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Task
+    {
+        pub name: String,
+        pub owner: String,
+        pub participants: BTreeSet<String>,
+        pub cost: Money,
+        // what the cost was paid in, None for the base currency
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub currency: Option<String>,
+        // how many shares of the cost a participant carries, everyone not
+        // listed carries one
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub weights: BTreeMap<String, u32>,
+        // what each participant owes when the task is split by exact amounts
+        // instead, these always add up to the cost
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub amounts: BTreeMap<String, Money>,
+        // each participant's percentage of the cost when the task is split
+        // that way, these always add up to 100
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub percents: BTreeMap<String, u32>,
+        // parts of the cost only some participants share, by name. the rest
+        // of the cost is split between the participants as usual
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub items: BTreeMap<String, Item>,
+        // others who paid part of the cost and how much, in the currency of
+        // the task. the owner paid whatever is left
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub payers: BTreeMap<String, Money>,
+        // the day it was paid for as YYYY-MM-DD, None in files from before
+        // tasks had dates
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub date: Option<String>,
+        // categories like food or lodging that print can group tasks by
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        pub tags: BTreeSet<String>,
+        // a tip or tax paid on top of the cost
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub tip: Option<Tip>,
+        // whatever is worth remembering about it, given with note
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub note: Option<String>,
+        // the day of a trip it belongs to, given with --day. tasks without one
+        // go by how long after the first task they were paid for
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub day: Option<u32>,
+        // values of the ledger's own fields, like an invoice number
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub fields: BTreeMap<String, String>,
+        // money given back to whoever paid, like part of a deposit
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub refunds: Vec<Refund>,
+        // taken off what was paid, like a coupon
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub discount: Option<Discount>,
+        // participants taken out with unpart, who part TASK all leaves out
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        pub excluded: BTreeSet<String>,
+        // the tasks compact folded into this one and what each of them cost,
+        // so they can still be explained and removed by name
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub folded: BTreeMap<String, Money>,
+    }
 
-        fn remove_from (&mut self, part_name: &str, task_name: &str) -> PaymentResult
+    // a discount on a task, either an amount in the currency of the task or
+    // a percent of what it cost
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    #[serde(rename_all = "kebab-case")]
+    enum Discount
+    {
+        Amount (Money),
+        Percent (u32),
+    }
+
+    // money given back for a task after it was paid for, in the currency of
+    // the task
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Refund
+    {
+        pub amount: Money,
+        pub date: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub memo: Option<String>,
+    }
+
+    // a percent of the cost paid on top of it, like a tip or a tax
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Tip
+    {
+        pub percent: u32,
+        pub split: TipSplit,
+    }
+
+    // how a tip is split between the participants
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    #[serde(rename_all = "kebab-case")]
+    enum TipSplit
+    {
+        // in proportion to what each is charged for the rest of the task
+        #[default]
+        Proportional,
+        // the same for everyone
+        Equal,
+    }
+
+    impl TipSplit
+    {
+        fn parse (text: &str) -> Result<Self, String>
         {
-            let Some (part) = self.participants.get_mut (part_name) else
+            match text
             {
-                return Err (format! ("No participant named {part_name} exists"));
-            };
-            if part.paid_tasks.contains (task_name)
+                "proportional" => Ok (TipSplit::Proportional),
+                "equal" => Ok (TipSplit::Equal),
+                t => Err (format! ("{t} is not a way to split a tip, use proportional or equal")),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Item
+    {
+        pub cost: Money,
+        pub participants: BTreeSet<String>,
+    }
+
+    impl Task
+    {
+        // what the tip came to, in the currency of the task
+        fn tip_amount (&self) -> Money
+        {
+            self.tip.map_or (Money::ZERO, |t| (self.cost * t.percent as i64).share (100))
+        }
+
+        // what was paid with the tip, in the currency of the task
+        fn total (&self) -> Money
+        {
+            self.cost + self.tip_amount ()
+        }
+
+        // what was given back of it, in the currency of the task
+        fn refunded (&self) -> Money
+        {
+            self.refunds.iter ().map (|r| r.amount).sum ()
+        }
+
+        // what the discount took off, in the currency of the task
+        fn discounted (&self) -> Money
+        {
+            match self.discount
             {
-                return Err (format! ("{part_name} paid for {task_name}, remove {task_name} instead"));
+                Some (Discount::Amount (amount)) => amount,
+                Some (Discount::Percent (percent)) => (self.total () * percent as i64).share (100),
+                None => Money::ZERO,
             }
-            if part.tasks.remove (task_name)
+        }
+
+        // whether a discount or refunds take something off every share
+        fn reduced (&self) -> bool
+        {
+            self.discount.is_some () || !self.refunds.is_empty ()
+        }
+
+        // a percentage works just like a weight out of a total of 100
+        fn weight (&self, name: &str) -> u32
+        {
+            if !self.percents.is_empty ()
             {
-                // this task exists since it's listed as a task for the participant
-                let task = self.tasks.get_mut (task_name).unwrap ();
-                task.participants.remove (part_name);
+                return self.percents.get (name).copied ().unwrap_or (0);
             }
-            Ok (())
+            self.weights.get (name).copied ().unwrap_or (1)
         }
 
-        fn remove (&mut self, args: &[&str]) -> PaymentResult
+        // how many shares the cost is divided into
+        fn total_weight (&self) -> usize
         {
-            if args.len () == 2
+            self.participants.iter ().map (|p| self.weight (p) as usize).sum ()
+        }
+
+        // goes back to splitting by weight once the amounts can't add up to the
+        // cost any more, saying so if there were any
+        fn clear_amounts (&mut self) -> Option<String>
+        {
+            if self.amounts.is_empty ()
             {
-                return self.remove_from (args[0], args[1]);
+                return None;
             }
-            if args.len () != 1
+            self.amounts.clear ();
+            Some (format! ("{} is split evenly again, give its split again with part", self.name))
+        }
+
+        // the same once the participants change, which percentages can't
+        // survive either
+        fn clear_split (&mut self) -> Option<String>
+        {
+            if self.percents.is_empty ()
             {
-                return Err (String::from ("Wrong number of arguments"));
+                return self.clear_amounts ();
             }
-            // check if the removal is a participant
-            if let Some (part) = self.participants.remove (args[0])
+            self.percents.clear ();
+            Some (format! ("{} is split evenly again, give its split again with part", self.name))
+        }
+
+        // every amount in the task put through a conversion. amounts given per
+        // participant still add up to the cost, whatever rounding leaves over
+        // goes to the largest of them
+        fn convert (&mut self, convert: &dyn Fn (Money) -> Money)
+        {
+            self.cost = convert (self.cost);
+            if !self.amounts.is_empty ()
             {
-                // remove this participant from all of their tasks
-                for task_name in &part.tasks
+                for amount in self.amounts.values_mut ()
                 {
-                    if part.paid_tasks.contains (task_name)
-                    {
-                        continue;
-                    }
-                    let task = self.tasks.get_mut (task_name).unwrap ();
-                    task.participants.remove (&part.name);
+                    *amount = convert (*amount);
                 }
-                // remove all tasks this participant owns
-                for task_name in &part.paid_tasks
+                let left = self.cost - self.amounts.values ().copied ().sum::<Money> ();
+                if let Some (largest) = self.amounts.values_mut ().max ()
                 {
-                    self.remove_task (task_name)?;
+                    *largest += left;
                 }
-                return Ok (());
             }
-            match self.remove_task (args[0])
+            for item in self.items.values_mut ()
             {
-                Ok (_) => Ok (()),
-                Err (_) => Err (format! ("{} is not a task or participant", args[0])),
+                item.cost = convert (item.cost);
             }
-        }
-
-        fn remove_task (&mut self, task_name: &str) -> PaymentResult
-        {
-            let Some (task) = self.tasks.remove (task_name) else
+            for paid in self.payers.values_mut ()
             {
-                return Err (format! ("Task {} was not present to be removed", task_name));
-            };
-            for name in &task.participants
+                *paid = convert (*paid);
+            }
+            for refund in &mut self.refunds
             {
-                let Some (part) = self.participants.get_mut (name) else
-                {
-                    continue;
-                };
-                part.tasks.remove (task_name);
-                part.paid_tasks.remove (task_name);
+                refund.amount = convert (refund.amount);
+            }
+            if let Some (Discount::Amount (amount)) = &mut self.discount
+            {
+                *amount = convert (*amount);
             }
-            Ok (())
         }
 
-        fn calculate (&mut self)
+        // a participant is gone from the task, so they can't share its items
+        // either. items nobody shares any more go back to the shared cost
+        fn leave_items (&mut self, name: &str)
         {
-            for part in self.participants.values_mut ()
+            for item in self.items.values_mut ()
             {
-                let mut sum = 0f32;
-                for task_name in &part.tasks
-                {
-                    // divide the cost of this task among its participants and
-                    // add that amount to the amount this participant owes
-                    let task = self.tasks.get (task_name).unwrap ();
-                    sum += task.cost as f32 / task.participants.len () as f32;
-                }
-                for task_name in &part.paid_tasks
+                item.participants.remove (name);
+            }
+            self.items.retain (|_, i| !i.participants.is_empty ());
+        }
+
+        // a participant goes by a new name, everywhere the task mentions them
+        fn rename_participant (&mut self, old: &str, new: &str)
+        {
+            if self.participants.remove (old)
+            {
+                self.participants.insert (String::from (new));
+            }
+            if let Some (weight) = self.weights.remove (old)
+            {
+                self.weights.insert (String::from (new), weight);
+            }
+            if let Some (amount) = self.amounts.remove (old)
+            {
+                self.amounts.insert (String::from (new), amount);
+            }
+            if let Some (percent) = self.percents.remove (old)
+            {
+                self.percents.insert (String::from (new), percent);
+            }
+            for item in self.items.values_mut ()
+            {
+                if item.participants.remove (old)
                 {
-                    // same as before but subtracting since this participant
-                    // has already paid their share of this task
-                    let task = self.tasks.get (task_name).unwrap ();
-                    sum -= task.cost as f32;
+                    item.participants.insert (String::from (new));
                 }
-                part.sum = Some (sum.round () / 100f32);
+            }
+            if let Some (paid) = self.payers.remove (old)
+            {
+                self.payers.insert (String::from (new), paid);
+            }
+            if self.owner == old
+            {
+                self.owner = String::from (new);
+            }
+            if self.excluded.remove (old)
+            {
+                self.excluded.insert (String::from (new));
             }
         }
 
-        fn print_participant (&self, part: &Participant)
+        // how a participant's share comes out of the cost, like / 3 or x 2/5
+        fn portion (&self, name: &str) -> String
         {
-            println! ("{} owes {}", part.name, part.sum.unwrap ());
-            if !part.tasks.is_empty ()
+            if !self.amounts.is_empty ()
             {
-                println! ("  participated in:");
+                String::from ("split by amount")
             }
-            for task_name in &part.tasks
+            else if !self.percents.is_empty ()
             {
-                let task = self.tasks.get (task_name).unwrap ();
-                println! ("    {task_name}: {} / {} = {}"
-                          , task.cost as f32 / 100f32
-                          , task.participants.len ()
-                          , (task.cost as f32
-                             / task.participants.len () as f32).round ()
-                          / 100f32);
+                format! ("x {}%", self.weight (name))
             }
-            if !part.paid_tasks.is_empty ()
+            else if self.weights.is_empty ()
             {
-                println! ("  paid for:");
+                format! ("/ {}", self.participants.len ())
             }
-            for task_name in &part.paid_tasks
+            else
             {
-                println! ("    {task_name}: {}",
-                          self.tasks.get (task_name)
-                          .unwrap ()
-                          .cost as f32 / 100f32);
+                format! ("x {}/{}", self.weight (name), self.total_weight ())
             }
         }
+    }
+
+    // money handed straight from one participant to another, like paying
+    // back some of what they owe
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Transfer
+    {
+        pub from: String,
+        pub to: String,
+        pub amount: Money,
+        // what the amount was paid in, None for the base currency
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub currency: Option<String>,
+        // what it was for, so transfers can be matched up with bank
+        // statements later
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub memo: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub date: Option<String>,
+        // the task it pays back, given with --for
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub task: Option<String>,
+        // the tag whose tasks it settled, for payments made by settle --tag
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub tag: Option<String>,
+        // given when it's recorded, so merging two copies of a ledger can
+        // tell one payment from two that look the same
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub id: Option<String>,
+    }
 
-        fn print_task (&self, task: &Task)
+    // how often a recurring task comes back
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    #[serde(rename_all = "kebab-case")]
+    enum Every
+    {
+        Days (u32),
+        Months (u32),
+    }
+
+    impl Every
+    {
+        fn parse (text: &str) -> Result<Self, String>
         {
-            println! ("{} paid {} for {}", task.owner, task.cost as f32 / 100f32, task.name);
-            println! ("  participants: {}", task.participants.len ());
-            for part in &task.participants
+            match text
             {
-                println! ("    {}", part);
+                "daily" => Ok (Every::Days (1)),
+                "weekly" => Ok (Every::Days (7)),
+                "monthly" => Ok (Every::Months (1)),
+                "yearly" => Ok (Every::Months (12)),
+                t => Err (format! ("{t} is not how often something comes back, use daily, weekly, monthly or yearly")),
             }
         }
 
-        fn print (&mut self, args: &[&str])
+        // the day it comes back for the given time after the start, counted
+        // from the start each time so a month after 01-31 doesn't drift
+        fn after (self, start: &str, times: u32) -> String
         {
-            self.calculate ();
-            let mut normal = true;
-            for &arg in args
+            match self
             {
-                if arg == "-a"
-                {
-                    for part in self.participants.values ()
-                    {
-                        self.print_participant (&part);
-                    }
-                    return;
-                }
-                if arg == "-t"
-                {
-                    for task in self.tasks.values ()
-                    {
-                        self.print_task (&task);
-                    }
-                    return;
-                }
-                let part = match self.participants.get (arg)
-                {
-                    Some (val) => val,
-                    None => continue,
-                };
-                self.print_participant (&part);
-                normal = false;
+                Every::Days (days) => clock::add_days (start, days * times),
+                Every::Months (months) => clock::add_months (start, months * times),
             }
-            if normal
+        }
+    }
+
+    impl std::fmt::Display for Every
+    {
+        fn fmt (&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            match self
             {
-                for part in self.participants.values ()
-                {
-                    println! ("{} owes {}", part.name, part.sum.unwrap ());
-                }
-                return;
+                Every::Days (1) => write! (f, "daily"),
+                Every::Days (7) => write! (f, "weekly"),
+                Every::Months (12) => write! (f, "yearly"),
+                Every::Months (1) => write! (f, "monthly"),
+                Every::Days (n) => write! (f, "every {n} days"),
+                Every::Months (n) => write! (f, "every {n} months"),
             }
         }
+    }
 
-        fn add (&mut self, args: &[&str]) -> PaymentResult
+    // a task that comes back every so often, like the rent. tick adds a copy
+    // of the task as it was when recur was given, once for each time it
+    // has come due
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Recurring
+    {
+        task: Task,
+        every: Every,
+        // the day of the task it was made from
+        start: String,
+        // how many copies tick has added so far
+        count: u32,
+    }
+
+    impl Recurring
+    {
+        fn next (&self) -> String
         {
-            if args.is_empty ()
+            self.every.after (&self.start, self.count + 1)
+        }
+    }
+
+    // money kept in a shared box, like a club's cash at an event. it is kept
+    // apart from what the participants owe each other
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Cashbox
+    {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        entries: Vec<CashEntry>,
+        // what was in the box when it was last counted, and on which day
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        counted: Option<(String, Money)>,
+    }
+
+    impl Cashbox
+    {
+        fn is_empty (&self) -> bool
+        {
+            self.entries.is_empty () && self.counted.is_none ()
+        }
+
+        // what should be in the box
+        fn balance (&self) -> Money
+        {
+            self.entries.iter ().map (|e| e.amount).sum ()
+        }
+    }
+
+    // the club details printed on invoices, and how many were written so
+    // each gets the next number
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Invoicing
+    {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        address: Option<String>,
+        // how to pay, like a bank account
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instructions: Option<String>,
+        #[serde(default)]
+        issued: u32,
+    }
+
+    impl Invoicing
+    {
+        fn is_empty (&self) -> bool
+        {
+            self.name.is_none () && self.address.is_none () && self.instructions.is_none () && self.issued == 0
+        }
+    }
+
+    // a fixed amount every member owes each period, like club fees. paid
+    // dues go into the cash box
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Dues
+    {
+        amount: Money,
+        every: Every,
+        // the day the first period started
+        start: String,
+        // who pays them, None for every participant
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        members: Option<BTreeSet<String>>,
+        // what each member has paid so far
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        paid: BTreeMap<String, Money>,
+    }
+
+    // money put in the box or taken out of it, out being negative
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct CashEntry
+    {
+        amount: Money,
+        // the participant who put it in, as with dues
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        from: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<String>,
+        date: String,
+    }
+
+    // where the ledger stood when it was last settled with settle --apply, so
+    // what happened since can be told apart from what was already settled
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Settled
+    {
+        date: String,
+        // the tasks there were then
+        tasks: BTreeSet<String>,
+        // how many payments there were then, the settlement's own included
+        transfers: usize,
+    }
+
+    // what part was told about one participant's share of a task
+    #[derive(Clone, Copy)]
+    enum Given
+    {
+        Nothing,
+        // alice:2
+        Weight (u32),
+        // alice=12.50
+        Amount (Money),
+        // alice=60%
+        Percent (u32),
+    }
+
+    impl Given
+    {
+        fn parse (arg: &str) -> Result<(&str, Given), String>
+        {
+            if let Some ((name, weight)) = arg.split_once (':')
             {
-                return Err (String::from ("Not enough arguments"));
+                return match weight.parse::<u32> ()
+                {
+                    Ok (w) if w > 0 => Ok ((name, Given::Weight (w))),
+                    _ => Err (format! ("{weight} is not a valid weight for {name}")),
+                };
             }
-            for &arg in args
+            let Some ((name, value)) = arg.split_once ('=') else
+            {
+                return Ok ((arg, Given::Nothing));
+            };
+            if let Some (percent) = value.strip_suffix ('%')
             {
-                let name = match arg
+                return match percent.parse::<u32> ()
                 {
-                    "" => return Err (String::from ("Not enough arguments")),
-                    "-a" => return Err (String::from ("invalid name")),
-                    n => n,
+                    Ok (p) if p <= 100 => Ok ((name, Given::Percent (p))),
+                    _ => Err (format! ("{value} is not a valid percentage for {name}")),
                 };
-                // if there is already a participant with this name, we don't want
-                // to overwrite them
-                if self.participants.contains_key (name)
-                {
-                    return Err (format! ("participant {name} was already added"));
-                }
-                if self.tasks.contains_key (name)
-                {
-                    return Err (format! ("A task named {name} exists"));
-                }
-                self.participants.insert (String::from (name), Participant
-                                  {
-                                      name: String::from (name),
-                                      tasks: HashSet::new (),
-                                      paid_tasks: HashSet::new (),
-                                      sum: None,
-                                  });
             }
-            Ok (())
+            match Money::parse (value)
+            {
+                Ok (a) if a >= Money::ZERO => Ok ((name, Given::Amount (a))),
+                _ => Err (format! ("{value} is not a valid amount for {name}")),
+            }
         }
+    }
 
-        fn pay (&mut self, args: &[&str]) -> PaymentResult
+    // one line of the double entry journal
+    struct Entry
+    {
+        task: String,
+        account: String,
+        debit: Money,
+        credit: Money,
+    }
+
+    // interest on what has been owed for longer than a grace period, see
+    // accrue_interest
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Interest
+    {
+        // percent per period in basis points, hundredths of a percent, so
+        // 1.5% is 150
+        rate: u32,
+        // days in a period
+        period: u32,
+        // days a debt goes without interest
+        grace: u32,
+    }
+
+    impl std::fmt::Display for Interest
+    {
+        fn fmt (&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
         {
-            let name = match args.get (0)
-            {
-                Some (&"") => return Err (String::from ("Not enough arguments")),
-                Some (&n) => n,
-                None => return Err (String::from ("Not enough arguments")),
-            };
-            let task_name = match args.get (1)
-            {
-                Some (&"") => return Err (String::from ("Not enough arguments")),
-                Some (&n) => n,
-                None => return Err (String::from ("Not enough arguments")),
-            };
-            if self.participants.contains_key (task_name)
+            write! (f, "{}.{:02}% every {} days after {} days", self.rate / 100, self.rate % 100, self.period, self.grace)
+        }
+    }
+
+    impl Interest
+    {
+        // a rate like 1.5% in basis points. a rate finer than a basis point
+        // can't be stored, so it's refused rather than rounded
+        fn parse_rate (text: &str) -> Result<u32, String>
+        {
+            let invalid = || format! ("{text} is not a valid rate, give a percent like 2%");
+            let percent = text.strip_suffix ('%').ok_or_else (invalid)?;
+            let (whole, fraction) = percent.split_once ('.').unwrap_or ((percent, ""));
+            if (whole.is_empty () && fraction.is_empty ())
+                || !whole.chars ().chain (fraction.chars ()).all (|c| c.is_ascii_digit ())
             {
-                return Err (format! ("Cannot add {task_name}, a participant exists with that name"));
+                return Err (invalid ());
             }
-            let price_string = match args.get (2)
+            // trailing zeros don't make it any finer
+            let fraction = fraction.trim_end_matches ('0');
+            if fraction.len () > 2
             {
-                Some (&"") => return Err (String::from ("Not enough arguments")),
-                Some (&n) => n,
-                None => return Err (String::from ("Not enough arguments")),
-            };
-            let price = match price_string.parse::<f32> ()
+                return Err (format! ("{text} is finer than a hundredth of a percent, which is as precise as a rate can be"));
+            }
+            let whole = match whole
             {
-                Ok (p) => p,
-                Err (_) => return Err (format! (
-                        "{} not a valid decimal number for the price"
-                        , price_string)),
+                "" => 0,
+                w => w.parse::<u32> ().map_err (|_| invalid ())?,
             };
-            // if this participant doesn't yet exist, add them
-            if !self.participants.contains_key (name)
+            let fraction = format! ("{fraction:0<2}").parse::<u32> ().map_err (|_| invalid ())?;
+            match whole.checked_mul (100).and_then (|r| r.checked_add (fraction))
             {
-                self.participants.insert (String::from (name), Participant
-                                  {
-                                      name: String::from (name),
-                                      tasks: HashSet::new (),
-                                      paid_tasks: HashSet::new (),
-                                      sum: None,
-                                  });
+                Some (rate) if rate > 0 => Ok (rate),
+                _ => Err (invalid ()),
             }
-            // see if the task is being edited or added
-            let task = match self.tasks.get_mut (task_name)
+        }
+    }
+
+    // caps on how big a ledger may grow, None means no cap
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Limits
+    {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        participants: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tasks: Option<usize>,
+        // amounts paid above this have to be confirmed, since they're more
+        // likely a missing decimal point than a real cost
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        amount: Option<Money>,
+    }
+
+    impl Limits
+    {
+        fn is_empty (&self) -> bool
+        {
+            self.participants.is_none () && self.tasks.is_none () && self.amount.is_none ()
+        }
+    }
+
+    // the currency balances are worked out in, and what every other currency
+    // a task was paid in is worth in it
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Currencies
+    {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base: Option<String>,
+        // how much one unit of each currency is worth in the base currency
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        rates: BTreeMap<String, f64>,
+    }
+
+    impl Currencies
+    {
+        fn is_empty (&self) -> bool
+        {
+            self.base.is_none () && self.rates.is_empty ()
+        }
+
+        // an amount in the given currency converted to the base currency
+        fn to_base (&self, amount: Money, currency: Option<&str>) -> Money
+        {
+            match currency.and_then (|c| self.rates.get (c))
             {
-                Some (val) =>
-                {
-                    // this task already exists, check if the owner should be
-                    // changed and change the cost
-                    val.cost = (price * 100f32) as i32;
-                    if val.owner != name
-                    {
-                        let owner = &val.owner;
-                        let part = self.participants.get_mut (owner).unwrap ();
-                        part.tasks.remove (&val.name);
-                        part.paid_tasks.remove (&val.name);
-                        val.participants.remove (owner);
-                        val.owner = String::from (name);
-                    }
-                    val
-                },
-                None =>
-                {
-                    self.tasks.insert (String::from (task_name), Task
-                                  {
-                                      name: String::from (task_name),
-                                      owner: String::from (name),
-                                      participants: HashSet::new (),
-                                      cost: (price * 100f32) as i32,
-                                  });
-                    self.tasks.get_mut (task_name).unwrap ()
-                },
-            };
-            // add this task to the paid tasks of the participant
-            let participant = self.participants.get_mut (name).unwrap ();
-            participant.paid_tasks.insert (String::from (task_name));
-            participant.tasks.insert (String::from (task_name));
-            //self.tasks.get_mut (task_name)
-            //    .unwrap ()
-                task.participants.insert (String::from (name));
-            Ok (())
+                Some (rate) => amount.scale (*rate),
+                None => amount,
+            }
         }
 
-        fn part (&mut self, args: &[&str]) -> PaymentResult
+        // checks a currency code and puts it in capitals, so eur and EUR are
+        // the same currency
+        fn code (text: &str) -> Result<String, String>
         {
-            if args.len () <= 1
+            if text.len () != 3 || !text.chars ().all (|c| c.is_ascii_alphabetic ())
             {
-                return Err (String::from ("Not enough arguments"));
+                return Err (format! ("{text} is not a currency code like EUR or USD"));
             }
-            let task_name = match args.get (0)
+            Ok (text.to_ascii_uppercase ())
+        }
+
+        // how many hundredths the smallest amount of a currency is, currencies
+        // without cents are counted in whole units
+        fn minor_unit (code: &str) -> i64
+        {
+            match code
             {
-                Some (&"") => return Err (String::from ("Not enough arguments")),
-                Some (&n) => n,
-                None => return Err (String::from ("Not enough arguments")),
-            };
-            let Some (task) = self.tasks.get_mut (task_name) else
+                "JPY" | "KRW" | "ISK" | "CLP" | "VND" | "PYG" | "UGX" | "XAF" | "XOF" => 100,
+                _ => 1,
+            }
+        }
+
+        // the currency a task paid in the given code is stored with, None when
+        // it's the base currency
+        fn task_currency (&self, text: &str) -> Result<Option<String>, String>
+        {
+            let code = Self::code (text)?;
+            if self.base.as_ref () == Some (&code)
             {
-                return Err (format! ("Task {task_name} has not yet been added"));
-            };
-            for &arg in &args[1..]
+                return Ok (None);
+            }
+            if !self.rates.contains_key (&code)
             {
-                let Some (participant) = self.participants.get_mut (arg) else
+                return Err (format! ("No exchange rate for {code}, add one with rate {code} AMOUNT"));
+            }
+            Ok (Some (code))
+        }
+    }
+
+    // who pays the cents left over when a cost doesn't split evenly
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    #[serde(rename_all = "kebab-case")]
+    enum Rounding
+    {
+        // whoever paid for the task
+        Owner,
+        // the participant with the largest share, the first by name on a tie
+        Largest,
+        // one cent each, starting one participant further along every task
+        RoundRobin,
+    }
+
+    // how the ledger is worked out and shown, kept in the save file with
+    // everything else
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    struct Settings
+    {
+        // amounts are printed with this currency's symbol
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        currency: Option<String>,
+        // with a policy every task is split into shares that add up to its
+        // cost exactly, without one shares are rounded
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rounding: Option<Rounding>,
+        // names that aren't participants are an error instead of being skipped
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        strict: bool,
+        // how dates are typed and shown, they're always saved as YYYY-MM-DD
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dates: Option<DateFormat>,
+    }
+
+    // the order of the day, month and year in dates, for the places that
+    // don't write them the ISO way
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    #[serde(rename_all = "kebab-case")]
+    enum DateFormat
+    {
+        // 31.05.2024
+        DayFirst,
+        // 05/31/2024
+        MonthFirst,
+    }
+
+    impl Settings
+    {
+        fn is_empty (&self) -> bool
+        {
+            self.currency.is_none () && self.rounding.is_none () && !self.strict && self.dates.is_none ()
+        }
+
+        // changes a setting by name, none puts it back to the default
+        fn set (&mut self, key: &str, value: &str) -> PaymentResult
+        {
+            match (key, value)
+            {
+                ("currency", "none") => self.currency = None,
+                ("currency", v) => self.currency = Some (Currencies::code (v)?),
+                ("rounding", "none") => self.rounding = None,
+                ("rounding", "owner") => self.rounding = Some (Rounding::Owner),
+                ("rounding", "largest") => self.rounding = Some (Rounding::Largest),
+                ("rounding", "round-robin") => self.rounding = Some (Rounding::RoundRobin),
+                ("rounding", v) => return Err (format! ("{v} is not a rounding policy, use owner, largest or round-robin")),
+                ("strict", "on") => self.strict = true,
+                ("strict", "off" | "none") => self.strict = false,
+                ("strict", _) => return Err (String::from ("strict must be set to on or off")),
+                ("dates", "iso" | "none") => self.dates = None,
+                ("dates", "day-first") => self.dates = Some (DateFormat::DayFirst),
+                ("dates", "month-first") => self.dates = Some (DateFormat::MonthFirst),
+                ("dates", v) => return Err (format! ("{v} is not a date format, use iso, day-first or month-first")),
+                (k, _) => return Err (format! ("{k} is not a setting")),
+            }
+            Ok (())
+        }
+
+        // a date as it was typed, in the ledger's date format or as
+        // YYYY-MM-DD, which is always understood. day-first dates can be
+        // written 31.05.2024 or 31/05/2024 and month-first ones 05/31/2024
+        fn parse_date (&self, text: &str) -> Result<String, String>
+        {
+            let parts = text.split (['.', '/', '-']).collect::<Vec<&str>> ();
+            let Some (format) = self.dates.filter (|_| parts[0].len () != 4) else
+            {
+                return clock::parse_date (text);
+            };
+            let invalid = || format! ("{text} is not a date like {}", self.show_date ("2024-05-31"));
+            let [first, second, year] = parts[..] else
+            {
+                return Err (invalid ());
+            };
+            let (day, month) = match format
+            {
+                DateFormat::DayFirst => (first, second),
+                DateFormat::MonthFirst => (second, first),
+            };
+            if day.len () > 2 || month.len () > 2 || year.len () != 4
+            {
+                return Err (invalid ());
+            }
+            clock::parse_date (&format! ("{year}-{month:0>2}-{day:0>2}")).map_err (|_| invalid ())
+        }
+
+        // a saved YYYY-MM-DD date the way print shows it
+        fn show_date (&self, date: &str) -> String
+        {
+            let [year, month, day] = date.split ('-').collect::<Vec<&str>> ()[..] else
+            {
+                return String::from (date);
+            };
+            match self.dates
+            {
+                Some (DateFormat::DayFirst) => format! ("{day}.{month}.{year}"),
+                Some (DateFormat::MonthFirst) => format! ("{month}/{day}/{year}"),
+                None => String::from (date),
+            }
+        }
+
+        // an amount the way print shows it, like $12.50 once a currency is set
+        fn show (&self, amount: Money) -> String
+        {
+            let Some (code) = &self.currency else
+            {
+                return amount.to_string ();
+            };
+            let symbol = match code.as_str ()
+            {
+                "USD" | "CAD" | "AUD" | "NZD" => "$",
+                "EUR" => "€",
+                "GBP" => "£",
+                "JPY" | "CNY" => "¥",
+                "INR" => "₹",
+                code => return format! ("{amount} {code}"),
+            };
+            if amount < Money::ZERO
+            {
+                format! ("-{symbol}{}", -amount)
+            }
+            else
+            {
+                format! ("{symbol}{amount}")
+            }
+        }
+    }
+
+    // something every change to the ledger is checked against, like every
+    // task having at least two participants. breaking a rule doesn't stop a
+    // change, it's reported instead
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    #[serde(tag = "rule", rename_all = "kebab-case")]
+    enum Rule
+    {
+        // every task has at least this many participants
+        MinParticipants
+        {
+            min: usize,
+        },
+        // no task costs more than this in the base currency, apart from the
+        // ones named or tagged with one of the names
+        MaxCost
+        {
+            max: Money,
+            #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+            except: BTreeSet<String>,
+        },
+        // everyone in the ledger takes part in this task
+        IncludeAll
+        {
+            task: String,
+        },
+    }
+
+    impl Rule
+    {
+        fn parse (args: &[&str]) -> Result<Self, String>
+        {
+            match args
+            {
+                ["min-participants", n] => match n.parse::<usize> ()
+                {
+                    Ok (min) => Ok (Rule::MinParticipants { min }),
+                    Err (_) => Err (format! ("{n} is not a number of participants")),
+                },
+                ["max-cost", max, rest @ ..] =>
+                {
+                    let max = Money::parse (max).map_err (|e| format! ("Invalid cost: {e}"))?;
+                    let except = match rest
+                    {
+                        [] => BTreeSet::new (),
+                        ["except", tasks @ ..] if !tasks.is_empty () => tasks.iter ().map (|t| String::from (*t)).collect (),
+                        _ => return Err (String::from ("Tasks a cost rule doesn't apply to come after except")),
+                    };
+                    Ok (Rule::MaxCost { max, except })
+                },
+                ["include-all", task] => Ok (Rule::IncludeAll { task: String::from (*task) }),
+                ["min-participants" | "max-cost" | "include-all", ..] => Err (String::from ("Wrong number of arguments")),
+                [kind, ..] => Err (format! ("{kind} is not a kind of rule, use min-participants, max-cost or include-all")),
+                [] => Err (String::from ("Not enough arguments")),
+            }
+        }
+    }
+
+    impl std::fmt::Display for Rule
+    {
+        fn fmt (&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            match self
+            {
+                Rule::MinParticipants { min } => write! (f, "every task has at least {min} participants"),
+                Rule::MaxCost { max, except } if except.is_empty () => write! (f, "no task costs more than {max}"),
+                Rule::MaxCost { max, except } => write! (f, "no task costs more than {max}, except {}"
+                                                         , except.iter ().cloned ().collect::<Vec<String>> ().join (", ")),
+                Rule::IncludeAll { task } => write! (f, "everyone takes part in {task}"),
+            }
+        }
+    }
+
+    // one way the ledger breaks one of its rules. rule is the rule's number
+    // as rules lists them, starting at 1
+    #[derive(Clone, Debug, PartialEq, Serialize)]
+    pub struct Violation
+    {
+        pub rule: usize,
+        pub task: String,
+        pub message: String,
+    }
+
+    // what a report template is given. amounts are already shown the way
+    // print shows them, so templates don't have to format money
+    #[derive(Serialize)]
+    struct Report
+    {
+        currency: Option<String>,
+        spent: String,
+        outstanding: String,
+        unsettled_tasks: usize,
+        balances: Vec<ReportBalance>,
+        tasks: Vec<ReportTask>,
+        settlement: Vec<ReportTransfer>,
+    }
+
+    #[derive(Serialize)]
+    struct ReportBalance
+    {
+        name: String,
+        balance: String,
+        // whether they owe money rather than being owed it
+        owes: bool,
+    }
+
+    #[derive(Serialize)]
+    struct ReportTask
+    {
+        name: String,
+        owner: String,
+        cost: String,
+        date: Option<String>,
+        tags: Vec<String>,
+        note: Option<String>,
+        day: Option<u32>,
+        fields: BTreeMap<String, String>,
+        shares: Vec<ReportBalance>,
+    }
+
+    #[derive(Serialize)]
+    struct ReportTransfer
+    {
+        from: String,
+        to: String,
+        amount: String,
+    }
+
+    impl Report
+    {
+        // the report as html tables, for notebooks
+        fn to_html (&self) -> String
+        {
+            let mut html = format! ("<p>total spent {}, outstanding {}, unsettled tasks {}</p>\n"
+                                    , escape_html (&self.spent)
+                                    , escape_html (&self.outstanding)
+                                    , self.unsettled_tasks);
+            html += &html_table (&["participant", "balance"], self.balances.iter ()
+                                 .map (|b| vec! [b.name.clone (), b.balance.clone ()]));
+            html += &html_table (&["task", "paid by", "cost", "date", "shares"], self.tasks.iter ()
+                                 .map (|t| vec! [t.name.clone ()
+                                                 , t.owner.clone ()
+                                                 , t.cost.clone ()
+                                                 , t.date.clone ().unwrap_or_default ()
+                                                 , t.shares.iter ()
+                                                     .map (|s| format! ("{} {}", s.name, s.balance))
+                                                     .collect::<Vec<String>> ()
+                                                     .join (", ")]));
+            if !self.settlement.is_empty ()
+            {
+                html += &html_table (&["from", "to", "amount"], self.settlement.iter ()
+                                     .map (|t| vec! [t.from.clone (), t.to.clone (), t.amount.clone ()]));
+            }
+            html
+        }
+    }
+
+    // the ledger as Arrow record batches, one for each kind of thing in it.
+    // amounts are decimals in the base currency and dates are days
+    #[cfg (feature = "arrow")]
+    pub struct ArrowTables
+    {
+        // name, owner, cost, currency, date, tags, note
+        pub tasks: arrow_array::RecordBatch,
+        // task, participant, share
+        pub shares: arrow_array::RecordBatch,
+        // from, to, amount, memo, date, task
+        pub transfers: arrow_array::RecordBatch,
+        // participant, balance
+        pub balances: arrow_array::RecordBatch,
+    }
+
+    // totals for the whole ledger, amounts in the same units as balances
+    #[derive(Clone, Debug, Serialize)]
+    pub struct Summary
+    {
+        pub spent: Money,
+        pub outstanding: Money,
+        pub unsettled_tasks: usize,
+    }
+
+    impl Summary
+    {
+        // what evcxr shows when a summary is the value of a notebook cell
+        pub fn evcxr_display (&self)
+        {
+            evcxr_html (&html_table (&["spent", "outstanding", "unsettled tasks"], std::iter::once (
+                vec! [self.spent.to_string (), self.outstanding.to_string (), self.unsettled_tasks.to_string ()])));
+        }
+    }
+
+    // what came of a command sent with an idempotency key: the sequence
+    // number of the ledger after it and what it printed, or why it failed
+    #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    pub struct Outcome
+    {
+        pub key: String,
+        pub command: String,
+        pub result: Result<(u64, String), String>,
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    pub struct Payment
+    {
+        participants: BTreeMap<String, Participant>,
+        tasks: BTreeMap<String, Task>,
+        // payments made directly between participants, oldest first
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        transfers: Vec<Transfer>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        settled: Option<Settled>,
+        // names that stand for several participants at once, like a family
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        groups: BTreeMap<String, BTreeSet<String>>,
+        // tasks that come back, by the name of the task they were made from
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        recurring: BTreeMap<String, Recurring>,
+        // tasks put away with archive. they are kept to look back on but no
+        // longer count towards anyone's balance
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        archived: BTreeMap<String, Task>,
+        // extra fields tasks can be given, like project or invoice#, for
+        // whoever keeps the books
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        fields: BTreeSet<String>,
+        #[serde(default, skip_serializing_if = "Cashbox::is_empty")]
+        cashbox: Cashbox,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dues: Option<Dues>,
+        #[serde(default, skip_serializing_if = "Invoicing::is_empty")]
+        invoicing: Invoicing,
+        // separate ledgers for parts of a bigger event, see `in`
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        subledgers: BTreeMap<String, Payment>,
+        #[serde(default, skip_serializing_if = "Limits::is_empty")]
+        limits: Limits,
+        #[serde(default, skip_serializing_if = "Currencies::is_empty")]
+        currencies: Currencies,
+        #[serde(default, skip_serializing_if = "Settings::is_empty")]
+        settings: Settings,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        rules: Vec<Rule>,
+        // balances come from the double entry journal instead of dividing
+        // costs directly
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        double_entry: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        interest: Option<Interest>,
+        // what came of the latest commands sent with an idempotency key,
+        // oldest first. they're saved so a retry after a restart still isn't
+        // run twice
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        outcomes: Vec<Outcome>,
+        // whether anything changed since the last save or load
+        #[serde(skip)]
+        dirty: bool,
+        // problems that didn't stop a command, waiting to be shown to the user
+        #[serde(skip)]
+        warnings: Vec<String>,
+        #[serde(skip)]
+        disambiguate: Option<Disambiguator>,
+        #[serde(skip)]
+        confirm: Option<Confirmer>,
+        #[serde(skip)]
+        clock: LedgerClock,
+        #[serde(skip)]
+        ids: LedgerIds,
+        #[serde(skip)]
+        output: LedgerOutput,
+        // time spent in each phase of the last command, when being measured
+        #[serde(skip)]
+        timings: Option<Vec<(&'static str, Duration)>>,
+        // the file keeping track of recently loaded and saved ledgers
+        #[serde(skip)]
+        recent: Option<PathBuf>,
+        // the file counting how many times each command was run. it's kept
+        // out of the ledger so the counts never leave this machine
+        #[serde(skip)]
+        usage: Option<PathBuf>,
+        // the file with the key reports are signed with, made the first time
+        // one is signed. like usage it never goes into the ledger
+        #[serde(skip)]
+        key: Option<PathBuf>,
+        // single letters standing for whole commands, like p for print
+        #[serde(skip)]
+        shortcuts: BTreeMap<String, String>,
+    }
+
+    impl Default for Payment
+    {
+        fn default () -> Self
+        {
+            Self::new ()
+        }
+    }
+
+    impl Payment
+    {
+        pub fn new () -> Self
+        {
+            Self {
+                participants: BTreeMap::new (),
+                tasks: BTreeMap::new (),
+                transfers: Vec::new (),
+                settled: None,
+                groups: BTreeMap::new (),
+                recurring: BTreeMap::new (),
+                archived: BTreeMap::new (),
+                fields: BTreeSet::new (),
+                cashbox: Cashbox::default (),
+                dues: None,
+                invoicing: Invoicing::default (),
+                subledgers: BTreeMap::new (),
+                limits: Limits::default (),
+                currencies: Currencies::default (),
+                settings: Settings::default (),
+                rules: Vec::new (),
+                double_entry: false,
+                interest: None,
+                outcomes: Vec::new (),
+                dirty: false,
+                warnings: Vec::new (),
+                disambiguate: None,
+                confirm: None,
+                clock: LedgerClock::default (),
+                ids: LedgerIds::default (),
+                output: LedgerOutput::default (),
+                timings: None,
+                recent: None,
+                usage: None,
+                key: None,
+                shortcuts: BTreeMap::new (),
+            }
+        }
+
+        // whether anything changed since the ledger was last saved or loaded
+        pub fn is_dirty (&self) -> bool
+        {
+            self.dirty
+        }
+
+        // every way the ledger breaks its rules, in the order of the rules
+        pub fn violations (&self) -> Vec<Violation>
+        {
+            let mut violations = Vec::new ();
+            for (i, rule) in self.rules.iter ().enumerate ()
+            {
+                let mut broken = |task: &str, message: String|
                 {
-                    continue;   // ignore it if they entered a bad name
+                    violations.push (Violation { rule: i + 1, task: String::from (task), message });
                 };
-                // if this participant is paying for this task, don't add it to their list of tasks
-                //if participant.paid_tasks.contains (task_name)
-                //{
-                //    return Ok (());
-                //}
-                // done with preparing, add stuff together
-                participant.tasks.insert (String::from (task_name));
-                participant.sum = None;
-                task.participants.insert (String::from (arg));
+                match rule
+                {
+                    Rule::MinParticipants { min } =>
+                    {
+                        for task in self.tasks.values ().filter (|t| t.participants.len () < *min)
+                        {
+                            broken (&task.name, format! ("{} has {} participants, fewer than {min}"
+                                                         , task.name, task.participants.len ()));
+                        }
+                    },
+                    Rule::MaxCost { max, except } =>
+                    {
+                        let exempt = |t: &Task| except.contains (&t.name) || !except.is_disjoint (&t.tags);
+                        for task in self.tasks.values ().filter (|t| !exempt (t) && self.cost (t) > *max)
+                        {
+                            broken (&task.name, format! ("{} costs {}, more than {max}", task.name, self.cost (task)));
+                        }
+                    },
+                    // a task that doesn't exist yet can't leave anyone out
+                    Rule::IncludeAll { task } =>
+                    {
+                        let Some (task) = self.tasks.get (task) else
+                        {
+                            continue;
+                        };
+                        let missing = self.participants.keys ()
+                            .filter (|p| !task.participants.contains (*p))
+                            .cloned ()
+                            .collect::<Vec<String>> ();
+                        if !missing.is_empty ()
+                        {
+                            broken (&task.name, format! ("{} leaves out {}", task.name, missing.join (", ")));
+                        }
+                    },
+                }
+            }
+            violations
+        }
+
+        // hands over the warnings collected by the commands run since the last
+        // call, the frontend decides how to show them
+        pub fn take_warnings (&mut self) -> Vec<String>
+        {
+            std::mem::take (&mut self.warnings)
+        }
+
+        // with a disambiguator set, names can be shortened to any prefix that
+        // is unique, and the disambiguator is asked when it isn't
+        pub fn set_disambiguator (&mut self, disambiguate: Option<Disambiguator>)
+        {
+            self.disambiguate = disambiguate;
+        }
+
+        // makes a single letter stand for a command, or stop standing for one
+        // with none
+        pub fn set_shortcut (&mut self, letter: &str, command: &str) -> PaymentResult
+        {
+            if letter.chars ().count () != 1 || letter.chars ().any (char::is_whitespace)
+            {
+                return Err (format! ("{letter} is not a single letter"));
             }
+            if command == "none"
+            {
+                self.shortcuts.remove (letter);
+                return Ok (());
+            }
+            if Self::usage (command).is_none ()
+            {
+                return Err (Self::unknown_command (command));
+            }
+            self.shortcuts.insert (String::from (letter), String::from (command));
             Ok (())
         }
+
+        // the command meant by what was typed, which can be a shortcut or any
+        // prefix of a command that only one command starts with
+        pub fn command_name (&self, typed: &str) -> Result<String, String>
+        {
+            if let Some (command) = self.shortcuts.get (typed)
+            {
+                return Ok (command.clone ());
+            }
+            let mut commands = USAGE.iter ()
+                .filter_map (|line| line.split (' ').next ())
+                .filter (|c| c.starts_with (typed))
+                .collect::<Vec<&str>> ();
+            commands.sort ();
+            commands.dedup ();
+            match commands[..]
+            {
+                _ if typed.is_empty () || commands.contains (&typed) => Ok (String::from (typed)),
+                [command] => Ok (String::from (command)),
+                [] => Ok (String::from (typed)),
+                _ => Err (format! ("{typed} is ambiguous, it could be {}", commands.join (", "))),
+            }
+        }
+
+        // with a confirmer set, amounts over the ledger's amount limit are put
+        // to it instead of needing --confirm
+        pub fn set_confirmer (&mut self, confirm: Option<Confirmer>)
+        {
+            self.confirm = confirm;
+        }
+
+        // changes a setting the same way set does, but as an override from
+        // outside rather than a change to the ledger
+        pub fn set_setting (&mut self, key: &str, value: &str) -> PaymentResult
+        {
+            self.settings.set (key, value)
+        }
+
+        // everything that depends on the current time asks this clock, so a
+        // fixed clock makes those commands repeatable
+        pub fn set_clock (&mut self, clock: Arc<dyn Clock>)
+        {
+            self.clock = LedgerClock (clock);
+        }
+
+        // seconds since the unix epoch according to the ledger's clock
+        pub fn now (&self) -> u64
+        {
+            self.clock.0.now ()
+        }
+
+        // a date given with --date. besides a date in the ledger's format it
+        // can be today, yesterday, a day of the week like friday for the last
+        // one up to today, last friday for the one before today, or 3 days ago
+        fn date_arg (&self, words: &[&str]) -> Result<String, String>
+        {
+            const WEEKDAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+            let today = clock::date (self.now ());
+            let weekday = |name: &str| WEEKDAYS.iter ().position (|d| name.len () >= 3 && d.starts_with (&name.to_lowercase ()));
+            // how many days back the last one of a day of the week was
+            let back = |day: usize| (clock::weekday (&today) + 7 - day) % 7;
+            match words
+            {
+                [] => Err (String::from ("--date must be followed by a date")),
+                ["today"] => Ok (today),
+                ["yesterday"] => Ok (clock::days_before (&today, 1)),
+                [n, "days" | "day", "ago"] => match n.parse::<u32> ()
+                {
+                    Ok (n) => Ok (clock::days_before (&today, n)),
+                    Err (_) => Err (format! ("{n} is not a number of days")),
+                },
+                ["last", day] => match weekday (day)
+                {
+                    Some (day) => Ok (clock::days_before (&today, match back (day) { 0 => 7, n => n } as u32)),
+                    None => Err (format! ("{day} is not a day of the week")),
+                },
+                [word] => match weekday (word)
+                {
+                    Some (day) => Ok (clock::days_before (&today, back (day) as u32)),
+                    None => self.settings.parse_date (word),
+                },
+                _ => Err (format! ("{} is not a date", words.join (" "))),
+            }
+        }
+
+        // takes --date and the date after it off the end of a command
+        fn split_date<'a, 'b> (&self, args: &'b [&'a str]) -> Result<(&'b [&'a str], Option<String>), String>
+        {
+            match args.iter ().position (|a| *a == "--date")
+            {
+                Some (i) => Ok ((&args[..i], Some (self.date_arg (&args[i + 1..])?))),
+                None => Ok ((args, None)),
+            }
+        }
+
+        // turns on measuring how long each phase of a command takes
+        pub fn set_timings (&mut self, on: bool)
+        {
+            self.timings = if on { Some (Vec::new ()) } else { None };
+        }
+
+        // hands over how long each phase of the commands since the last call took
+        pub fn take_timings (&mut self) -> Vec<(&'static str, Duration)>
+        {
+            match &mut self.timings
+            {
+                Some (timings) => std::mem::take (timings),
+                None => Vec::new (),
+            }
+        }
+
+        // adds time to a phase of the current command if timings are on
+        fn record (&mut self, phase: &'static str, start: Instant)
+        {
+            self.add_timing (phase, start.elapsed ());
+        }
+
+        fn add_timing (&mut self, phase: &'static str, elapsed: Duration)
+        {
+            let Some (timings) = &mut self.timings else
+            {
+                return;
+            };
+            match timings.iter_mut ().find (|t| t.0 == phase)
+            {
+                Some (t) => t.1 += elapsed,
+                None => timings.push ((phase, elapsed)),
+            }
+        }
+
+        // with a file set, every ledger loaded or saved is added to the list of
+        // recent files kept in it, which is what open picks from
+        pub fn set_recent_file (&mut self, file: Option<PathBuf>)
+        {
+            self.recent = file;
+        }
+
+        // the ledgers loaded or saved most recently, newest first
+        pub fn recent_files (&self) -> Vec<String>
+        {
+            self.recent.as_ref ()
+                .and_then (|f| std::fs::read_to_string (f).ok ())
+                .map (|text| text.lines ().filter (|l| !l.is_empty ()).map (String::from).collect ())
+                .unwrap_or_default ()
+        }
+
+        // puts a file at the top of the recent files list
+        fn remember (&mut self, filename: &str)
+        {
+            const KEEP: usize = 10;
+            let Some (recent) = self.recent.clone () else
+            {
+                return;
+            };
+            let path = std::fs::canonicalize (filename).unwrap_or_else (|_| PathBuf::from (filename));
+            let path = path.display ().to_string ();
+            let mut files = self.recent_files ();
+            files.retain (|f| *f != path);
+            files.insert (0, path);
+            files.truncate (KEEP);
+            let written = recent.parent ()
+                .map_or (Ok (()), std::fs::create_dir_all)
+                .and_then (|_| std::fs::write (&recent, files.join ("\n") + "\n"));
+            if written.is_err ()
+            {
+                self.warnings.push (format! ("couldn't update the list of recent files in {}", recent.display ()));
+            }
+        }
+
+        // with capturing on, what commands print is kept for take_output
+        // instead of going to stdout
+        pub fn capture_output (&mut self, on: bool)
+        {
+            self.output = LedgerOutput (on.then (|| Arc::new (Mutex::new (String::new ()))));
+        }
+
+        // hands over what commands printed since the last call while capturing
+        pub fn take_output (&mut self) -> String
+        {
+            match &self.output.0
+            {
+                Some (buffer) => std::mem::take (&mut *buffer.lock ().unwrap_or_else (|e| e.into_inner ())),
+                None => String::new (),
+            }
+        }
+
+        // what came of the command sent with an idempotency key, if it's
+        // one of the latest
+        pub fn outcome (&self, key: &str) -> Option<&Outcome>
+        {
+            self.outcomes.iter ().find (|o| o.key == key)
+        }
+
+        // keeps what came of a command sent with an idempotency key. only the
+        // latest are kept, a retry comes long before that many other commands
+        pub fn record_outcome (&mut self, outcome: Outcome)
+        {
+            const KEEP: usize = 1000;
+            self.outcomes.retain (|o| o.key != outcome.key);
+            self.outcomes.push (outcome);
+            let over = self.outcomes.len ().saturating_sub (KEEP);
+            self.outcomes.drain (..over);
+            self.dirty = true;
+        }
+
+        // with a file set, every command run is counted in it for stats --usage
+        pub fn set_usage_file (&mut self, file: Option<PathBuf>)
+        {
+            self.usage = file;
+        }
+
+        // with a file set, report --sign and invoice --sign sign with the key
+        // kept in it
+        pub fn set_key_file (&mut self, file: Option<PathBuf>)
+        {
+            self.key = file;
+        }
+
+        // how many times each command was run, from the usage file
+        fn usage_counts (&self) -> BTreeMap<String, u64>
+        {
+            self.usage.as_ref ()
+                .and_then (|f| std::fs::read_to_string (f).ok ())
+                .map (|text| text.lines ()
+                      .filter_map (|l| l.split_once (' '))
+                      .filter_map (|(command, count)| Some ((String::from (command), count.parse ().ok ()?)))
+                      .collect ())
+                .unwrap_or_default ()
+        }
+
+        fn count_usage (&mut self, command: &str)
+        {
+            let Some (usage) = self.usage.clone () else
+            {
+                return;
+            };
+            let mut counts = self.usage_counts ();
+            *counts.entry (String::from (command)).or_insert (0) += 1;
+            let text = counts.iter ().map (|(command, count)| format! ("{command} {count}\n")).collect::<String> ();
+            let written = usage.parent ()
+                .map_or (Ok (()), std::fs::create_dir_all)
+                .and_then (|_| std::fs::write (&usage, text));
+            if written.is_err ()
+            {
+                self.warnings.push (format! ("couldn't count the command in {}", usage.display ()));
+            }
+        }
+
+        // everything random, like perturbing amounts, draws from this source
+        pub fn set_id_source (&mut self, ids: Arc<dyn IdSource>)
+        {
+            self.ids = LedgerIds (ids);
+        }
+
+        // turns what the user typed into the name of a participant or task. names
+        // that don't match anything are handed back as they are so commands can
+        // report them or create them as usual
+        fn resolve (&self, name: &str) -> Result<String, String>
+        {
+            let Some (disambiguate) = &self.disambiguate else
+            {
+                return Ok (String::from (name));
+            };
+            if name.is_empty () || self.participants.contains_key (name) || self.tasks.contains_key (name)
+                || self.groups.contains_key (name)
+            {
+                return Ok (String::from (name));
+            }
+            let candidates = self.participants.keys ()
+                .chain (self.tasks.keys ())
+                .filter (|n| n.starts_with (name))
+                .cloned ()
+                .collect::<Vec<String>> ();
+            match candidates.len ()
+            {
+                0 => Ok (String::from (name)),
+                1 => Ok (candidates[0].clone ()),
+                _ => match (disambiguate.0) (name, &candidates)
+                {
+                    Some (choice) if candidates.contains (&choice) => Ok (choice),
+                    _ => Err (format! ("{name} is ambiguous, it could be {}", candidates.join (", "))),
+                },
+            }
+        }
+
+        pub fn command (&mut self, com: &str) -> PaymentResult
+        {
+            let start = Instant::now ();
+            let parts = Regex::new (r"\s+")
+                .unwrap ()
+                .split (com.trim ())
+                .collect::<Vec<&str>> ();
+            self.record ("parse", start);
+            let start = Instant::now ();
+            let result = self.run (&parts);
+            self.record ("execute", start);
+            result
+        }
+
+        // runs every command on a line like add dave; part rent dave; print
+        // one after the other, with what came of each. a command that fails
+        // stops the ones after it, since they likely depend on it
+        pub fn command_line (&mut self, line: &str) -> Vec<(String, PaymentResult)>
+        {
+            let commands = Self::split_commands (line);
+            if commands.len () == 1
+            {
+                return vec! [(String::from (line.trim ()), self.command (line))];
+            }
+            let mut results = Vec::new ();
+            for com in commands.into_iter ().filter (|c| !c.is_empty ())
+            {
+                let result = self.command (&format! ("{com}\n"));
+                let failed = result.is_err ();
+                results.push ((com, result));
+                if failed
+                {
+                    break;
+                }
+            }
+            results
+        }
+
+        // the commands on a line, split at semicolons that aren't quoted
+        fn split_commands (line: &str) -> Vec<String>
+        {
+            let mut commands = vec! [String::new ()];
+            let mut quoted = false;
+            for c in line.chars ()
+            {
+                match c
+                {
+                    ';' if !quoted => commands.push (String::new ()),
+                    c =>
+                    {
+                        quoted ^= c == '"';
+                        commands.last_mut ().unwrap ().push (c);
+                    },
+                }
+            }
+            commands.iter ().map (|c| String::from (c.trim ())).collect ()
+        }
+
+        fn run (&mut self, parts: &[&str]) -> PaymentResult
+        {
+            let command = self.command_name (parts[0])?;
+            let parts = std::iter::once (command.as_str ()).chain (parts[1..].iter ().copied ()).collect::<Vec<&str>> ();
+            let end = &parts[1..];
+            if parts.first () == Some (&"in")
+            {
+                return self.in_subledger (end);
+            }
+            let changes = matches! (parts.first (), Some (&"add" | &"part" | &"pay" | &"rename" | &"remove" | &"import"
+                                                         | &"limit" | &"compact" | &"double-entry" | &"edit"
+                                                         | &"rate" | &"set" | &"item" | &"rule" | &"payment" | &"tag" | &"group" | &"uneven"
+                                                         | &"recur" | &"tick" | &"archive"
+                                                         | &"note" | &"interest" | &"field" | &"refund" | &"discount"
+                                                         | &"invoice" | &"budget" | &"unpart"
+                                                         | &"convert" | &"prune"))
+                || parts.first () == Some (&"settle") && end.contains (&"--apply")
+                || parts.first () == Some (&"cashbox") && !end.is_empty ()
+                || parts.first () == Some (&"dues") && !end.is_empty () && end[0] != "remind";
+            // only what a change breaks is reported, not everything broken
+            // before it
+            let before = if changes && !self.rules.is_empty ()
+            {
+                self.violations ()
+            }
+            else
+            {
+                Vec::new ()
+            };
+            let over_before = if changes { self.over_budget () } else { Vec::new () };
+            match parts.first ()
+            {
+                Some (&"add")   => self.add (end)?,
+                Some (&"part")  => self.part (end)?,
+                Some (&"unpart")=> self.unpart (end)?,
+                Some (&"item")  => self.item (end)?,
+                Some (&"tag")   => self.tag (end)?,
+                Some (&"group") => self.group (end)?,
+                Some (&"groups")=> self.print_groups (),
+                Some (&"recur") => self.recur (end)?,
+                Some (&"tick")  => self.tick ()?,
+                Some (&"archive")=> self.archive (end)?,
+                Some (&"note")  => self.note (end)?,
+                Some (&"refund")=> self.refund (end)?,
+                Some (&"discount")=> self.discount (end)?,
+                Some (&"field") => self.field (end)?,
+                Some (&"cashbox")=> self.cashbox (end)?,
+                Some (&"dues")  => self.dues (end)?,
+                Some (&"budget")=> self.budget (end)?,
+                Some (&"invoice")=> self.invoice (end)?,
+                Some (&"notify")=> self.notify (end)?,
+                Some (&"verify")=> self.verify (end)?,
+                Some (&"fields")=> self.print_fields (),
+                Some (&"recurring")=> self.print_recurring (),
+                Some (&"pay")   => self.pay (end)?,
+                Some (&"payment")=> self.payment (end)?,
+                Some (&"uneven")=> self.uneven (end)?,
+                Some (&"print") => self.print (end)?,
+                Some (&"save")  => self.save (end)?,
+                Some (&"load")  => self.load (end)?,
+                Some (&"open")  => self.open (end)?,
+                Some (&"rename")=> self.rename (end)?,
+                Some (&"remove")=> self.remove (end)?,
+                Some (&"settle")=> self.settle (end)?,
+                Some (&"explain")=> self.explain (end)?,
+                Some (&"owed-by")=> self.owed_by (end)?,
+                Some (&"owed-to")=> self.owed_to (end)?,
+                Some (&"export")=> self.export (end)?,
+                Some (&"import")=> self.import (end)?,
+                Some (&"stats") => self.stats (end)?,
+                Some (&"limit") => self.limit (end)?,
+                Some (&"interest")=> self.set_interest (end)?,
+                Some (&"compact")=> self.compact (),
+                Some (&"prune") => self.prune (end)?,
+                Some (&"double-entry")=> self.set_double_entry (end)?,
+                Some (&"edit")  => self.edit (end)?,
+                Some (&"journal")=> self.print_journal (),
+                Some (&"rate")  => self.rate (end)?,
+                Some (&"convert")=> self.convert (end)?,
+                Some (&"set")   => self.set (end)?,
+                Some (&"rule")  => self.rule (end)?,
+                Some (&"rules") => self.print_rules (),
+                Some (&"report")=> self.report (end)?,
+                Some (&"paths") => self.print_paths (),
+                Some (&"help")  => self.help (end)?,
+                Some (&"parse") => self.parse_preview (end)?,
+                Some (&a)       => return Err (Self::unknown_command (a)),
+                None            => return Err (String::from ("syntax error")),
+            }
+            self.count_usage (parts[0]);
+            if changes
+            {
+                self.dirty = true;
+                for violation in self.violations ()
+                {
+                    if !before.iter ().any (|v| v.message == violation.message)
+                    {
+                        self.warnings.push (format! ("breaks rule {}: {}", violation.rule, violation.message));
+                    }
+                }
+                // the same for budgets, unless nothing changed for whoever is over
+                for over in self.over_budget ()
+                {
+                    if !over_before.contains (&over)
+                    {
+                        self.warn_over_budget (&over);
+                    }
+                }
+            }
+            Ok (())
+        }
+
+        // the usage lines of a command, or of every command without one
+        fn help (&self, args: &[&str]) -> PaymentResult
+        {
+            match args
+            {
+                [] => USAGE.iter ().for_each (|line| say! (self, "{line}")),
+                [command] => match Self::usage (command)
+                {
+                    Some (usage) => say! (self, "{usage}"),
+                    None => return Err (Self::unknown_command (command)),
+                },
+                _ => return Err (String::from ("Too many arguments")),
+            }
+            Ok (())
+        }
+
+        // how a command is called, every way of calling it on its own line
+        fn usage (command: &str) -> Option<String>
+        {
+            let lines = USAGE.iter ()
+                .filter (|line| line.split (' ').next () == Some (command))
+                .copied ()
+                .collect::<Vec<&str>> ();
+            (!lines.is_empty ()).then (|| lines.join ("\n       "))
+        }
+
+        // how a command would be read, without running it: which command it
+        // is and what each word after it was taken as
+        fn parse_preview (&self, args: &[&str]) -> PaymentResult
+        {
+            let Some ((typed, args)) = args.split_first ().filter (|(t, _)| !t.is_empty ()) else
+            {
+                return Err (String::from ("Not enough arguments"));
+            };
+            let command = self.command_name (typed)?;
+            let Some (usage) = Self::usage (&command) else
+            {
+                return Err (Self::unknown_command (typed));
+            };
+            match *typed == command
+            {
+                true => say! (self, "command: {command}"),
+                false => say! (self, "command: {command} (from {typed})"),
+            }
+            say! (self, "usage: {usage}");
+            // the plain words in the usage, like none or all
+            let keywords = usage.split_whitespace ()
+                .skip (1)
+                .flat_map (|w| w.split (|c| "|[]".contains (c)))
+                .map (|w| w.trim_end_matches ("..."))
+                .filter (|w| !w.is_empty () && !w.starts_with ('-')
+                         && w.chars ().all (|c| c.is_ascii_lowercase () || c == '-'))
+                .collect::<BTreeSet<&str>> ();
+            let mut quoted = false;
+            for arg in args
+            {
+                let what = if quoted || arg.starts_with ('"')
+                {
+                    let start = !quoted;
+                    quoted = !(arg.ends_with ('"') && (arg.len () > 1 || !start));
+                    match (start, quoted)
+                    {
+                        (true, false) => String::from ("quoted text"),
+                        (true, true) => String::from ("start of quoted text"),
+                        (false, true) => String::from ("part of quoted text"),
+                        (false, false) => String::from ("end of quoted text"),
+                    }
+                }
+                else if arg.starts_with ("--") || arg.starts_with ('-') && arg.chars ().nth (1).is_some_and (|c| c.is_alphabetic ())
+                {
+                    String::from ("flag")
+                }
+                else if keywords.contains (arg)
+                {
+                    String::from ("keyword")
+                }
+                else
+                {
+                    self.parse_word (arg)
+                };
+                say! (self, "  [{arg}] {what}");
+            }
+            if quoted
+            {
+                say! (self, "the quote is never closed");
+            }
+            Ok (())
+        }
+
+        // what a word in a command looks like, going by what's in the ledger
+        fn parse_word (&self, word: &str) -> String
+        {
+            let name = |name: &str| if self.participants.contains_key (name)
+            {
+                Some ("participant")
+            }
+            else if self.tasks.contains_key (name)
+            {
+                Some ("task")
+            }
+            else if self.groups.contains_key (name)
+            {
+                Some ("group")
+            }
+            else
+            {
+                None
+            };
+            if let Some (kind) = name (word)
+            {
+                return String::from (kind);
+            }
+            if let Ok ((n, given)) = Given::parse (word)
+            {
+                match (name (n), given)
+                {
+                    (Some (kind), Given::Weight (w)) => return format! ("{kind} {n} with weight {w}"),
+                    (Some (kind), Given::Amount (a)) => return format! ("{kind} {n} owing {a}"),
+                    (Some (kind), Given::Percent (p)) => return format! ("{kind} {n} owing {p}%"),
+                    _ => (),
+                }
+            }
+            // before amounts, since 2024-05-31 is arithmetic too
+            if let Ok (date) = self.date_arg (&[word])
+            {
+                return format! ("date {date}");
+            }
+            if let Ok (amount) = Money::input (word)
+            {
+                return format! ("amount {amount}");
+            }
+            match self.currencies.task_currency (word)
+            {
+                Ok (_) => return format! ("currency {}", word.to_ascii_uppercase ()),
+                Err (_) if Currencies::code (word).is_ok () && word.chars ().all (|c| c.is_ascii_uppercase ()) =>
+                {
+                    return format! ("currency {word}, which has no exchange rate");
+                },
+                Err (_) => (),
+            }
+            String::from ("text, or a name not in the ledger")
+        }
+
+        // says a command doesn't exist, suggesting the one that was probably meant
+        fn unknown_command (typed: &str) -> String
+        {
+            let commands = USAGE.iter ().filter_map (|line| line.split (' ').next ());
+            match closest (typed, commands).and_then (|c| Some ((c, Self::usage (c)?)))
+            {
+                Some ((command, usage)) => format! ("{typed} is not recognized as a command, did you mean {command}? usage: {usage}"),
+                None => format! ("{typed} is not recognized as a command"),
+            }
+        }
+
+        // runs a command against a sub-ledger, creating it if it doesn't exist
+        // yet. sub-ledgers can have their own sub-ledgers, so `in trip in food
+        // print` works too
+        fn in_subledger (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (name, com) = match args
+            {
+                [name, com @ ..] if !name.is_empty () && !com.is_empty () => (*name, com),
+                _ => return Err (String::from ("Not enough arguments")),
+            };
+            let sub = self.subledgers.entry (String::from (name)).or_default ();
+            sub.disambiguate = self.disambiguate.clone ();
+            sub.confirm = self.confirm.clone ();
+            sub.clock = self.clock.clone ();
+            sub.ids = self.ids.clone ();
+            sub.output = self.output.clone ();
+            sub.timings = self.timings.as_ref ().map (|_| Vec::new ());
+            sub.recent = self.recent.clone ();
+            sub.usage = self.usage.clone ();
+            sub.key = self.key.clone ();
+            sub.shortcuts = self.shortcuts.clone ();
+            let result = sub.command (&format! ("{}\n", com.join (" ")));
+            let timings = sub.take_timings ();
+            self.warnings.append (&mut sub.warnings);
+            // the sub-ledger is saved as part of this one
+            if sub.dirty
+            {
+                sub.dirty = false;
+                self.dirty = true;
+            }
+            // parsing and running the sub-ledger's command is already part of
+            // running this one
+            for (phase, elapsed) in timings
+            {
+                if phase != "parse" && phase != "execute"
+                {
+                    self.add_timing (phase, elapsed);
+                }
+            }
+            result
+        }
+
+        // balances of everyone in this ledger and all of its sub-ledgers added up
+        fn umbrella_balances (&self) -> BTreeMap<String, Money>
+        {
+            let mut balances = BTreeMap::new ();
+            for part in self.participants.values ()
+            {
+                *balances.entry (part.name.clone ()).or_insert (Money::ZERO) += part.sum.unwrap ();
+            }
+            for sub in self.subledgers.values ()
+            {
+                for (name, sum) in sub.umbrella_balances ()
+                {
+                    *balances.entry (name).or_insert (Money::ZERO) += sum;
+                }
+            }
+            balances
+        }
+
+        fn load (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (merge, filename) = match args
+            {
+                [f] => (false, *f),
+                ["--merge", f] => (true, *f),
+                [] => return Err (String::from ("Not enough arguments")),
+                _ => return Err (String::from ("Too many arguments")),
+            };
+            let file = match File::open (filename)
+            {
+                Ok (f) => f,
+                Err (_) => return Err (format! ("Unable to open file {}", filename)),
+            };
+            let payment: Payment = match serde_json::from_reader (BufReader::new (file))
+            {
+                Ok (pay) => pay,
+                Err (e) => return Err (format! ("Error deserializing file:\n{}", e)),
+            };
+            self.load_ledger (payment, filename, merge)?;
+            self.remember (filename);
+            Ok (())
+        }
+
+        // makes a ledger read from somewhere this one, or merges it in
+        fn load_ledger (&mut self, payment: Payment, filename: &str, merge: bool) -> PaymentResult
+        {
+            if merge
+            {
+                return self.merge (payment);
+            }
+            if self.dirty
+            {
+                self.warnings.push (format! ("unsaved changes were discarded by loading {filename}"));
+            }
+            self.participants = payment.participants;
+            self.tasks = payment.tasks;
+            self.transfers = payment.transfers;
+            self.settled = payment.settled;
+            self.groups = payment.groups;
+            self.recurring = payment.recurring;
+            self.archived = payment.archived;
+            self.fields = payment.fields;
+            self.cashbox = payment.cashbox;
+            self.dues = payment.dues;
+            self.invoicing = payment.invoicing;
+            self.subledgers = payment.subledgers;
+            self.limits = payment.limits;
+            self.currencies = payment.currencies;
+            self.settings = payment.settings;
+            self.rules = payment.rules;
+            self.double_entry = payment.double_entry;
+            self.interest = payment.interest;
+            self.outcomes = payment.outcomes;
+            self.dirty = false;
+            Ok (())
+        }
+
+        // loads one of the recent files, 1 being the newest
+        fn open (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let position = match args
+            {
+                ["last"] => 1,
+                ["recent", n] => match n.parse::<usize> ()
+                {
+                    Ok (n) if n >= 1 => n,
+                    _ => return Err (format! ("{n} is not a position in the recent files list")),
+                },
+                [] | ["recent"] => return Err (String::from ("Not enough arguments")),
+                [a, ..] => return Err (format! ("Can't open {a}, use open last or open recent N")),
+            };
+            let files = self.recent_files ();
+            match files.get (position - 1)
+            {
+                Some (file) => self.load (&[file]),
+                None if files.is_empty () => Err (String::from ("No ledgers have been loaded or saved yet")),
+                None => Err (format! ("There are only {} recent files", files.len ())),
+            }
+        }
+
+        // combines another ledger into this one. participants in both ledgers
+        // keep the tasks from both, tasks in both ledgers have to agree on
+        // everything. nothing is changed unless the whole merge can be done
+        fn merge (&mut self, other: Payment) -> PaymentResult
+        {
+            self.check_merge (&other)?;
+            self.apply_merge (other);
+            self.dirty = true;
+            Ok (())
+        }
+
+        // what kind of thing each name stands for. participants, tasks,
+        // archived tasks and groups share one set of names
+        fn name_kinds (&self) -> BTreeMap<&str, &'static str>
+        {
+            let mut kinds = BTreeMap::new ();
+            kinds.extend (self.participants.keys ().map (|n| (n.as_str (), "participant")));
+            kinds.extend (self.tasks.keys ().map (|n| (n.as_str (), "task")));
+            kinds.extend (self.archived.keys ().map (|n| (n.as_str (), "archived task")));
+            kinds.extend (self.groups.keys ().map (|n| (n.as_str (), "group")));
+            // a recurring task is named after the task it was made from
+            for name in self.recurring.keys ()
+            {
+                kinds.entry (name.as_str ()).or_insert ("task");
+            }
+            kinds
+        }
+
+        // whether two copies of a task would split the same way
+        fn same_task (ours: &Task, task: &Task) -> bool
+        {
+            ours.owner == task.owner && ours.cost == task.cost && ours.currency == task.currency
+                && ours.participants == task.participants && ours.weights == task.weights
+                && ours.amounts == task.amounts && ours.percents == task.percents && ours.items == task.items
+                && ours.payers == task.payers && ours.tip == task.tip && ours.refunds == task.refunds
+                && ours.discount == task.discount
+                && (ours.date.is_none () || task.date.is_none () || ours.date == task.date)
+        }
+
+        // how many entries two lists start with in common. both copies of a
+        // ledger have whatever was recorded before they went their own ways,
+        // and everything after that was recorded in only one of them
+        fn shared_start<T: PartialEq> (ours: &[T], theirs: &[T]) -> usize
+        {
+            ours.iter ().zip (theirs).take_while (|(a, b)| a == b).count ()
+        }
+
+        // fails on everything the file and this ledger disagree about, before
+        // anything is changed
+        fn check_merge (&self, other: &Payment) -> PaymentResult
+        {
+            let kinds = self.name_kinds ();
+            for (name, kind) in other.name_kinds ()
+            {
+                match kinds.get (name)
+                {
+                    Some (ours) if *ours != kind => return Err (format! ("{name} is a {kind} in the file but a {ours} here")),
+                    _ => (),
+                }
+            }
+            for (name, task) in &other.tasks
+            {
+                if self.tasks.get (name).is_some_and (|ours| !Self::same_task (ours, task))
+                {
+                    return Err (format! ("Task {name} differs between the file and the current ledger"));
+                }
+            }
+            for (name, task) in &other.archived
+            {
+                if self.archived.get (name).is_some_and (|ours| !Self::same_task (ours, task))
+                {
+                    return Err (format! ("Archived task {name} differs between the file and the current ledger"));
+                }
+            }
+            for (name, recurring) in &other.recurring
+            {
+                if self.recurring.get (name).is_some_and (|ours| ours.every != recurring.every || ours.start != recurring.start
+                                                          || !Self::same_task (&ours.task, &recurring.task))
+                {
+                    return Err (format! ("{name} recurs differently in the file and the current ledger"));
+                }
+            }
+            for (name, part) in &other.participants
+            {
+                let Some (ours) = self.participants.get (name) else
+                {
+                    continue;
+                };
+                let differs = |a: &Option<String>, b: &Option<String>| a.is_some () && b.is_some () && a != b;
+                if differs (&ours.email, &part.email) || differs (&ours.phone, &part.phone)
+                    || ours.budget.is_some () && part.budget.is_some () && ours.budget != part.budget
+                    || ours.weight.is_some () && part.weight.is_some () && ours.weight != part.weight
+                {
+                    return Err (format! ("{name}'s details differ between the file and the current ledger"));
+                }
+            }
+            if other.currencies.base.is_some () && self.currencies.base.is_some ()
+                && other.currencies.base != self.currencies.base
+            {
+                return Err (String::from ("The file and the current ledger have different base currencies"));
+            }
+            for (code, rate) in &other.currencies.rates
+            {
+                if self.currencies.rates.get (code).is_some_and (|r| r != rate)
+                {
+                    return Err (format! ("The exchange rate for {code} differs between the file and the current ledger"));
+                }
+            }
+            // the rest either agrees, or is only set in one of the two
+            fn clash<T: PartialEq> (ours: &Option<T>, theirs: &Option<T>) -> bool
+            {
+                ours.is_some () && theirs.is_some () && ours != theirs
+            }
+            let clashes = [
+                (clash (&self.settled, &other.settled), "were settled at different times"),
+                (clash (&self.dues, &other.dues), "have different dues or dues payments"),
+                (clash (&self.interest, &other.interest), "charge different interest"),
+                (clash (&self.limits.participants, &other.limits.participants)
+                 || clash (&self.limits.tasks, &other.limits.tasks)
+                 || clash (&self.limits.amount, &other.limits.amount), "have different limits"),
+                (clash (&self.settings.currency, &other.settings.currency)
+                 || clash (&self.settings.rounding, &other.settings.rounding)
+                 || clash (&self.settings.dates, &other.settings.dates), "have different settings"),
+                (self.double_entry != other.double_entry, "don't both use double entry"),
+                (clash (&self.invoicing.name, &other.invoicing.name)
+                 || clash (&self.invoicing.address, &other.invoicing.address)
+                 || clash (&self.invoicing.instructions, &other.invoicing.instructions), "have different invoice details"),
+                (matches! ((&self.cashbox.counted, &other.cashbox.counted), (Some (a), Some (b)) if a.0 == b.0 && a.1 != b.1)
+                 , "counted the cash box differently on the same day"),
+            ];
+            if let Some ((_, what)) = clashes.iter ().find (|c| c.0)
+            {
+                return Err (format! ("The file and the current ledger {what}"));
+            }
+            for (name, sub) in &other.subledgers
+            {
+                if let Some (ours) = self.subledgers.get (name)
+                {
+                    ours.check_merge (sub)?;
+                }
+            }
+            let new_participants = other.participants.keys ()
+                .filter (|n| !self.participants.contains_key (*n))
+                .count ();
+            let new_tasks = other.tasks.keys ()
+                .filter (|n| !self.tasks.contains_key (*n))
+                .count ();
+            self.check_limits (new_participants, new_tasks)
+        }
+
+        fn apply_merge (&mut self, other: Payment)
+        {
+            for (name, part) in other.participants
+            {
+                match self.participants.get_mut (&name)
+                {
+                    Some (ours) =>
+                    {
+                        ours.tasks.extend (part.tasks);
+                        ours.paid_tasks.extend (part.paid_tasks);
+                        ours.email = ours.email.take ().or (part.email);
+                        ours.phone = ours.phone.take ().or (part.phone);
+                        ours.budget = ours.budget.or (part.budget);
+                        ours.weight = ours.weight.or (part.weight);
+                        ours.sum = None;
+                    },
+                    None =>
+                    {
+                        self.participants.insert (name, part);
+                    },
+                }
+            }
+            for (name, task) in other.tasks
+            {
+                match self.tasks.get_mut (&name)
+                {
+                    Some (ours) =>
+                    {
+                        ours.tags.extend (task.tags);
+                        ours.excluded.extend (task.excluded.into_iter ().filter (|e| !ours.participants.contains (e)));
+                        ours.date = ours.date.take ().or (task.date);
+                        ours.note = ours.note.take ().or (task.note);
+                        ours.day = ours.day.or (task.day);
+                        for (field, value) in task.fields
+                        {
+                            ours.fields.entry (field).or_insert (value);
+                        }
+                    },
+                    None =>
+                    {
+                        self.tasks.insert (name, task);
+                    },
+                }
+            }
+            if self.currencies.base.is_none ()
+            {
+                self.currencies.base = other.currencies.base;
+            }
+            for (code, rate) in other.currencies.rates
+            {
+                self.currencies.rates.entry (code).or_insert (rate);
+            }
+            // payments are told apart by their ids. ones recorded before
+            // payments had ids are the same if both copies start with them
+            let shared = Self::shared_start (&self.transfers, &other.transfers);
+            let before = self.transfers.len ();
+            let ids = self.transfers.iter ().filter_map (|t| t.id.clone ()).collect::<BTreeSet<String>> ();
+            self.transfers.extend (other.transfers.into_iter ().skip (shared)
+                                   .filter (|t| t.id.as_ref ().is_none_or (|id| !ids.contains (id))));
+            if self.settled.is_none ()
+            {
+                // the payments the settlement came after are counted where
+                // they ended up
+                self.settled = other.settled.map (|mut settled|
+                {
+                    if settled.transfers > shared
+                    {
+                        settled.transfers += before - shared;
+                    }
+                    settled
+                });
+            }
+            for (name, members) in other.groups
+            {
+                self.groups.entry (name).or_default ().extend (members);
+            }
+            for (name, recurring) in other.recurring
+            {
+                let ours = self.recurring.entry (name).or_insert_with (|| recurring.clone ());
+                ours.count = ours.count.max (recurring.count);
+            }
+            for (name, task) in other.archived
+            {
+                self.archived.entry (name).or_insert (task);
+            }
+            self.fields.extend (other.fields);
+            let shared = Self::shared_start (&self.cashbox.entries, &other.cashbox.entries);
+            self.cashbox.entries.extend (other.cashbox.entries.into_iter ().skip (shared));
+            // the latest count is what's in the box now
+            if other.cashbox.counted.as_ref ().is_some_and (|c| self.cashbox.counted.as_ref ().is_none_or (|ours| c.0 > ours.0))
+            {
+                self.cashbox.counted = other.cashbox.counted;
+            }
+            self.dues = self.dues.take ().or (other.dues);
+            self.interest = self.interest.or (other.interest);
+            self.limits.participants = self.limits.participants.or (other.limits.participants);
+            self.limits.tasks = self.limits.tasks.or (other.limits.tasks);
+            self.limits.amount = self.limits.amount.or (other.limits.amount);
+            self.settings.currency = self.settings.currency.take ().or (other.settings.currency);
+            self.settings.rounding = self.settings.rounding.or (other.settings.rounding);
+            self.settings.strict |= other.settings.strict;
+            self.settings.dates = self.settings.dates.or (other.settings.dates);
+            self.invoicing.name = self.invoicing.name.take ().or (other.invoicing.name);
+            self.invoicing.address = self.invoicing.address.take ().or (other.invoicing.address);
+            self.invoicing.instructions = self.invoicing.instructions.take ().or (other.invoicing.instructions);
+            // so no invoice number is given out twice
+            self.invoicing.issued = self.invoicing.issued.max (other.invoicing.issued);
+            for rule in other.rules
+            {
+                if !self.rules.contains (&rule)
+                {
+                    self.rules.push (rule);
+                }
+            }
+            for outcome in other.outcomes
+            {
+                if self.outcome (&outcome.key).is_none ()
+                {
+                    self.record_outcome (outcome);
+                }
+            }
+            for (name, sub) in other.subledgers
+            {
+                self.subledgers.entry (name).or_default ().apply_merge (sub);
+            }
+        }
+
+        fn save (&mut self, args: &[&str]) -> PaymentResult
+        {
+            self.calculate ();
+            match args.first ()
+            {
+                Some (&s) =>
+                {
+                    let start = Instant::now ();
+                    self.save_file (s)?;
+                    self.record ("save", start);
+                    self.remember (s);
+                    self.dirty = false;
+                    Ok (())
+                },
+                None => self.save_string (),
+            }
+        }
+
+        fn save_file (&self, filename: &str) -> PaymentResult
+        {
+            let file = match File::create (filename)
+            {
+                Ok (f) => f,
+                Err (_) => return Err (format! ("Unable to open file {}", filename)),
+            };
+            let write = BufWriter::new (file);
+            match serde_json::to_writer_pretty (write, &self)
+            {
+                Ok (_) => Ok (()),
+                Err (e) => Err (format! ("Error serializing the object:\n{}", e)),
+            }
+        }
+        
+        fn save_string (&self) -> PaymentResult
+        {
+            let json = match serde_json::to_string_pretty (&self)
+            {
+                Ok (val) => val,
+                Err (e) => return Err (format! ("Something went wrong serializing the object:\n{}", e)),
+            };
+            say! (self, "{}", json);
+            Ok (())
+        }
+
+        fn export (&mut self, args: &[&str]) -> PaymentResult
+        {
+            match args
+            {
+                ["ledgerlog", filename] => return self.export_ledgerlog (filename),
+                ["ledgerlog"] => return Err (String::from ("Not enough arguments")),
+                ["arrow", dir] => return self.export_arrow (dir),
+                ["arrow"] => return Err (String::from ("Not enough arguments")),
+                ["schema", file] => return Self::export_schema (file),
+                ["schema"] => return Err (String::from ("Not enough arguments")),
+                ["bundle", file] => return self.export_bundle (file),
+                ["bundle"] => return Err (String::from ("Not enough arguments")),
+                _ => (),
+            }
+            let mut anonymize = false;
+            let mut perturb = false;
+            let mut filename = None;
+            for &arg in args
+            {
+                match arg
+                {
+                    "--anonymize" => anonymize = true,
+                    "--perturb" => perturb = true,
+                    f if filename.is_none () => filename = Some (f),
+                    _ => return Err (String::from ("Too many arguments")),
+                }
+            }
+            if perturb && !anonymize
+            {
+                return Err (String::from ("--perturb can only be used with --anonymize"));
+            }
+            let mut export = if anonymize
+            {
+                self.anonymized (perturb)
+            }
+            else
+            {
+                self.clone ()
+            };
+            export.calculate ();
+            match filename
+            {
+                Some (f) => export.save_file (f),
+                None => export.save_string (),
+            }
+        }
+
+        // writes every economic event oldest first, one line per entry: a
+        // credit to each payer of a task and a debit of each participant's
+        // share, what every refund gave back and took off, and both sides of
+        // every direct payment. undated entries come first
+        fn export_ledgerlog (&self, filename: &str) -> PaymentResult
+        {
+            // the ledger's own fields get a column each, filled in on task rows
+            let mut log = String::from ("date,event,task,participant,debit,credit,memo");
+            for field in &self.fields
+            {
+                log.push_str (&format! (",{}", Self::csv_field (field)));
+            }
+            log.push ('\n');
+            let blank = ",".repeat (self.fields.len ());
+            let mut rows = Vec::new ();
+            let amount = |m: Money| if m == Money::ZERO { String::new () } else { m.to_string () };
+            let mut row = |date: Option<&str>, event: &str, task: &str, name: &str, (debit, credit): (Money, Money), memo: Option<&str>, fields: &str|
+            {
+                let (debit, credit) = (amount (debit), amount (credit));
+                rows.push ((String::from (date.unwrap_or ("")), format! ("{},{event},{},{},{debit},{credit},{}{fields}\n"
+                                                                        , date.unwrap_or ("")
+                                                                        , Self::csv_field (task)
+                                                                        , Self::csv_field (name)
+                                                                        , Self::csv_field (memo.unwrap_or ("")))));
+            };
+            for task in self.tasks.values ()
+            {
+                let fields = self.fields.iter ()
+                    .map (|f| format! (",{}", Self::csv_field (task.fields.get (f).map_or ("", String::as_str))))
+                    .collect::<String> ();
+                // the task as it was before anything was refunded, then each
+                // refund as the difference it made on the day it was given
+                let mut before = task.clone ();
+                before.refunds.clear ();
+                let mut paid = self.paid (&before);
+                let mut shares = self.charged_shares (&before);
+                for (name, amount) in &paid
+                {
+                    row (task.date.as_deref (), "paid", &task.name, name, (Money::ZERO, *amount), task.note.as_deref (), &fields);
+                }
+                for (name, share) in &shares
+                {
+                    row (task.date.as_deref (), "share", &task.name, name, (*share, Money::ZERO), None, &blank);
+                }
+                for refund in &task.refunds
+                {
+                    before.refunds.push (refund.clone ());
+                    let (paid_after, shares_after) = (self.paid (&before), self.charged_shares (&before));
+                    for ((name, after), (_, was)) in paid_after.iter ().zip (&paid)
+                    {
+                        if after != was
+                        {
+                            row (Some (&refund.date), "refund", &task.name, name, (*was - *after, Money::ZERO), refund.memo.as_deref (), &blank);
+                        }
+                    }
+                    for ((name, after), (_, was)) in shares_after.iter ().zip (&shares)
+                    {
+                        if after != was
+                        {
+                            row (Some (&refund.date), "refund", &task.name, name, (Money::ZERO, *was - *after), refund.memo.as_deref (), &blank);
+                        }
+                    }
+                    (paid, shares) = (paid_after, shares_after);
+                }
+            }
+            // a payment credits whoever made it and debits whoever got it
+            for transfer in &self.transfers
+            {
+                let task = transfer.task.as_deref ().unwrap_or ("");
+                let amount = self.transferred (transfer);
+                row (transfer.date.as_deref (), "payment", task, &transfer.from, (Money::ZERO, amount), transfer.memo.as_deref (), &blank);
+                row (transfer.date.as_deref (), "payment", task, &transfer.to, (amount, Money::ZERO), transfer.memo.as_deref (), &blank);
+            }
+            // stable, so entries of the same day stay in the order above
+            rows.sort_by (|a, b| a.0.cmp (&b.0));
+            for (_, line) in rows
+            {
+                log.push_str (&line);
+            }
+            match std::fs::write (filename, log)
+            {
+                Ok (_) => Ok (()),
+                Err (_) => Err (format! ("Unable to open file {}", filename)),
+            }
+        }
+
+        // the ledger as Arrow record batches, for looking through years of it
+        // with anything that reads Arrow, like Polars
+        #[cfg (feature = "arrow")]
+        pub fn to_arrow (&mut self) -> Result<ArrowTables, String>
+        {
+            use arrow_array::{ArrayRef, Date32Array, Decimal128Array, RecordBatch, StringArray};
+            self.calculate ();
+            let text = |values: Vec<Option<String>>| Arc::new (StringArray::from (values)) as ArrayRef;
+            let names = |values: Vec<&str>| Arc::new (StringArray::from (values)) as ArrayRef;
+            let days = |dates: Vec<Option<&str>>| Arc::new (Date32Array::from (dates.into_iter ()
+                                                                              .map (|d| d.map (|d| clock::days_between ("1970-01-01", d) as i32))
+                                                                              .collect::<Vec<Option<i32>>> ())) as ArrayRef;
+            let amounts = |values: Vec<Money>| -> Result<ArrayRef, String>
+            {
+                let array = Decimal128Array::from_iter_values (values.into_iter ().map (|m| m.cents () as i128))
+                    .with_precision_and_scale (18, 2)
+                    .map_err (|e| e.to_string ())?;
+                Ok (Arc::new (array))
+            };
+            let tasks = self.tasks.values ().collect::<Vec<&Task>> ();
+            let shares = tasks.iter ()
+                .flat_map (|t| self.charged_shares (t).into_iter ().map (|(p, s)| (t.name.as_str (), p, s)))
+                .collect::<Vec<(&str, String, Money)>> ();
+            let parts = self.participants.values ().collect::<Vec<&Participant>> ();
+            let batch = |columns: Vec<(&str, ArrayRef)>| RecordBatch::try_from_iter (columns).map_err (|e| e.to_string ());
+            Ok (ArrowTables
+                {
+                    tasks: batch (vec! [
+                        ("name", names (tasks.iter ().map (|t| t.name.as_str ()).collect ())),
+                        ("owner", names (tasks.iter ().map (|t| t.owner.as_str ()).collect ())),
+                        ("cost", amounts (tasks.iter ().map (|t| self.cost (t)).collect ())?),
+                        ("currency", text (tasks.iter ().map (|t| t.currency.clone ()).collect ())),
+                        ("date", days (tasks.iter ().map (|t| t.date.as_deref ()).collect ())),
+                        ("tags", Arc::new (StringArray::from (tasks.iter ()
+                                                              .map (|t| t.tags.iter ().cloned ().collect::<Vec<String>> ().join (","))
+                                                              .collect::<Vec<String>> ())) as ArrayRef),
+                        ("note", text (tasks.iter ().map (|t| t.note.clone ()).collect ())),
+                    ])?,
+                    shares: batch (vec! [
+                        ("task", names (shares.iter ().map (|s| s.0).collect ())),
+                        ("participant", names (shares.iter ().map (|s| s.1.as_str ()).collect ())),
+                        ("share", amounts (shares.iter ().map (|s| s.2).collect ())?),
+                    ])?,
+                    transfers: batch (vec! [
+                        ("from", names (self.transfers.iter ().map (|t| t.from.as_str ()).collect ())),
+                        ("to", names (self.transfers.iter ().map (|t| t.to.as_str ()).collect ())),
+                        ("amount", amounts (self.transfers.iter ().map (|t| self.transferred (t)).collect ())?),
+                        ("currency", text (self.transfers.iter ().map (|t| t.currency.clone ()).collect ())),
+                        ("memo", text (self.transfers.iter ().map (|t| t.memo.clone ()).collect ())),
+                        ("date", days (self.transfers.iter ().map (|t| t.date.as_deref ()).collect ())),
+                        ("task", text (self.transfers.iter ().map (|t| t.task.clone ()).collect ())),
+                    ])?,
+                    balances: batch (vec! [
+                        ("participant", names (parts.iter ().map (|p| p.name.as_str ()).collect ())),
+                        ("balance", amounts (parts.iter ().map (|p| p.sum.unwrap ()).collect ())?),
+                    ])?,
+                })
+        }
+
+        // writes the Arrow tables as Arrow IPC files in a directory, named
+        // tasks.arrow, shares.arrow, transfers.arrow and balances.arrow
+        #[cfg (feature = "arrow")]
+        fn export_arrow (&mut self, dir: &str) -> PaymentResult
+        {
+            let tables = self.to_arrow ()?;
+            std::fs::create_dir_all (dir).map_err (|_| format! ("Unable to create directory {dir}"))?;
+            for (name, batch) in [("tasks", &tables.tasks), ("shares", &tables.shares)
+                                  , ("transfers", &tables.transfers), ("balances", &tables.balances)]
+            {
+                let path = PathBuf::from (dir).join (format! ("{name}.arrow"));
+                let Ok (file) = File::create (&path) else
+                {
+                    return Err (format! ("Unable to open file {}", path.display ()));
+                };
+                let written = arrow_ipc::writer::FileWriter::try_new (file, &batch.schema ())
+                    .and_then (|mut writer| writer.write (batch).and_then (|_| writer.finish ()));
+                if let Err (e) = written
+                {
+                    return Err (format! ("Unable to write {}: {e}", path.display ()));
+                }
+            }
+            Ok (())
+        }
+
+        #[cfg (not (feature = "arrow"))]
+        fn export_arrow (&mut self, _dir: &str) -> PaymentResult
+        {
+            Err (String::from ("This build can't write Arrow files, build it with --features arrow"))
+        }
+
+        // the whole ledger in one zip file to hand over or keep as a backup:
+        // ledger.json as save writes it, report.json with what a report
+        // template is given, and journal.csv with the double entry journal
+        fn export_bundle (&mut self, filename: &str) -> PaymentResult
+        {
+            use std::io::Write;
+            let ledger = serde_json::to_string_pretty (&self)
+                .map_err (|e| format! ("Error serializing the object:\n{}", e))?;
+            let report = serde_json::to_string_pretty (&self.report_model ())
+                .map_err (|e| format! ("Error serializing the report:\n{}", e))?;
+            let mut journal = String::from ("task,account,debit,credit\n");
+            for entry in self.journal ()
+            {
+                journal.push_str (&format! ("{},{},{},{}\n", Self::csv_field (&entry.task), Self::csv_field (&entry.account)
+                                            , entry.debit, entry.credit));
+            }
+            let Ok (file) = File::create (filename) else
+            {
+                return Err (format! ("Unable to open file {}", filename));
+            };
+            let mut zip = zip::ZipWriter::new (BufWriter::new (file));
+            let options = zip::write::SimpleFileOptions::default ();
+            for (name, text) in [("ledger.json", ledger), ("report.json", report), ("journal.csv", journal)]
+            {
+                zip.start_file (name, options)
+                    .and_then (|_| zip.write_all (text.as_bytes ()).map_err (zip::result::ZipError::from))
+                    .map_err (|e| format! ("Unable to write {name} to {filename}: {e}"))?;
+            }
+            zip.finish ().map_err (|e| format! ("Unable to write {filename}: {e}"))?;
+            Ok (())
+        }
+
+        // a JSON Schema of the save format, worked out from the types that are
+        // saved so it can't drift from them
+        #[cfg (feature = "schema")]
+        pub fn json_schema () -> String
+        {
+            serde_json::to_string_pretty (&schemars::schema_for! (Payment)).unwrap ()
+        }
+
+        #[cfg (feature = "schema")]
+        fn export_schema (file: &str) -> PaymentResult
+        {
+            std::fs::write (file, Self::json_schema () + "\n").map_err (|_| format! ("Unable to write file {file}"))
+        }
+
+        #[cfg (not (feature = "schema"))]
+        fn export_schema (_file: &str) -> PaymentResult
+        {
+            Err (String::from ("This build can't write a schema, build it with --features schema"))
+        }
+
+        // quotes a value for a CSV file if it needs it
+        fn csv_field (value: &str) -> String
+        {
+            if value.contains ([',', '"', '\n'])
+            {
+                format! ("\"{}\"", value.replace ('"', "\"\""))
+            }
+            else
+            {
+                String::from (value)
+            }
+        }
+
+        // copies the ledger with every name and tag replaced by a pseudonym.
+        // names are handed out in sorted order so the same ledger always maps
+        // to the same pseudonyms. perturbing scales every amount by one random
+        // factor, which hides the real amounts but keeps how they compare to
+        // each other
+        fn anonymized (&self, perturb: bool) -> Payment
+        {
+            let mut kinds = BTreeMap::new ();
+            let mut tags = BTreeSet::new ();
+            self.collect_names (&mut kinds, &mut tags);
+            let mut counts = BTreeMap::new ();
+            let names = kinds.into_iter ()
+                .map (|(name, kind)|
+                      {
+                          let count = counts.entry (kind).or_insert (0);
+                          *count += 1;
+                          (name, format! ("{kind}{count}"))
+                      })
+                .collect::<BTreeMap<&str, String>> ();
+            let tags = tags.into_iter ().enumerate ()
+                .map (|(i, tag)| (tag, format! ("tag{}", i + 1)))
+                .collect::<BTreeMap<&str, String>> ();
+            let factor = if perturb
+            {
+                let random = self.ids.0.next_u64 ();
+                0.5f64 + (random % 1000) as f64 / 1000f64
+            }
+            else
+            {
+                1f64
+            };
+            self.anonymized_with (&names, &tags, factor)
+        }
+
+        // every name anywhere in the ledger and its subledgers with what kind
+        // of name it is, and every tag
+        fn collect_names<'a> (&'a self, kinds: &mut BTreeMap<&'a str, &'static str>, tags: &mut BTreeSet<&'a str>)
+        {
+            let mut add = |name: &'a String, kind: &'static str|
+            {
+                kinds.entry (name.as_str ()).or_insert (kind);
+            };
+            for name in self.participants.keys ()
+            {
+                add (name, "person");
+            }
+            for (name, members) in &self.groups
+            {
+                add (name, "group");
+                members.iter ().for_each (|m| add (m, "person"));
+            }
+            let tasks = self.tasks.values ()
+                .chain (self.archived.values ())
+                .chain (self.recurring.values ().map (|r| &r.task));
+            for task in tasks
+            {
+                add (&task.name, "task");
+                task.folded.keys ().for_each (|n| add (n, "task"));
+                std::iter::once (&task.owner)
+                    .chain (&task.participants)
+                    .chain (task.weights.keys ())
+                    .chain (task.amounts.keys ())
+                    .chain (task.percents.keys ())
+                    .chain (task.items.values ().flat_map (|i| &i.participants))
+                    .chain (task.payers.keys ())
+                    .chain (&task.excluded)
+                    .for_each (|n| add (n, "person"));
+                tags.extend (task.tags.iter ().map (String::as_str));
+            }
+            self.recurring.keys ().for_each (|n| add (n, "task"));
+            for transfer in &self.transfers
+            {
+                add (&transfer.from, "person");
+                add (&transfer.to, "person");
+            }
+            for entry in &self.cashbox.entries
+            {
+                entry.from.iter ().for_each (|n| add (n, "person"));
+            }
+            if let Some (dues) = &self.dues
+            {
+                dues.members.iter ().flatten ().chain (dues.paid.keys ()).for_each (|n| add (n, "person"));
+            }
+            for (name, sub) in &self.subledgers
+            {
+                kinds.entry (name.as_str ()).or_insert ("ledger");
+                sub.collect_names (kinds, tags);
+            }
+        }
+
+        // the ledger with every name and tag swapped for its pseudonym and
+        // every amount scaled by the factor. anything written by the user that
+        // isn't a name, like notes, memos and contact details, is left out
+        fn anonymized_with (&self, names: &BTreeMap<&str, String>, tags: &BTreeMap<&str, String>, factor: f64) -> Payment
+        {
+            let name = |n: &String| names[n.as_str ()].clone ();
+            // names that aren't in the ledger any more are left out
+            let rename = |set: &BTreeSet<String>| -> BTreeSet<String>
+            {
+                set.iter ().filter_map (|n| names.get (n.as_str ()).cloned ()).collect ()
+            };
+            let task = |task: &Task| -> Task
+            {
+                let amounts = task.amounts.iter ()
+                    .map (|(n, a)| (name (n), a.scale (factor)))
+                    .collect::<BTreeMap<String, Money>> ();
+                // scaled amounts have to keep adding up to the scaled cost
+                let cost = if amounts.is_empty ()
+                {
+                    task.cost.scale (factor)
+                }
+                else
+                {
+                    amounts.values ().sum ()
+                };
+                // parts of the cost are scaled with it, rounding down so they
+                // can never add up to more than the cost does
+                let part = |m: Money| if task.cost == Money::ZERO
+                {
+                    m.scale (factor)
+                }
+                else
+                {
+                    Money::from_cents ((m.cents () as i128 * cost.cents () as i128 / task.cost.cents () as i128) as i64)
+                };
+                Task
+                {
+                    name: name (&task.name),
+                    owner: name (&task.owner),
+                    participants: rename (&task.participants),
+                    cost,
+                    currency: task.currency.clone (),
+                    weights: task.weights.iter ().map (|(n, w)| (name (n), *w)).collect (),
+                    amounts,
+                    percents: task.percents.iter ().map (|(n, p)| (name (n), *p)).collect (),
+                    // item names can say as much as task names do
+                    items: task.items.values ().enumerate ()
+                        .map (|(i, item)| (format! ("item{}", i + 1), Item
+                                           {
+                                               cost: part (item.cost),
+                                               participants: rename (&item.participants),
+                                           }))
+                        .collect (),
+                    payers: task.payers.iter ().map (|(n, a)| (name (n), part (*a))).collect (),
+                    date: task.date.clone (),
+                    tags: task.tags.iter ().map (|t| tags[t.as_str ()].clone ()).collect (),
+                    tip: task.tip,
+                    // notes can say as much as names do
+                    note: None,
+                    day: task.day,
+                    fields: BTreeMap::new (),
+                    refunds: task.refunds.iter ()
+                        .map (|r| Refund { amount: part (r.amount), date: r.date.clone (), memo: None })
+                        .collect (),
+                    discount: match task.discount
+                    {
+                        Some (Discount::Amount (amount)) => Some (Discount::Amount (part (amount))),
+                        d => d,
+                    },
+                    excluded: rename (&task.excluded),
+                    folded: task.folded.iter ().map (|(n, c)| (name (n), part (*c))).collect (),
+                }
+            };
+            let mut anon = self.clone ();
+            anon.participants = self.participants.values ()
+                .map (|part| (name (&part.name), Participant
+                              {
+                                  tasks: rename (&part.tasks),
+                                  paid_tasks: rename (&part.paid_tasks),
+                                  budget: part.budget.map (|b| b.scale (factor)),
+                                  weight: part.weight,
+                                  ..Participant::new (&name (&part.name))
+                              }))
+                .collect ();
+            anon.tasks = self.tasks.values ().map (|t| (name (&t.name), task (t))).collect ();
+            anon.archived = self.archived.values ().map (|t| (name (&t.name), task (t))).collect ();
+            anon.recurring = self.recurring.iter ()
+                .map (|(n, r)| (name (n), Recurring { task: task (&r.task), ..r.clone () }))
+                .collect ();
+            anon.groups = self.groups.iter ().map (|(n, members)| (name (n), rename (members))).collect ();
+            // memos can say as much as names do
+            anon.transfers = self.transfers.iter ()
+                .map (|t| Transfer
+                      {
+                          from: name (&t.from),
+                          to: name (&t.to),
+                          amount: t.amount.scale (factor),
+                          currency: t.currency.clone (),
+                          memo: None,
+                          date: t.date.clone (),
+                          task: t.task.as_ref ().and_then (|n| names.get (n.as_str ()).cloned ()),
+                          tag: t.tag.as_ref ().and_then (|n| tags.get (n.as_str ()).cloned ()),
+                          id: t.id.clone (),
+                      })
+                .collect ();
+            anon.settled = self.settled.as_ref ().map (|s| Settled { tasks: rename (&s.tasks), ..s.clone () });
+            // so are the names of the ledger's own fields
+            anon.fields = (1..=self.fields.len ()).map (|i| format! ("field{i}")).collect ();
+            anon.cashbox = Cashbox
+            {
+                entries: self.cashbox.entries.iter ()
+                    .map (|e| CashEntry
+                          {
+                              amount: e.amount.scale (factor),
+                              from: e.from.as_ref ().map (name),
+                              memo: None,
+                              date: e.date.clone (),
+                          })
+                    .collect (),
+                counted: self.cashbox.counted.as_ref ().map (|(date, count)| (date.clone (), count.scale (factor))),
+            };
+            anon.dues = self.dues.as_ref ().map (|d| Dues
+                                               {
+                                                   amount: d.amount.scale (factor),
+                                                   members: d.members.as_ref ().map (rename),
+                                                   paid: d.paid.iter ().map (|(n, a)| (name (n), a.scale (factor))).collect (),
+                                                   ..d.clone ()
+                                               });
+            anon.invoicing = Invoicing { issued: self.invoicing.issued, ..Invoicing::default () };
+            anon.limits.amount = self.limits.amount.map (|a| a.scale (factor));
+            anon.rules = self.rules.iter ()
+                .filter_map (|rule| match rule
+                             {
+                                 Rule::MaxCost { max, except } => Some (Rule::MaxCost
+                                     {
+                                         max: max.scale (factor),
+                                         except: except.iter ()
+                                             .filter_map (|n| names.get (n.as_str ()).or (tags.get (n.as_str ())).cloned ())
+                                             .collect (),
+                                     }),
+                                 Rule::IncludeAll { task } => names.get (task.as_str ())
+                                     .map (|t| Rule::IncludeAll { task: t.clone () }),
+                                 r => Some (r.clone ()),
+                             })
+                .collect ();
+            // commands can say anything
+            anon.outcomes = Vec::new ();
+            anon.subledgers = self.subledgers.iter ()
+                .map (|(n, sub)| (name (n), sub.anonymized_with (names, tags, factor)))
+                .collect ();
+            anon
+        }
+
+        fn import (&mut self, args: &[&str]) -> PaymentResult
+        {
+            match args
+            {
+                ["contacts", filename] => self.import_contacts (filename),
+                ["matrix", filename] => self.import_matrix (filename),
+                ["bundle", "--merge", filename] => self.import_bundle (filename, true),
+                ["contacts"] | ["matrix"] | ["bundle"] | ["bundle", "--merge"] => Err (String::from ("Not enough arguments")),
+                ["bundle", filename] => self.import_bundle (filename, false),
+                [kind, ..] => Err (format! ("Don't know how to import {kind}")),
+                [] => Err (String::from ("Not enough arguments")),
+            }
+        }
+
+        // loads the ledger in a zip file written by export bundle, the rest
+        // of the bundle being made from it
+        fn import_bundle (&mut self, filename: &str, merge: bool) -> PaymentResult
+        {
+            let Ok (file) = File::open (filename) else
+            {
+                return Err (format! ("Unable to open file {}", filename));
+            };
+            let mut zip = zip::ZipArchive::new (BufReader::new (file))
+                .map_err (|e| format! ("{filename} is not a bundle: {e}"))?;
+            let ledger = zip.by_name ("ledger.json")
+                .map_err (|_| format! ("{filename} has no ledger.json in it"))?;
+            let payment: Payment = match serde_json::from_reader (ledger)
+            {
+                Ok (pay) => pay,
+                Err (e) => return Err (format! ("Error deserializing file:\n{}", e)),
+            };
+            self.load_ledger (payment, filename, merge)
+        }
+
+        // adds a participant for every contact in a vCard (.vcf) or CSV file.
+        // CSV files need a header row naming the name, email and phone columns
+        fn import_contacts (&mut self, filename: &str) -> PaymentResult
+        {
+            let Ok (text) = std::fs::read_to_string (filename) else
+            {
+                return Err (format! ("Unable to open file {}", filename));
+            };
+            let contacts = if filename.to_lowercase ().ends_with (".vcf")
+            {
+                Self::read_vcards (&text)
+            }
+            else
+            {
+                Self::read_contacts_csv (&text)?
+            };
+            // names can't contain whitespace since commands are split on it
+            let whitespace = Regex::new (r"\s+").unwrap ();
+            let mut new = Vec::new ();
+            for mut contact in contacts
+            {
+                contact.name = whitespace.replace_all (contact.name.trim (), "_").into_owned ();
+                if contact.name.is_empty ()
+                {
+                    continue;
+                }
+                if self.tasks.contains_key (&contact.name)
+                {
+                    return Err (format! ("A task named {} exists", contact.name));
+                }
+                if self.participants.contains_key (&contact.name) || new.iter ().any (|c: &Participant| c.name == contact.name)
+                {
+                    self.warnings.push (format! ("participant {} was already added, skipped", contact.name));
+                    continue;
+                }
+                new.push (contact);
+            }
+            self.check_limits (new.len (), 0)?;
+            for contact in new
+            {
+                self.participants.insert (contact.name.clone (), contact);
+            }
+            Ok (())
+        }
+
+        // reads a spreadsheet with a row per person and a column per task. each
+        // cell is that person's share of the task, and the person who paid for
+        // it has a * after their share. the first row names the tasks
+        fn import_matrix (&mut self, filename: &str) -> PaymentResult
+        {
+            let Ok (text) = std::fs::read_to_string (filename) else
+            {
+                return Err (format! ("Unable to open file {}", filename));
+            };
+            let mut lines = text.lines ();
+            let Some (header) = lines.next () else
+            {
+                return Err (format! ("{filename} is empty"));
+            };
+            let task_names = Self::csv_fields (header).split_off (1);
+            let rows = lines
+                .map (Self::csv_fields)
+                .filter (|r| r.first ().is_some_and (|n| !n.is_empty ()))
+                .collect::<Vec<Vec<String>>> ();
+            let mut tasks = Vec::new ();
+            for (col, task_name) in task_names.iter ().enumerate ()
+            {
+                if task_name.is_empty ()
+                {
+                    continue;
+                }
+                if self.tasks.contains_key (task_name) || self.participants.contains_key (task_name)
+                    || tasks.iter ().any (|t: &Task| &t.name == task_name)
+                {
+                    return Err (format! ("{task_name} already exists"));
+                }
+                let mut owner = None;
+                let mut shares = Vec::new ();
+                for row in &rows
+                {
+                    let Some (cell) = row.get (col + 1).filter (|c| !c.is_empty ()) else
+                    {
+                        continue;
+                    };
+                    let (share, paid) = match cell.strip_suffix ('*')
+                    {
+                        Some (share) => (share.trim (), true),
+                        None => (cell.as_str (), false),
+                    };
+                    let Ok (share) = Money::parse (share) else
+                    {
+                        return Err (format! ("{cell} is not a valid share of {task_name}"));
+                    };
+                    if paid && owner.replace (row[0].clone ()).is_some ()
+                    {
+                        return Err (format! ("{task_name} has more than one payer marked"));
+                    }
+                    shares.push ((row[0].clone (), share));
+                }
+                let Some (owner) = owner else
+                {
+                    return Err (format! ("Nobody is marked as having paid for {task_name}"));
+                };
+                // uneven shares are kept as the amounts the task is split by
+                let amounts = if shares.iter ().any (|s| s.1 != shares[0].1)
+                {
+                    shares.iter ().cloned ().collect ()
+                }
+                else
+                {
+                    BTreeMap::new ()
+                };
+                tasks.push (Task
+                            {
+                                name: task_name.clone (),
+                                owner,
+                                participants: shares.iter ().map (|s| s.0.clone ()).collect (),
+                                cost: shares.iter ().map (|s| s.1).sum (),
+                                currency: None,
+                                weights: BTreeMap::new (),
+                                amounts,
+                                percents: BTreeMap::new (),
+                                items: BTreeMap::new (),
+                                payers: BTreeMap::new (),
+                                date: Some (clock::date (self.now ())),
+                                tags: BTreeSet::new (),
+                                tip: None,
+                                note: None,
+                                day: None,
+                                fields: BTreeMap::new (),
+                                refunds: Vec::new (),
+                                discount: None,
+                                excluded: BTreeSet::new (),
+                                folded: BTreeMap::new (),
+                            });
+            }
+            let mut people = BTreeSet::new ();
+            for row in &rows
+            {
+                if self.tasks.contains_key (&row[0])
+                {
+                    return Err (format! ("A task named {} exists", row[0]));
+                }
+                if !self.participants.contains_key (&row[0])
+                {
+                    people.insert (row[0].clone ());
+                }
+            }
+            self.check_limits (people.len (), tasks.len ())?;
+            for name in people
+            {
+                self.participants.insert (name.clone (), Participant::new (&name));
+            }
+            for task in tasks
+            {
+                for name in &task.participants
+                {
+                    self.participants.get_mut (name).unwrap ().tasks.insert (task.name.clone ());
+                }
+                self.participants.get_mut (&task.owner).unwrap ().paid_tasks.insert (task.name.clone ());
+                self.tasks.insert (task.name.clone (), task);
+            }
+            Ok (())
+        }
+
+        fn read_vcards (text: &str) -> Vec<Participant>
+        {
+            let mut contacts = Vec::new ();
+            let mut current: Option<Participant> = None;
+            for line in text.lines ()
+            {
+                let Some ((key, value)) = line.split_once (':') else
+                {
+                    continue;
+                };
+                // properties can carry parameters like EMAIL;TYPE=home
+                let key = key.split (';').next ().unwrap_or ("").to_uppercase ();
+                let value = value.trim ();
+                match (key.as_str (), current.as_mut ())
+                {
+                    ("BEGIN", _) => current = Some (Participant::new ("")),
+                    ("END", Some (_)) => contacts.extend (current.take ()),
+                    ("FN", Some (c)) => c.name = String::from (value),
+                    ("EMAIL", Some (c)) if c.email.is_none () => c.email = Some (String::from (value)),
+                    ("TEL", Some (c)) if c.phone.is_none () => c.phone = Some (String::from (value)),
+                    _ => (),
+                }
+            }
+            contacts
+        }
+
+        fn read_contacts_csv (text: &str) -> Result<Vec<Participant>, String>
+        {
+            let mut lines = text.lines ();
+            let Some (header) = lines.next () else
+            {
+                return Ok (Vec::new ());
+            };
+            let columns = Self::csv_fields (header)
+                .iter ()
+                .map (|c| c.to_lowercase ())
+                .collect::<Vec<String>> ();
+            let column = |name: &str| columns.iter ().position (|c| c == name);
+            let Some (name_col) = column ("name") else
+            {
+                return Err (String::from ("The CSV file needs a name column"));
+            };
+            let email_col = column ("email");
+            let phone_col = column ("phone");
+            let mut contacts = Vec::new ();
+            for line in lines
+            {
+                let fields = Self::csv_fields (line);
+                let field = |col: Option<usize>| col
+                    .and_then (|c| fields.get (c))
+                    .filter (|f| !f.is_empty ())
+                    .cloned ();
+                let Some (name) = field (Some (name_col)) else
+                {
+                    continue;
+                };
+                contacts.push (Participant
+                               {
+                                   email: field (email_col),
+                                   phone: field (phone_col),
+                                   ..Participant::new (&name)
+                               });
+            }
+            Ok (contacts)
+        }
+
+        // splits a line of a CSV file on commas, allowing fields to be quoted
+        fn csv_fields (line: &str) -> Vec<String>
+        {
+            let mut fields = Vec::new ();
+            let mut field = String::new ();
+            let mut quoted = false;
+            let mut chars = line.chars ().peekable ();
+            while let Some (c) = chars.next ()
+            {
+                match c
+                {
+                    '"' if quoted && chars.peek () == Some (&'"') =>
+                    {
+                        field.push ('"');
+                        chars.next ();
+                    },
+                    '"' => quoted = !quoted,
+                    ',' if !quoted => fields.push (std::mem::take (&mut field).trim ().to_string ()),
+                    c => field.push (c),
+                }
+            }
+            fields.push (field.trim ().to_string ());
+            fields
+        }
+
+        fn edit (&mut self, args: &[&str]) -> PaymentResult
+        {
+            match args
+            {
+                ["part", name, key, value] =>
+                {
+                    let name = self.resolve (name)?;
+                    let Some (part) = self.participants.get_mut (&name) else
+                    {
+                        return Err (format! ("No participant named {name} exists"));
+                    };
+                    part.set_attribute (key, value)
+                },
+                ["part", ..] => Err (String::from ("edit part must be called with a name, a field and a value")),
+                ["task", task_name, key, value] =>
+                {
+                    let task_name = self.resolve (task_name)?;
+                    if !self.fields.contains (*key)
+                    {
+                        return Err (format! ("{key} is not a field of this ledger, add it with field {key}"));
+                    }
+                    let Some (task) = self.tasks.get_mut (&task_name) else
+                    {
+                        return Err (format! ("Task {task_name} has not yet been added"));
+                    };
+                    match *value
+                    {
+                        "none" => task.fields.remove (*key),
+                        v => task.fields.insert (String::from (*key), String::from (v)),
+                    };
+                    Ok (())
+                },
+                ["task", ..] => Err (String::from ("edit task must be called with a task, a field and a value")),
+                [kind, ..] => Err (format! ("{kind} can't be edited")),
+                [] => Err (String::from ("Not enough arguments")),
+            }
+        }
+
+        fn rename (&mut self, args: &[&str]) -> PaymentResult
+        {
+            if args.len () != 2
+            {
+                return Err (String::from ("remove must be called with 2 arguments"));
+            }
+            if self.participants.contains_key (args[1]) || self.tasks.contains_key (args[1])
+                || self.groups.contains_key (args[1])
+            {
+                return Err (format! ("{} already exists", args[1]));
+            }
+            let old = self.resolve (args[0])?;
+            let args = [old.as_str (), args[1]];
+            if let Some (members) = self.groups.remove (args[0])
+            {
+                self.groups.insert (String::from (args[1]), members);
+                return Ok (());
+            }
+            // see if we are renaming a participant
+            if let Some (mut part) = self.participants.remove (args[0])
+            {
+                // renaming a participant, tasks they were taken out of
+                // mention them too
+                for task in self.tasks.values_mut ()
+                {
+                    task.rename_participant (&part.name, args[1]);
+                }
+                for recurring in self.recurring.values_mut ()
+                {
+                    recurring.task.rename_participant (&part.name, args[1]);
+                }
+                for task in self.archived.values_mut ()
+                {
+                    task.rename_participant (&part.name, args[1]);
+                }
+                for members in self.groups.values_mut ()
+                {
+                    if members.remove (&part.name)
+                    {
+                        members.insert (String::from (args[1]));
+                    }
+                }
+                for transfer in &mut self.transfers
+                {
+                    if transfer.from == part.name
+                    {
+                        transfer.from = String::from (args[1]);
+                    }
+                    if transfer.to == part.name
+                    {
+                        transfer.to = String::from (args[1]);
+                    }
+                }
+                for entry in self.cashbox.entries.iter_mut ().filter (|e| e.from.as_ref () == Some (&part.name))
+                {
+                    entry.from = Some (String::from (args[1]));
+                }
+                if let Some (dues) = &mut self.dues
+                {
+                    if let Some (members) = &mut dues.members
+                    {
+                        if members.remove (&part.name)
+                        {
+                            members.insert (String::from (args[1]));
+                        }
+                    }
+                    if let Some (paid) = dues.paid.remove (&part.name)
+                    {
+                        dues.paid.insert (String::from (args[1]), paid);
+                    }
+                }
+                part.name = String::from (args[1]);
+                self.participants.insert (String::from (args[1]), part);
+
+                return Ok (())
+            }
+            // check if there is a task with this name
+            if let Some (mut task) = self.tasks.remove (args[0])
+            {
+                for name in &task.participants
+                {
+                    let part = self.participants.get_mut (name).unwrap ();
+                    part.tasks.remove (&task.name);
+                    part.tasks.insert (String::from (args[1]));
+                    if part.paid_tasks.remove (&task.name)
+                    {
+                        part.paid_tasks.insert (String::from (args[1]));
+                    }
+                }
+                if let Some (settled) = &mut self.settled
+                {
+                    if settled.tasks.remove (&task.name)
+                    {
+                        settled.tasks.insert (String::from (args[1]));
+                    }
+                }
+                for transfer in self.transfers.iter_mut ().filter (|t| t.task.as_ref () == Some (&task.name))
+                {
+                    transfer.task = Some (String::from (args[1]));
+                }
+                task.name = String::from (args[1]);
+                self.tasks.insert (String::from (args[1]), task);
+
+                return Ok (());
+            }
+            // nothing can be renamed, return error
+            Err (format! ("No task or participant found named {}", args[0]))
+        }
+
+        fn remove_from (&mut self, part_name: &str, task_name: &str) -> PaymentResult
+        {
+            let Some (part) = self.participants.get_mut (part_name) else
+            {
+                return Err (format! ("No participant named {part_name} exists"));
+            };
+            if part.paid_tasks.contains (task_name)
+            {
+                if self.tasks[task_name].owner != part_name
+                {
+                    return Err (format! ("{part_name} paid part of {task_name}, take that back with pay {part_name} {task_name} +0 first"));
+                }
+                return Err (format! ("{part_name} paid for {task_name}, remove {task_name} instead"));
+            }
+            if part.tasks.remove (task_name)
+            {
+                // this task exists since it's listed as a task for the participant
+                let task = self.tasks.get_mut (task_name).unwrap ();
+                task.participants.remove (part_name);
+                task.weights.remove (part_name);
+                task.leave_items (part_name);
+                self.warnings.extend (task.clear_split ());
+            }
+            Ok (())
+        }
+
+        fn remove (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let resolved = args.iter ()
+                .map (|a| self.resolve (a))
+                .collect::<Result<Vec<String>, String>> ()?;
+            let args = resolved.iter ().map (String::as_str).collect::<Vec<&str>> ();
+            if args.len () == 2
+            {
+                return self.remove_from (args[0], args[1]);
+            }
+            if args.len () != 1
+            {
+                return Err (String::from ("Wrong number of arguments"));
+            }
+            // check if the removal is a participant
+            if let Some (part) = self.participants.remove (args[0])
+            {
+                // remove this participant from all of their tasks
+                for task_name in &part.tasks
+                {
+                    if part.paid_tasks.contains (task_name)
+                    {
+                        continue;
+                    }
+                    let task = self.tasks.get_mut (task_name).unwrap ();
+                    task.participants.remove (&part.name);
+                    task.weights.remove (&part.name);
+                    task.leave_items (&part.name);
+                    self.warnings.extend (task.clear_split ());
+                }
+                // remove all tasks this participant owns, the owners of tasks
+                // they only paid part of pay their part instead
+                for task_name in &part.paid_tasks
+                {
+                    let task = self.tasks.get_mut (task_name).unwrap ();
+                    if task.owner == part.name
+                    {
+                        self.remove_task (task_name)?;
+                        continue;
+                    }
+                    task.payers.remove (&part.name);
+                    task.participants.remove (&part.name);
+                    task.weights.remove (&part.name);
+                    task.leave_items (&part.name);
+                    self.warnings.extend (task.clear_split ());
+                }
+                for task in self.tasks.values_mut ()
+                {
+                    task.excluded.remove (&part.name);
+                }
+                // and the payments they made or got, keeping count of how many
+                // were from before the last settlement
+                let involved = |t: &Transfer| t.from == part.name || t.to == part.name;
+                if let Some (settled) = &mut self.settled
+                {
+                    settled.transfers -= self.transfers.iter ().take (settled.transfers).filter (|t| involved (t)).count ();
+                }
+                self.transfers.retain (|t| !involved (t));
+                // what they put in the cash box stays in it
+                for entry in self.cashbox.entries.iter_mut ().filter (|e| e.from.as_ref () == Some (&part.name))
+                {
+                    entry.from = None;
+                }
+                if let Some (dues) = &mut self.dues
+                {
+                    if let Some (members) = &mut dues.members
+                    {
+                        members.remove (&part.name);
+                    }
+                    dues.paid.remove (&part.name);
+                }
+                for members in self.groups.values_mut ()
+                {
+                    members.remove (&part.name);
+                }
+                self.groups.retain (|_, m| !m.is_empty ());
+                // recurring tasks they paid for stop, the others go on without them
+                self.recurring.retain (|_, r| r.task.owner != part.name);
+                for recurring in self.recurring.values_mut ()
+                {
+                    let task = &mut recurring.task;
+                    if task.participants.remove (&part.name)
+                    {
+                        task.weights.remove (&part.name);
+                        task.payers.remove (&part.name);
+                        task.leave_items (&part.name);
+                        self.warnings.extend (task.clear_split ());
+                    }
+                }
+                return Ok (());
+            }
+            // a task folded into another by compact comes out of it again
+            if let Some (into) = self.folded_into (args[0]).map (|t| t.name.clone ()).filter (|_| !self.tasks.contains_key (args[0]))
+            {
+                let task = self.tasks.get_mut (&into).unwrap ();
+                task.cost -= task.folded.remove (args[0]).unwrap ();
+                return Ok (());
+            }
+            match self.remove_task (args[0])
+            {
+                Ok (_) =>
+                {
+                    // payments for it still count, just not towards any task
+                    for transfer in self.transfers.iter_mut ().filter (|t| t.task.as_deref () == Some (args[0]))
+                    {
+                        transfer.task = None;
+                    }
+                    Ok (())
+                },
+                Err (_) => Err (format! ("{} is not a task or participant", args[0])),
+            }
+        }
+
+        // the task compact folded a task into
+        fn folded_into (&self, name: &str) -> Option<&Task>
+        {
+            self.tasks.values ().find (|t| t.folded.contains_key (name))
+        }
+
+        fn remove_task (&mut self, task_name: &str) -> PaymentResult
+        {
+            let Some (task) = self.tasks.remove (task_name) else
+            {
+                return Err (format! ("Task {} was not present to be removed", task_name));
+            };
+            for name in &task.participants
+            {
+                let Some (part) = self.participants.get_mut (name) else
+                {
+                    continue;
+                };
+                part.tasks.remove (task_name);
+                part.paid_tasks.remove (task_name);
+            }
+            Ok (())
+        }
+
+        // the journal behind the ledger. every task credits whoever paid for it
+        // what they paid and debits each participant the share they are
+        // charged. both add up to the cost exactly, so the entries of every
+        // task add up to exactly zero
+        fn journal (&self) -> Vec<Entry>
+        {
+            let mut entries = Vec::new ();
+            for task in self.tasks.values ()
+            {
+                if task.participants.is_empty ()
+                {
+                    continue;
+                }
+                for (name, paid) in self.paid (task)
+                {
+                    entries.push (Entry
+                                  {
+                                      task: task.name.clone (),
+                                      account: name,
+                                      debit: Money::ZERO,
+                                      credit: paid,
+                                  });
+                }
+                for (name, share) in self.charged_shares (task)
+                {
+                    entries.push (Entry
+                                  {
+                                      task: task.name.clone (),
+                                      account: name,
+                                      debit: share,
+                                      credit: Money::ZERO,
+                                  });
+                }
+            }
+            // a payment credits whoever made it and debits whoever got it
+            for transfer in &self.transfers
+            {
+                let amount = self.transferred (transfer);
+                entries.push (Entry
+                              {
+                                  task: String::from ("payment"),
+                                  account: transfer.from.clone (),
+                                  debit: Money::ZERO,
+                                  credit: amount,
+                              });
+                entries.push (Entry
+                              {
+                                  task: String::from ("payment"),
+                                  account: transfer.to.clone (),
+                                  debit: amount,
+                                  credit: Money::ZERO,
+                              });
+            }
+            entries
+        }
+
+        fn set_double_entry (&mut self, args: &[&str]) -> PaymentResult
+        {
+            self.double_entry = match args
+            {
+                ["on"] => true,
+                ["off"] => false,
+                _ => return Err (String::from ("double-entry must be called with on or off")),
+            };
+            Ok (())
+        }
+
+        fn print_journal (&self)
+        {
+            let entries = self.journal ();
+            let show = |m| self.settings.show (m);
+            for entry in &entries
+            {
+                match entry.debit
+                {
+                    Money::ZERO => say! (self, "{:<12} {:<12} {:>10} {:>10}", entry.task, entry.account, "", show (entry.credit)),
+                    d => say! (self, "{:<12} {:<12} {:>10} {:>10}", entry.task, entry.account, show (d), ""),
+                }
+            }
+            let debits = entries.iter ().map (|e| e.debit).sum::<Money> ();
+            let credits = entries.iter ().map (|e| e.credit).sum::<Money> ();
+            say! (self, "{:<25} {:>10} {:>10}", "total", show (debits), show (credits));
+        }
+
+        fn calculate (&mut self)
+        {
+            let start = Instant::now ();
+            self.calculate_balances ();
+            self.accrue_interest ();
+            self.record ("calculate", start);
+        }
+
+        fn calculate_balances (&mut self)
+        {
+            if self.splits_exactly ()
+            {
+                let mut balances: BTreeMap<String, Money> = BTreeMap::new ();
+                for entry in self.journal ()
+                {
+                    *balances.entry (entry.account).or_insert (Money::ZERO) += entry.debit - entry.credit;
+                }
+                for part in self.participants.values_mut ()
+                {
+                    part.sum = Some (balances.get (&part.name).copied ().unwrap_or (Money::ZERO));
+                }
+                for sub in self.subledgers.values_mut ()
+                {
+                    sub.calculate ();
+                }
+                return;
+            }
+            let mut sums = BTreeMap::new ();
+            for part in self.participants.values ()
+            {
+                let mut sum = ExactSum::default ();
+                for task_name in &part.tasks
+                {
+                    // divide the cost of this task among its participants and
+                    // add that amount to the amount this participant owes
+                    let task = self.tasks.get (task_name).unwrap ();
+                    self.add_share (&mut sum, task, &part.name);
+                }
+                for task_name in &part.paid_tasks
+                {
+                    // same as before but subtracting what this participant
+                    // has already paid towards this task
+                    let task = self.tasks.get (task_name).unwrap ();
+                    sum.sub (self.paid_by (task, &part.name));
+                }
+                sums.insert (part.name.clone (), sum.rounded ());
+            }
+            for transfer in &self.transfers
+            {
+                let amount = self.transferred (transfer);
+                *sums.get_mut (&transfer.from).unwrap () -= amount;
+                *sums.get_mut (&transfer.to).unwrap () += amount;
+            }
+            for part in self.participants.values_mut ()
+            {
+                part.sum = sums.remove (&part.name);
+            }
+            for sub in self.subledgers.values_mut ()
+            {
+                sub.calculate ();
+            }
+        }
+
+        // works out the transfers needed to bring every balance to zero.
+        // debtors and creditors are matched largest first, with ties broken by
+        // name, so the same ledger always produces the same plan
+        fn settlement (&mut self) -> Vec<(String, String, Money)>
+        {
+            self.calculate ();
+            let mut debtors = Vec::new ();
+            let mut creditors = Vec::new ();
+            for part in self.participants.values ()
+            {
+                let sum = part.sum.unwrap ();
+                if sum > Money::ZERO
+                {
+                    debtors.push ((part.name.clone (), sum));
+                }
+                else if sum < Money::ZERO
+                {
+                    creditors.push ((part.name.clone (), -sum));
+                }
+            }
+            let mut transfers = Vec::new ();
+            loop
+            {
+                debtors.sort_by (|a, b| b.1.cmp (&a.1).then_with (|| a.0.cmp (&b.0)));
+                creditors.sort_by (|a, b| b.1.cmp (&a.1).then_with (|| a.0.cmp (&b.0)));
+                let (Some (debtor), Some (creditor)) = (debtors.first_mut (), creditors.first_mut ()) else
+                {
+                    break;
+                };
+                let amount = debtor.1.min (creditor.1);
+                transfers.push ((debtor.0.clone (), creditor.0.clone (), amount));
+                debtor.1 -= amount;
+                creditor.1 -= amount;
+                debtors.retain (|d| d.1 > Money::ZERO);
+                creditors.retain (|c| c.1 > Money::ZERO);
+            }
+            transfers
+        }
+
+        // a plan for each currency the ledger was paid in, the base currency
+        // first, settling what was run up in it. the balances in a currency
+        // add up to zero on their own, so nothing needs converting. only when
+        // two people would pay each other back in different currencies is the
+        // smaller of the two converted and taken off the larger
+        fn settlement_by_currency (&self) -> Vec<(String, String, Money, Option<String>)>
+        {
+            let mut currencies = self.tasks.values ().map (|t| &t.currency)
+                .chain (self.transfers.iter ().map (|t| &t.currency))
+                .cloned ()
+                .collect::<BTreeSet<Option<String>>> ();
+            currencies.insert (None);
+            let mut plan = Vec::new ();
+            for currency in currencies
+            {
+                let mut only = self.only (|t| t.currency == currency, |t| t.currency == currency);
+                // amounts stay in the currency they were paid in, and interest
+                // is only charged in the base currency
+                only.currencies.rates.clear ();
+                if currency.is_some ()
+                {
+                    only.interest = None;
+                }
+                plan.extend (only.settlement ().into_iter ().map (|(from, to, amount)| (from, to, amount, currency.clone ())));
+            }
+            let rate = |currency: &Option<String>| currency.as_ref ().map_or (1f64, |c| self.currencies.rates[c]);
+            loop
+            {
+                let opposite = (0..plan.len ())
+                    .flat_map (|i| (0..plan.len ()).map (move |j| (i, j)))
+                    .find (|&(i, j)| plan[i].0 == plan[j].1 && plan[i].1 == plan[j].0 && plan[i].3 != plan[j].3);
+                let Some ((i, j)) = opposite else
+                {
+                    break;
+                };
+                let worth = |k: usize| plan[k].2.scale (rate (&plan[k].3));
+                let (larger, smaller) = if worth (i) >= worth (j) { (i, j) } else { (j, i) };
+                let left = worth (larger) - worth (smaller);
+                plan[larger].2 = left.scale (1f64 / rate (&plan[larger].3));
+                plan.remove (smaller);
+                plan.retain (|t| t.2 > Money::ZERO);
+            }
+            plan
+        }
+
+        // the ledger as far as the tasks with a tag go: those tasks, the
+        // payments for them and what earlier settle --tag paid. interest is
+        // left out, it runs on whole balances
+        fn tagged (&self, tag: &str) -> Result<Payment, String>
+        {
+            if !self.tasks.values ().any (|t| t.tags.contains (tag))
+            {
+                return Err (format! ("No task is tagged {tag}"));
+            }
+            let has_tag = |name: &String| self.tasks.get (name).is_some_and (|t| t.tags.contains (tag));
+            let mut tagged = self.only (|t| t.tags.contains (tag)
+                                        , |t| t.tag.as_deref () == Some (tag) || t.task.as_ref ().is_some_and (has_tag));
+            tagged.interest = None;
+            Ok (tagged)
+        }
+
+        // a copy of the ledger with only the tasks and payments picked, to
+        // work out balances over part of it
+        fn only (&self, task: impl Fn (&Task) -> bool, transfer: impl Fn (&Transfer) -> bool) -> Payment
+        {
+            let mut only = self.clone ();
+            for name in self.tasks.values ().filter (|t| !task (t)).map (|t| &t.name)
+            {
+                only.remove_task (name).ok ();
+            }
+            only.transfers.retain (transfer);
+            only.subledgers.clear ();
+            only
+        }
+
+        // everything a report template can use, worked out fresh
+        fn report_model (&mut self) -> Report
+        {
+            let summary = self.summary ();
+            let balance = |name: &str, amount: Money| ReportBalance
+            {
+                name: String::from (name),
+                balance: self.settings.show (amount),
+                owes: amount > Money::ZERO,
+            };
+            Report {
+                currency: self.settings.currency.clone ().or (self.currencies.base.clone ()),
+                spent: self.settings.show (summary.spent),
+                outstanding: self.settings.show (summary.outstanding),
+                unsettled_tasks: summary.unsettled_tasks,
+                balances: self.participants.values ()
+                    .map (|p| balance (&p.name, p.sum.unwrap ()))
+                    .collect (),
+                tasks: self.tasks.values ()
+                    .map (|t| ReportTask
+                          {
+                              name: t.name.clone (),
+                              owner: t.owner.clone (),
+                              cost: self.amount (t),
+                              date: t.date.clone (),
+                              tags: t.tags.iter ().cloned ().collect (),
+                              note: t.note.clone (),
+                              day: self.day (t),
+                              fields: t.fields.clone (),
+                              shares: self.charged_shares (t).into_iter ()
+                                  .map (|(name, share)| balance (&name, share))
+                                  .collect (),
+                          })
+                    .collect (),
+                settlement: self.settlement ().into_iter ()
+                    .map (|(from, to, amount)| ReportTransfer { from, to, amount: self.settings.show (amount) })
+                    .collect (),
+            }
+        }
+
+        // fills in a template with the report model, so a group can get its
+        // balances in whatever format they already use. with --sign the
+        // written report can be checked later with verify
+        fn report (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (since, args) = match args
+            {
+                ["--since-last-settlement", rest @ ..] => (true, rest),
+                _ => (false, args),
+            };
+            let (sign, args) = match args
+            {
+                [rest @ .., "--sign"] => (true, rest),
+                _ => (false, args),
+            };
+            let (template, output) = match args
+            {
+                [] if since => (None, None),
+                ["--template", t] => (Some (*t), None),
+                ["--template", t, o] => (Some (*t), Some (*o)),
+                ["--template"] | [] => return Err (String::from ("Not enough arguments")),
+                [a, ..] if *a != "--template" => return Err (format! ("{a} is not a report option, use --since-last-settlement or --template FILE")),
+                _ => return Err (String::from ("Too many arguments")),
+            };
+            if sign && output.is_none ()
+            {
+                return Err (String::from ("report --sign must be given an OUTPUT file to sign"));
+            }
+            let mut delta;
+            let ledger = if since
+            {
+                delta = self.since_settlement ()?;
+                &mut delta
+            }
+            else
+            {
+                self
+            };
+            match template
+            {
+                Some (t) => ledger.render_report (t, output, sign),
+                None =>
+                {
+                    ledger.print_since ();
+                    Ok (())
+                },
+            }
+        }
+
+        // a copy of the ledger with only what happened since it was last
+        // settled, so its balances are how much each balance moved since
+        fn since_settlement (&self) -> Result<Payment, String>
+        {
+            let Some (settled) = &self.settled else
+            {
+                return Err (String::from ("The ledger hasn't been settled yet, settle it with settle --apply"));
+            };
+            let mut since = self.clone ();
+            for name in settled.tasks.iter ().filter (|t| self.tasks.contains_key (*t))
+            {
+                since.remove_task (name)?;
+            }
+            since.transfers.drain (..settled.transfers.min (self.transfers.len ()));
+            // sub-ledgers are settled on their own
+            since.subledgers.clear ();
+            Ok (since)
+        }
+
+        fn print_since (&mut self)
+        {
+            self.calculate ();
+            say! (self, "since the settlement on {}:", self.settings.show_date (&self.settled.as_ref ().unwrap ().date));
+            if self.tasks.is_empty () && self.transfers.is_empty ()
+            {
+                say! (self, "  nothing happened");
+                return;
+            }
+            for task in self.tasks.values ()
+            {
+                say! (self, "  {} paid {} for {}", task.owner, self.amount (task), task.name);
+            }
+            for transfer in &self.transfers
+            {
+                match &transfer.memo
+                {
+                    Some (memo) => say! (self, "  {} paid {} {}: {memo}", transfer.from, transfer.to, self.transfer_amount (transfer)),
+                    None => say! (self, "  {} paid {} {}", transfer.from, transfer.to, self.transfer_amount (transfer)),
+                }
+            }
+            for part in self.participants.values ()
+            {
+                match part.sum.unwrap ()
+                {
+                    m if m > Money::ZERO => say! (self, "  {} owes {} more", part.name, self.settings.show (m)),
+                    m if m < Money::ZERO => say! (self, "  {} is owed {} more", part.name, self.settings.show (-m)),
+                    _ => (),
+                }
+            }
+        }
+
+        fn render_report (&mut self, template: &str, output: Option<&str>, sign: bool) -> PaymentResult
+        {
+            let source = match std::fs::read_to_string (template)
+            {
+                Ok (s) => s,
+                Err (_) => return Err (format! ("Unable to open file {}", template)),
+            };
+            let model = self.report_model ();
+            let mut env = minijinja::Environment::new ();
+            let text = env.add_template (template, &source)
+                .and_then (|_| env.get_template (template))
+                .and_then (|t| t.render (&model));
+            let text = match text
+            {
+                Ok (t) => t,
+                Err (e) => return Err (format! ("Error in template {}:\n{}", template, e)),
+            };
+            match output
+            {
+                Some (f) if sign => std::fs::write (f, self.signed (f, text.into_bytes ())?).map_err (|_| format! ("Unable to open file {}", f)),
+                Some (f) => std::fs::write (f, text).map_err (|_| format! ("Unable to open file {}", f)),
+                None =>
+                {
+                    self.output.print (format_args! ("{text}"));
+                    Ok (())
+                },
+            }
+        }
+
+        // writes a numbered PDF invoice for a participant with everything
+        // they were charged and paid, any dues they are behind on and how to
+        // pay. invoice set name|address|instructions TEXT sets the club
+        // details printed on it, and --sign signs it so it can be checked
+        // later with verify
+        fn invoice (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (sign, args) = match args
+            {
+                [rest @ .., "--sign"] if rest.first () != Some (&"set") => (true, rest),
+                _ => (false, args),
+            };
+            let (name, file) = match args
+            {
+                ["set", key, text @ ..] if !text.is_empty () =>
+                {
+                    let text = text.join (" ");
+                    let text = text.strip_prefix ('"').and_then (|t| t.strip_suffix ('"')).unwrap_or (&text);
+                    let value = Some (String::from (text)).filter (|t| t != "none");
+                    match *key
+                    {
+                        "name" => self.invoicing.name = value,
+                        "address" => self.invoicing.address = value,
+                        "instructions" => self.invoicing.instructions = value,
+                        k => return Err (format! ("{k} is not on an invoice, use name, address or instructions")),
+                    }
+                    return Ok (());
+                },
+                ["set", ..] => return Err (String::from ("invoice set must be called with a detail and its text")),
+                [name] => (*name, None),
+                [name, file] => (*name, Some (*file)),
+                [] => return Err (String::from ("Not enough arguments")),
+                _ => return Err (String::from ("Too many arguments")),
+            };
+            let name = self.resolve (name)?;
+            self.calculate ();
+            if !self.participants.contains_key (&name)
+            {
+                return Err (format! ("No participant named {name} exists"));
+            }
+            let number = self.invoicing.issued + 1;
+            let (mut pdf, _) = self.statement_pdf (&name, &format! ("Invoice {number:04}"));
+            let file = file.map_or_else (|| format! ("invoice-{number:04}-{name}.pdf"), String::from);
+            if sign
+            {
+                pdf = self.signed (&file, pdf)?;
+            }
+            std::fs::write (&file, pdf).map_err (|_| format! ("Unable to open file {}", file))?;
+            self.invoicing.issued = number;
+            say! (self, "wrote invoice {number:04} to {file}");
+            Ok (())
+        }
+
+        // the PDF an invoice or statement is printed as, with everything a
+        // participant was charged and paid, any dues they are behind on and
+        // how to pay, and what they owe in total. the ledger has to be
+        // calculated first
+        fn statement_pdf (&self, name: &str, title: &str) -> (Vec<u8>, Money)
+        {
+            let part = &self.participants[name];
+            let show = |amount: Money| format! ("{:>12}", self.settings.show (amount));
+            let mut lines = vec! [(16f32, self.invoicing.name.clone ().unwrap_or_default ())];
+            lines.extend (self.invoicing.address.iter ().flat_map (|a| a.split (", ")).map (|l| (10f32, String::from (l))));
+            lines.push ((10f32, String::new ()));
+            lines.push ((14f32, String::from (title)));
+            lines.push ((10f32, format! ("Date: {}", self.settings.show_date (&clock::date (self.now ())))));
+            lines.push ((10f32, match &part.email
+            {
+                Some (email) => format! ("To: {name} <{email}>"),
+                None => format! ("To: {name}"),
+            }));
+            lines.push ((10f32, String::new ()));
+            let mut items = Vec::new ();
+            for task_name in &part.tasks
+            {
+                items.push ((format! ("{task_name}, your share"), self.charged_share (&self.tasks[task_name], name)));
+            }
+            for task_name in &part.paid_tasks
+            {
+                items.push ((format! ("{task_name}, paid by you"), -self.paid_by (&self.tasks[task_name], name)));
+            }
+            for transfer in &self.transfers
+            {
+                if transfer.from == name
+                {
+                    items.push ((format! ("payment to {}", transfer.to), -self.transferred (transfer)));
+                }
+                else if transfer.to == name
+                {
+                    items.push ((format! ("payment from {}", transfer.from), self.transferred (transfer)));
+                }
+            }
+            if let Some (interest) = part.interest
+            {
+                items.push ((String::from ("interest"), interest));
+            }
+            let mut total = part.sum.unwrap ();
+            if let Some ((_, paid, owed)) = self.dues_status ().into_iter ().find (|d| d.0 == name).filter (|d| d.1 < d.2)
+            {
+                items.push ((String::from ("dues not yet paid"), owed - paid));
+                total += owed - paid;
+            }
+            for (what, amount) in items
+            {
+                let what = if what.chars ().count () > 56 { format! ("{}...", what.chars ().take (53).collect::<String> ()) } else { what };
+                lines.push ((10f32, format! ("{what:<56} {}", show (amount))));
+            }
+            lines.push ((10f32, format! ("{:<56} {}", "", "-".repeat (12))));
+            lines.push ((10f32, format! ("{:<56} {}", if total > Money::ZERO { "Total due" } else { "Nothing to pay" }, show (total))));
+            if let Some (instructions) = &self.invoicing.instructions
+            {
+                lines.push ((10f32, String::new ()));
+                lines.push ((10f32, String::from ("How to pay:")));
+                lines.extend (instructions.split (", ").map (|l| (10f32, String::from (l))));
+            }
+            (Self::text_pdf (&lines), total)
+        }
+
+        // the key reports are signed with, made and kept in the key file the
+        // first time it's needed
+        fn signing_key (&self) -> Result<ed25519_dalek::SigningKey, String>
+        {
+            let Some (file) = &self.key else
+            {
+                return Err (String::from ("There is nowhere to keep a signing key, set PAYMENTS_DATA_DIR"));
+            };
+            if let Ok (text) = std::fs::read_to_string (file)
+            {
+                return Self::unhex::<32> (text.trim ())
+                    .map (|seed| ed25519_dalek::SigningKey::from_bytes (&seed))
+                    .ok_or_else (|| format! ("{} is not a signing key", file.display ()));
+            }
+            let mut seed = [0u8; 32];
+            getrandom::getrandom (&mut seed).map_err (|e| format! ("Unable to make a signing key: {e}"))?;
+            let mut options = std::fs::OpenOptions::new ();
+            options.write (true).create_new (true);
+            // only whoever made the key can read it
+            #[cfg (unix)]
+            std::os::unix::fs::OpenOptionsExt::mode (&mut options, 0o600);
+            use std::io::Write;
+            file.parent ()
+                .map_or (Ok (()), std::fs::create_dir_all)
+                .and_then (|_| options.open (file))
+                .and_then (|mut f| f.write_all (Self::hex (&seed).as_bytes ()))
+                .map_err (|_| format! ("Unable to open file {}", file.display ()))?;
+            Ok (ed25519_dalek::SigningKey::from_bytes (&seed))
+        }
+
+        // how a signature is written into a file of each kind it can sign, so
+        // the file still opens like it did: a comment at the end of a PDF or
+        // HTML file, and the last field of a JSON object. the comma is left
+        // out when the object is otherwise empty
+        fn signature_text (file: &str, signature: &str) -> Result<String, String>
+        {
+            let kind = std::path::Path::new (file).extension ().and_then (|e| e.to_str ()).unwrap_or ("").to_lowercase ();
+            match kind.as_str ()
+            {
+                "pdf" => Ok (format! ("\n%payments-signature {signature}\n")),
+                "html" | "htm" => Ok (format! ("\n<!-- payments-signature {signature} -->\n")),
+                "json" => Ok (format! (",\"payments-signature\": \"{signature}\"")),
+                _ => Err (format! ("{file} can't be signed, only PDF, HTML and JSON files can")),
+            }
+        }
+
+        // the file with a signature of its contents put in, which is the
+        // public key followed by the signature itself
+        fn signed (&self, file: &str, mut bytes: Vec<u8>) -> Result<Vec<u8>, String>
+        {
+            use ed25519_dalek::Signer;
+            let key = self.signing_key ()?;
+            let signature = key.sign (&bytes);
+            let public = Self::hex (key.verifying_key ().as_bytes ());
+            let text = Self::signature_text (file, &format! ("ed25519 {public} {}", Self::hex (&signature.to_bytes ())))?;
+            let (at, text) = match text.strip_prefix (',')
+            {
+                Some (field) =>
+                {
+                    let end = bytes.iter ().rposition (|b| *b == b'}').ok_or_else (|| format! ("{file} is not a JSON object"))?;
+                    let empty = bytes[..end].iter ().rev ().find (|b| !b.is_ascii_whitespace ()) == Some (&b'{');
+                    (end, if empty { String::from (field) } else { text })
+                },
+                None => (bytes.len (), text),
+            };
+            bytes.splice (at..at, text.into_bytes ());
+            say! (self, "signed {file} with key {public}");
+            Ok (bytes)
+        }
+
+        // checks that a signed file wasn't changed since it was signed, and
+        // if a KEY is given, that it was signed with that key
+        fn verify (&mut self, args: &[&str]) -> PaymentResult
+        {
+            use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+            let (file, expected) = match args
+            {
+                [file] => (*file, None),
+                [file, key] => (*file, Some (*key)),
+                [] => return Err (String::from ("Not enough arguments")),
+                _ => return Err (String::from ("Too many arguments")),
+            };
+            let bytes = std::fs::read (file).map_err (|_| format! ("Unable to open file {}", file))?;
+            let found = regex::bytes::Regex::new ("payments-signature\"?:? \"?ed25519 ([0-9a-f]{64}) ([0-9a-f]{128})").unwrap ()
+                .captures_iter (&bytes)
+                .last ()
+                .map (|c| (String::from_utf8_lossy (&c[1]).into_owned (), String::from_utf8_lossy (&c[2]).into_owned ()));
+            let Some ((public, signature)) = found else
+            {
+                return Err (format! ("{file} isn't signed"));
+            };
+            // the file as it was signed is the file without its signature
+            let text = Self::signature_text (file, &format! ("ed25519 {public} {signature}"))?;
+            let text = text.as_bytes ();
+            let mut unsigned = bytes.clone ();
+            for text in [text, text.strip_prefix (b",").unwrap_or (text)]
+            {
+                if let Some (at) = bytes.windows (text.len ()).rposition (|w| w == text)
+                {
+                    unsigned.drain (at..at + text.len ());
+                    break;
+                }
+            }
+            let key = Self::unhex::<32> (&public).and_then (|k| VerifyingKey::from_bytes (&k).ok ());
+            let signature = Self::unhex::<64> (&signature).map (|s| Signature::from_bytes (&s));
+            match (key, signature)
+            {
+                (Some (key), Some (signature)) if key.verify (&unsigned, &signature).is_ok () => (),
+                _ => return Err (format! ("{file} was changed since it was signed")),
+            }
+            match expected
+            {
+                Some (expected) if expected != public => Err (format! ("{file} wasn't signed with {expected}, it was signed with {public}")),
+                _ =>
+                {
+                    say! (self, "{file} is as it was signed with key {public}");
+                    Ok (())
+                },
+            }
+        }
+
+        fn hex (bytes: &[u8]) -> String
+        {
+            bytes.iter ().map (|b| format! ("{b:02x}")).collect ()
+        }
+
+        fn unhex<const N: usize> (text: &str) -> Option<[u8; N]>
+        {
+            let mut bytes = [0u8; N];
+            if text.len () != 2 * N
+            {
+                return None;
+            }
+            for (i, byte) in bytes.iter_mut ().enumerate ()
+            {
+                *byte = u8::from_str_radix (text.get (2 * i..2 * i + 2)?, 16).ok ()?;
+            }
+            Some (bytes)
+        }
+
+        // writes each member with an email an email with their statement as
+        // a PDF and, if they owe anything, a calendar reminder to settle by
+        // the due date. the emails go to DIR, and with --send they are also
+        // handed to sendmail
+        fn notify (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let ["statements", args @ ..] = args else
+            {
+                return Err (String::from ("notify must be called with statements"));
+            };
+            let mut due = None;
+            let mut send = false;
+            let mut dir = None;
+            let mut iter = args.iter ();
+            while let Some (arg) = iter.next ()
+            {
+                match *arg
+                {
+                    "--due" => due = Some (self.date_arg (&[iter.next ().ok_or ("--due must be followed by a date")?])?),
+                    "--send" => send = true,
+                    d if dir.is_none () && !d.starts_with ("--") => dir = Some (d),
+                    a => return Err (format! ("{a} is not a notify option, use --due DATE, --send or a directory")),
+                }
+            }
+            let Some (due) = due else
+            {
+                return Err (String::from ("notify statements must be given the date to settle by with --due DATE"));
+            };
+            let dir = dir.unwrap_or (".");
+            std::fs::create_dir_all (dir).map_err (|_| format! ("Unable to create directory {dir}"))?;
+            self.calculate ();
+            let now = self.now ();
+            let stamp = format! ("{}T{:02}{:02}{:02}Z", clock::date (now).replace ('-', ""), now / 3600 % 24, now / 60 % 60, now % 60);
+            let from = self.invoicing.name.clone ().unwrap_or_else (|| String::from ("payments"));
+            let names = self.participants.keys ().cloned ().collect::<Vec<String>> ();
+            for name in names
+            {
+                let Some (email) = self.participants[&name].email.clone () else
+                {
+                    self.warnings.push (format! ("{name} has no email, no statement was written for them"));
+                    continue;
+                };
+                let (pdf, total) = self.statement_pdf (&name, "Statement");
+                let boundary = format! ("statement-{}", self.ids.0.next_id ());
+                let mut text = if total > Money::ZERO
+                {
+                    format! ("Hello {name},\r\n\r\nyou owe {} in total, please settle it by {}. Your statement is attached.\r\n"
+                             , self.settings.show (total), self.settings.show_date (&due))
+                }
+                else
+                {
+                    format! ("Hello {name},\r\n\r\nyou have nothing to pay. Your statement is attached.\r\n")
+                };
+                text.push_str (&format! ("\r\n{from}\r\n"));
+                let mut mail = format! ("To: {name} <{email}>\r\nSubject: Your statement from {from}\r\nMIME-Version: 1.0\r\n\
+                                         Content-Type: multipart/mixed; boundary=\"{boundary}\"\r\n\r\n\
+                                         --{boundary}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{text}");
+                let mut attach = |file: &str, kind: &str, bytes: &[u8]|
+                {
+                    mail.push_str (&format! ("\r\n--{boundary}\r\nContent-Type: {kind}\r\nContent-Transfer-Encoding: base64\r\n\
+                                              Content-Disposition: attachment; filename=\"{file}\"\r\n\r\n{}\r\n", Self::base64 (bytes)));
+                };
+                attach ("statement.pdf", "application/pdf", &pdf);
+                if total > Money::ZERO
+                {
+                    let event = format! ("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//payments//statements//EN\r\nBEGIN:VEVENT\r\n\
+                                          UID:{}\r\nDTSTAMP:{stamp}\r\nDTSTART;VALUE=DATE:{}\r\nSUMMARY:Pay {} to {from}\r\n\
+                                          BEGIN:VALARM\r\nACTION:DISPLAY\r\nDESCRIPTION:Pay {} to {from}\r\nTRIGGER:-P1D\r\nEND:VALARM\r\n\
+                                          END:VEVENT\r\nEND:VCALENDAR\r\n"
+                                         , self.ids.0.next_id (), due.replace ('-', ""), self.settings.show (total), self.settings.show (total));
+                    attach ("settle.ics", "text/calendar; charset=utf-8", event.as_bytes ());
+                }
+                mail.push_str (&format! ("\r\n--{boundary}--\r\n"));
+                let file = std::path::Path::new (dir).join (format! ("statement-{name}.eml"));
+                std::fs::write (&file, &mail).map_err (|_| format! ("Unable to open file {}", file.display ()))?;
+                say! (self, "wrote statement for {name} to {}", file.display ());
+                if send
+                {
+                    Self::sendmail (&mail).map_err (|e| format! ("Unable to send the statement to {name}: {e}"))?;
+                    say! (self, "sent statement to {email}");
+                }
+            }
+            Ok (())
+        }
+
+        // hands a whole email to the system's sendmail, which reads who it's
+        // for from its headers
+        fn sendmail (mail: &str) -> Result<(), String>
+        {
+            use std::io::Write;
+            let mut child = std::process::Command::new ("sendmail")
+                .arg ("-t")
+                .stdin (std::process::Stdio::piped ())
+                .spawn ()
+                .map_err (|e| e.to_string ())?;
+            child.stdin.take ().ok_or ("sendmail has no input")?.write_all (mail.as_bytes ()).map_err (|e| e.to_string ())?;
+            match child.wait ().map_err (|e| e.to_string ())?
+            {
+                status if status.success () => Ok (()),
+                status => Err (format! ("sendmail failed with {status}")),
+            }
+        }
+
+        // base64 in lines of 76 characters, the way email attachments are
+        // written
+        fn base64 (bytes: &[u8]) -> String
+        {
+            const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+            let mut out = String::new ();
+            for (i, chunk) in bytes.chunks (3).enumerate ()
+            {
+                if i > 0 && i % 19 == 0
+                {
+                    out.push_str ("\r\n");
+                }
+                let n = chunk.iter ().enumerate ().fold (0u32, |n, (j, b)| n | (*b as u32) << (16 - 8 * j));
+                for j in 0..4
+                {
+                    out.push (if j <= chunk.len () { DIGITS[(n >> (18 - 6 * j) & 63) as usize] as char } else { '=' });
+                }
+            }
+            out
+        }
+
+        // a PDF of lines of plain text in a fixed width font at the given
+        // sizes, on as many A4 pages as it takes. characters the standard
+        // fonts don't have come out as ?
+        fn text_pdf (lines: &[(f32, String)]) -> Vec<u8>
+        {
+            use pdf_writer::{Content, Name, Pdf, Rect, Ref, Str};
+            let font = Name (b"F1");
+            let catalog = Ref::new (1);
+            let tree = Ref::new (2);
+            let font_id = Ref::new (3);
+            let mut pages = Vec::new ();
+            let mut page = Vec::new ();
+            let mut y = 800f32;
+            for (size, text) in lines
+            {
+                let height = size * 1.4;
+                if y - height < 50f32
+                {
+                    pages.push (std::mem::take (&mut page));
+                    y = 800f32;
+                }
+                y -= height;
+                page.push ((*size, y, text.chars ().map (|c| if c.is_ascii () { c } else { '?' }).collect::<String> ()));
+            }
+            pages.push (page);
+            let mut pdf = Pdf::new ();
+            pdf.catalog (catalog).pages (tree);
+            let ids = (0..pages.len () as i32).map (|i| (Ref::new (4 + 2 * i), Ref::new (5 + 2 * i))).collect::<Vec<(Ref, Ref)>> ();
+            pdf.pages (tree).kids (ids.iter ().map (|i| i.0)).count (pages.len () as i32);
+            for ((page_id, content_id), lines) in ids.into_iter ().zip (pages)
+            {
+                let mut page = pdf.page (page_id);
+                page.media_box (Rect::new (0f32, 0f32, 595f32, 842f32));
+                page.parent (tree);
+                page.contents (content_id);
+                page.resources ().fonts ().pair (font, font_id);
+                drop (page);
+                let mut content = Content::new ();
+                for (size, y, text) in lines
+                {
+                    content.begin_text ();
+                    content.set_font (font, size);
+                    content.next_line (50f32, y);
+                    content.show (Str (text.as_bytes ()));
+                    content.end_text ();
+                }
+                pdf.stream (content_id, &content.finish ());
+            }
+            pdf.type1_font (font_id).base_font (Name (b"Courier"));
+            pdf.finish ()
+        }
+
+        // prints the transfers that would settle every balance. with
+        // --by-currency everyone pays back in the currency they were lent,
+        // and with --tag only what the tasks with that tag ran up is settled.
+        // with --apply they are recorded as payments, and what happens after
+        // a whole settlement can be reported on its own with report
+        // --since-last-settlement
+        fn settle (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let mut apply = false;
+            let mut by_currency = false;
+            let mut tag = None;
+            let mut iter = args.iter ();
+            while let Some (arg) = iter.next ()
+            {
+                match *arg
+                {
+                    "--apply" => apply = true,
+                    "--by-currency" => by_currency = true,
+                    "--tag" => tag = Some (String::from (*iter.next ().ok_or ("--tag must be followed by a tag")?)),
+                    a => return Err (format! ("{a} is not a settle option, use --by-currency, --tag TAG or --apply")),
+                }
+            }
+            let mut tagged = match &tag
+            {
+                Some (tag) => Some (self.tagged (tag)?),
+                None => None,
+            };
+            let plan = {
+                let scope = match &mut tagged
+                {
+                    Some (tagged) => tagged,
+                    None => &mut *self,
+                };
+                if by_currency
+                {
+                    scope.settlement_by_currency ()
+                }
+                else
+                {
+                    scope.settlement ().into_iter ().map (|(from, to, amount)| (from, to, amount, None)).collect ()
+                }
+            };
+            let date = clock::date (self.now ());
+            for (from, to, amount, currency) in plan
+            {
+                match &currency
+                {
+                    Some (code) => say! (self, "{from} pays {to} {amount} {code}"),
+                    None => say! (self, "{from} pays {to} {}", self.settings.show (amount)),
+                }
+                if apply
+                {
+                    self.transfers.push (Transfer
+                                         {
+                                             from,
+                                             to,
+                                             amount,
+                                             currency,
+                                             memo: Some (String::from ("settlement")),
+                                             date: Some (date.clone ()),
+                                             task: None,
+                                             tag: tag.clone (),
+                                             id: Some (self.ids.0.next_id ()),
+                                         });
+                }
+            }
+            // settling one tag leaves the rest of the ledger running
+            if apply && tag.is_none ()
+            {
+                self.settled = Some (Settled
+                                     {
+                                         date,
+                                         tasks: self.tasks.keys ().cloned ().collect (),
+                                         transfers: self.transfers.len (),
+                                     });
+            }
+            Ok (())
+        }
+
+        // the cash box. in and out record money put in or taken out, count
+        // checks what is really in it against what should be
+        fn cashbox (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (sign, amount, rest) = match args
+            {
+                [] =>
+                {
+                    self.print_cashbox ();
+                    return Ok (());
+                },
+                ["count", amount] =>
+                {
+                    let counted = Money::input (amount).map_err (|e| format! ("Invalid amount: {e}"))?;
+                    let expected = self.cashbox.balance ();
+                    if counted < expected
+                    {
+                        self.warnings.push (format! ("the cash box is {} short, it should have {}"
+                                                     , self.settings.show (expected - counted)
+                                                     , self.settings.show (expected)));
+                    }
+                    else if counted > expected
+                    {
+                        self.warnings.push (format! ("the cash box has {} more than it should, it should have {}"
+                                                     , self.settings.show (counted - expected)
+                                                     , self.settings.show (expected)));
+                    }
+                    self.cashbox.counted = Some ((clock::date (self.now ()), counted));
+                    return Ok (());
+                },
+                ["in", amount, rest @ ..] => (1, *amount, rest),
+                ["out", amount, rest @ ..] => (-1, *amount, rest),
+                _ => return Err (String::from ("cashbox must be called with in, out or count and an amount")),
+            };
+            let confirmed = rest.contains (&"--confirm");
+            let mut rest = rest.iter ().filter (|a| **a != "--confirm").copied ().collect::<Vec<&str>> ();
+            let from = match rest.iter ().position (|a| *a == "--from")
+            {
+                _ if sign < 0 && rest.contains (&"--from") => return Err (String::from ("Money taken out isn't from anyone")),
+                Some (i) if i + 1 < rest.len () =>
+                {
+                    let name = self.resolve (rest[i + 1])?;
+                    if !self.participants.contains_key (&name)
+                    {
+                        return Err (format! ("No participant named {name} exists"));
+                    }
+                    rest.drain (i..i + 2);
+                    Some (name)
+                },
+                Some (_) => return Err (String::from ("--from must be followed by a participant")),
+                None => None,
+            };
+            let amount = match Money::input (amount)
+            {
+                Ok (a) if a > Money::ZERO => a,
+                Ok (a) => return Err (format! ("{a} is not an amount that can go in or out")),
+                Err (e) => return Err (format! ("Invalid amount: {e}")),
+            };
+            self.check_amount (amount, confirmed)?;
+            if sign < 0 && amount > self.cashbox.balance ()
+            {
+                self.warnings.push (format! ("the cash box should only have {} in it", self.settings.show (self.cashbox.balance ())));
+            }
+            let memo = rest.join (" ");
+            let memo = memo.strip_prefix ('"').and_then (|m| m.strip_suffix ('"')).unwrap_or (&memo);
+            self.cashbox.entries.push (CashEntry
+                                       {
+                                           amount: amount * sign,
+                                           from,
+                                           memo: Some (String::from (memo)).filter (|m| !m.is_empty ()),
+                                           date: clock::date (self.now ()),
+                                       });
+            Ok (())
+        }
+
+        // membership dues, as in dues 15/month all or dues 15/month alice
+        // bob. dues paid NAME [AMOUNT] records a payment, dues shows who is
+        // behind and dues remind writes them a reminder
+        fn dues (&mut self, args: &[&str]) -> PaymentResult
+        {
+            match args
+            {
+                [] => return self.print_dues (false),
+                ["remind"] => return self.print_dues (true),
+                ["none"] =>
+                {
+                    self.dues = None;
+                    return Ok (());
+                },
+                ["paid", name, amount @ ..] =>
+                {
+                    let name = self.resolve (name)?;
+                    let Some (dues) = &self.dues else
+                    {
+                        return Err (String::from ("There are no dues, set them with dues AMOUNT/PERIOD all"));
+                    };
+                    if !self.dues_members ().contains (&name)
+                    {
+                        return Err (format! ("{name} doesn't pay dues"));
+                    }
+                    let amount = match amount
+                    {
+                        [] => dues.amount,
+                        [a] => match Money::input (a)
+                        {
+                            Ok (a) if a > Money::ZERO => a,
+                            Ok (a) => return Err (format! ("{a} can't be paid")),
+                            Err (e) => return Err (format! ("Invalid amount: {e}")),
+                        },
+                        _ => return Err (String::from ("Too many arguments")),
+                    };
+                    let date = clock::date (self.now ());
+                    *self.dues.as_mut ().unwrap ().paid.entry (name.clone ()).or_insert (Money::ZERO) += amount;
+                    self.cashbox.entries.push (CashEntry
+                                               {
+                                                   amount,
+                                                   from: Some (name),
+                                                   memo: Some (String::from ("dues")),
+                                                   date,
+                                               });
+                    return Ok (());
+                },
+                _ => (),
+            }
+            let [rate, members @ ..] = args else
+            {
+                return Err (String::from ("Not enough arguments"));
+            };
+            let Some ((amount, period)) = rate.split_once ('/') else
+            {
+                return Err (format! ("{rate} is not a rate, give one like 15/month"));
+            };
+            let amount = match Money::input (amount)
+            {
+                Ok (a) if a > Money::ZERO => a,
+                Ok (a) => return Err (format! ("{a} can't be owed as dues")),
+                Err (e) => return Err (format! ("Invalid amount: {e}")),
+            };
+            let every = match period
+            {
+                "day" => Every::Days (1),
+                "week" => Every::Days (7),
+                "month" => Every::Months (1),
+                "year" => Every::Months (12),
+                p => return Err (format! ("{p} is not a period, use day, week, month or year")),
+            };
+            let members = match members
+            {
+                [] => return Err (String::from ("Say who pays the dues, or all for everyone")),
+                ["all"] => None,
+                names =>
+                {
+                    let mut members = BTreeSet::new ();
+                    for name in names
+                    {
+                        let name = self.resolve (name)?;
+                        if !self.participants.contains_key (&name)
+                        {
+                            return Err (format! ("No participant named {name} exists"));
+                        }
+                        members.insert (name);
+                    }
+                    Some (members)
+                },
+            };
+            // changing the rate keeps what was already paid
+            let (start, paid) = match self.dues.take ()
+            {
+                Some (dues) => (dues.start, dues.paid),
+                None => (clock::date (self.now ()), BTreeMap::new ()),
+            };
+            self.dues = Some (Dues { amount, every, start, members, paid });
+            Ok (())
+        }
+
+        fn dues_members (&self) -> BTreeSet<String>
+        {
+            match self.dues.as_ref ().and_then (|d| d.members.as_ref ())
+            {
+                Some (members) => members.clone (),
+                None => self.participants.keys ().cloned ().collect (),
+            }
+        }
+
+        // what every member has paid in dues and what they should have by now,
+        // a period being owed from the day it starts
+        fn dues_status (&self) -> Vec<(String, Money, Money)>
+        {
+            let Some (dues) = &self.dues else
+            {
+                return Vec::new ();
+            };
+            let today = clock::date (self.now ());
+            let periods = (0..).take_while (|i| dues.every.after (&dues.start, *i) <= today).count ();
+            self.dues_members ().into_iter ()
+                .map (|name|
+                      {
+                          let paid = dues.paid.get (&name).copied ().unwrap_or (Money::ZERO);
+                          (name, paid, dues.amount * periods as i64)
+                      })
+                .collect ()
+        }
+
+        fn print_dues (&self, remind: bool) -> PaymentResult
+        {
+            let Some (dues) = &self.dues else
+            {
+                return Err (String::from ("There are no dues, set them with dues AMOUNT/PERIOD all"));
+            };
+            if !remind
+            {
+                say! (self, "{} {} since {}", self.settings.show (dues.amount), dues.every, self.settings.show_date (&dues.start));
+            }
+            for (name, paid, owed) in self.dues_status ()
+            {
+                if !remind
+                {
+                    say! (self, "{name}: paid {} of {}", self.settings.show (paid), self.settings.show (owed));
+                }
+                else if paid < owed
+                {
+                    // members who were removed since aren't reminded
+                    let Some (part) = self.participants.get (&name) else
+                    {
+                        continue;
+                    };
+                    let to = match &part.email
+                    {
+                        Some (email) => format! ("{name} <{email}>"),
+                        None => name.clone (),
+                    };
+                    say! (self, "to {to}: your dues are {} behind, {} of {} paid so far"
+                              , self.settings.show (owed - paid)
+                              , self.settings.show (paid)
+                              , self.settings.show (owed));
+                }
+            }
+            Ok (())
+        }
+
+        fn print_cashbox (&self)
+        {
+            for entry in &self.cashbox.entries
+            {
+                let mut line = format! ("{} {}{}", self.settings.show_date (&entry.date), if entry.amount > Money::ZERO { "+" } else { "" }
+                                        , self.settings.show (entry.amount));
+                if let Some (from) = &entry.from
+                {
+                    line.push_str (&format! (" from {from}"));
+                }
+                match &entry.memo
+                {
+                    Some (memo) => say! (self, "{line}: {memo}"),
+                    None => say! (self, "{line}"),
+                }
+            }
+            say! (self, "in the box: {}", self.settings.show (self.cashbox.balance ()));
+            if let Some ((date, counted)) = &self.cashbox.counted
+            {
+                say! (self, "counted {} on {}", self.settings.show (*counted), self.settings.show_date (date));
+            }
+        }
+
+        // an amount over the ledger's amount limit is more likely 4500 typed for
+        // 45.00 than a real cost, so it has to be confirmed with --confirm or
+        // by the confirmer before it's recorded
+        fn check_amount (&self, amount: Money, confirmed: bool) -> PaymentResult
+        {
+            let Some (max) = self.limits.amount else
+            {
+                return Ok (());
+            };
+            if confirmed || amount.max (-amount) <= max
+            {
+                return Ok (());
+            }
+            match &self.confirm
+            {
+                Some (confirm) if (confirm.0) (&format! ("{amount} is more than {max}, is that right?")) => Ok (()),
+                Some (_) => Err (String::from ("Nothing was changed")),
+                None => Err (format! ("{amount} is more than the limit of {max}, add --confirm if that's right")),
+            }
+        }
+
+        // makes sure adding this many participants and tasks stays within the
+        // ledger's limits
+        fn check_limits (&self, participants: usize, tasks: usize) -> PaymentResult
+        {
+            if let Some (max) = self.limits.participants
+            {
+                if participants > 0 && self.participants.len () + participants > max
+                {
+                    return Err (format! ("This ledger is limited to {max} participants, try compact"));
+                }
+            }
+            if let Some (max) = self.limits.tasks
+            {
+                if tasks > 0 && self.tasks.len () + tasks > max
+                {
+                    return Err (format! ("This ledger is limited to {max} tasks, try compact"));
+                }
+            }
+            Ok (())
+        }
+
+        fn limit (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (kind, value) = match args
+            {
+                [kind, value] => (*kind, *value),
+                [] | [_] => return Err (String::from ("Not enough arguments")),
+                _ => return Err (String::from ("Too many arguments")),
+            };
+            if kind == "amount"
+            {
+                self.limits.amount = match value
+                {
+                    "none" => None,
+                    v => match Money::parse (v)
+                    {
+                        Ok (m) if m > Money::ZERO => Some (m),
+                        _ => return Err (format! ("{v} is not a valid limit")),
+                    },
+                };
+                return Ok (());
+            }
+            let value = match value
+            {
+                "none" => None,
+                v => match v.parse::<usize> ()
+                {
+                    Ok (n) => Some (n),
+                    Err (_) => return Err (format! ("{v} is not a valid limit")),
+                },
+            };
+            match kind
+            {
+                "participants" => self.limits.participants = value,
+                "tasks" => self.limits.tasks = value,
+                k => return Err (format! ("{k} can't be limited, use participants, tasks or amount")),
+            }
+            Ok (())
+        }
+
+        // sets the interest charged on old debts, as in interest 2% monthly
+        // after 30, or none to charge none
+        fn set_interest (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (rate, period, grace) = match args
+            {
+                ["none"] =>
+                {
+                    self.interest = None;
+                    return Ok (());
+                },
+                [rate, period] => (*rate, *period, "0"),
+                [rate, period, "after", grace] => (*rate, *period, *grace),
+                _ => return Err (String::from ("interest must be called with a rate, a period and optionally after DAYS")),
+            };
+            let rate = Interest::parse_rate (rate)?;
+            let period = match period
+            {
+                "daily" => 1,
+                "weekly" => 7,
+                "monthly" => 30,
+                "yearly" => 365,
+                p => match p.parse::<u32> ()
+                {
+                    Ok (days) if days > 0 => days,
+                    _ => return Err (format! ("{p} is not a period, use daily, weekly, monthly, yearly or a number of days")),
+                },
+            };
+            let Ok (grace) = grace.parse::<u32> () else
+            {
+                return Err (format! ("{grace} is not a number of days"));
+            };
+            self.interest = Some (Interest { rate, period, grace });
+            Ok (())
+        }
+
+        // what a participant was charged and when, their shares of tasks and
+        // the payments they got
+        fn dated_debts (&self, name: &str) -> Vec<(Option<&str>, Money)>
+        {
+            let mut debts = self.participants[name].tasks.iter ()
+                .map (|t| &self.tasks[t])
+                .map (|t| (t.date.as_deref (), self.charged_share (t, name)))
+                .collect::<Vec<(Option<&str>, Money)>> ();
+            debts.extend (self.transfers.iter ()
+                          .filter (|t| t.to == name)
+                          .map (|t| (t.date.as_deref (), self.transferred (t))));
+            debts
+        }
+
+        // adds interest to the balance of everyone who has owed money for
+        // longer than the grace period. what they owe is taken to be their
+        // latest debts, the older ones being paid off first. the interest is
+        // owed to whoever is owed money, in proportion to how much
+        fn accrue_interest (&mut self)
+        {
+            for part in self.participants.values_mut ()
+            {
+                part.interest = None;
+            }
+            let Some (interest) = self.interest else
+            {
+                return;
+            };
+            let today = clock::date (self.now ());
+            let mut accrued = BTreeMap::new ();
+            for part in self.participants.values ()
+            {
+                let mut left = part.sum.unwrap_or (Money::ZERO);
+                let mut debts = self.dated_debts (&part.name);
+                // newest first, and undated ones last
+                debts.sort_by (|a, b| b.0.cmp (&a.0));
+                let mut total = Money::ZERO;
+                for (date, amount) in debts
+                {
+                    if left <= Money::ZERO
+                    {
+                        break;
+                    }
+                    let owed = amount.min (left);
+                    left -= owed;
+                    let Some (date) = date else
+                    {
+                        continue;
+                    };
+                    let periods = (clock::days_between (date, &today) - interest.grace as i64).max (0) / interest.period as i64;
+                    total += Money::from_cents ((owed.cents () as i128 * interest.rate as i128 * periods as i128 / 10000) as i64);
+                }
+                if total > Money::ZERO
+                {
+                    accrued.insert (part.name.clone (), total);
+                }
+            }
+            let creditors = self.participants.values ()
+                .filter_map (|p| p.sum.filter (|s| *s < Money::ZERO).map (|s| (p.name.clone (), -s)))
+                .collect::<Vec<(String, Money)>> ();
+            let owed = creditors.iter ().map (|c| c.1).sum::<Money> ();
+            let total = accrued.values ().sum::<Money> ();
+            if owed == Money::ZERO || total == Money::ZERO
+            {
+                return;
+            }
+            let mut shares = creditors.iter ()
+                .map (|(name, amount)| (name, Money::from_cents ((total.cents () as i128 * amount.cents () as i128 / owed.cents () as i128) as i64)))
+                .collect::<Vec<(&String, Money)>> ();
+            // the cents that don't divide go to the first creditors by name
+            let extra = total - shares.iter ().map (|s| s.1).sum::<Money> ();
+            for share in shares.iter_mut ().take (extra.cents () as usize)
+            {
+                share.1 += Money::from_cents (1);
+            }
+            for (name, share) in shares.into_iter ().map (|(n, s)| (n, -s)).chain (accrued.iter ().map (|(n, a)| (n, *a)))
+            {
+                let part = self.participants.get_mut (name).unwrap ();
+                part.sum = part.sum.map (|s| s + share);
+                part.interest = Some (share);
+            }
+        }
+
+        fn rule (&mut self, args: &[&str]) -> PaymentResult
+        {
+            match args
+            {
+                ["remove", n] => match n.parse::<usize> ()
+                {
+                    Ok (n) if n >= 1 && n <= self.rules.len () =>
+                    {
+                        self.rules.remove (n - 1);
+                    },
+                    _ => return Err (format! ("There is no rule {n}")),
+                },
+                _ => self.rules.push (Rule::parse (args)?),
+            }
+            Ok (())
+        }
+
+        fn print_rules (&self)
+        {
+            let violations = self.violations ();
+            for (i, rule) in self.rules.iter ().enumerate ()
+            {
+                say! (self, "{}. {}", i + 1, rule);
+                for violation in violations.iter ().filter (|v| v.rule == i + 1)
+                {
+                    say! (self, "   broken: {}", violation.message);
+                }
+            }
+        }
+
+        fn print_paths (&self)
+        {
+            let show = |dir: Option<std::path::PathBuf>| match dir
+            {
+                Some (d) => d.display ().to_string (),
+                None => String::from ("unknown, no home directory is set"),
+            };
+            say! (self, "config: {}", show (crate::paths::config_dir ()));
+            say! (self, "data: {}", show (crate::paths::data_dir ()));
+            for name in ["ledgers", "backups", "history", "attachments"]
+            {
+                say! (self, "  {name}: {}", show (crate::paths::data_subdir (name)));
+            }
+            say! (self, "cache: {}", show (crate::paths::cache_dir ()));
+        }
+
+        fn set (&mut self, args: &[&str]) -> PaymentResult
+        {
+            match args
+            {
+                [key, value] => self.settings.set (key, value),
+                [] | [_] => Err (String::from ("Not enough arguments")),
+                _ => Err (String::from ("Too many arguments")),
+            }
+        }
+
+        fn rate (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (code, value) = match args
+            {
+                [code, value] => (*code, *value),
+                [] | [_] => return Err (String::from ("Not enough arguments")),
+                _ => return Err (String::from ("Too many arguments")),
+            };
+            if code == "base"
+            {
+                return self.set_base_currency (&Currencies::code (value)?);
+            }
+            let code = Currencies::code (code)?;
+            if self.currencies.base.as_ref () == Some (&code)
+            {
+                return Err (format! ("{code} is the base currency"));
+            }
+            if value == "none"
+            {
+                if let Some (task) = self.tasks.values ().find (|t| t.currency.as_ref () == Some (&code))
+                {
+                    return Err (format! ("{} was paid in {code}, so its rate can't be removed", task.name));
+                }
+                if self.transfers.iter ().any (|t| t.currency.as_ref () == Some (&code))
+                {
+                    return Err (format! ("A payment was made in {code}, so its rate can't be removed"));
+                }
+                self.currencies.rates.remove (&code);
+                return Ok (());
+            }
+            match value.parse::<f64> ()
+            {
+                Ok (rate) if rate.is_finite () && rate > 0f64 =>
+                {
+                    self.currencies.rates.insert (code, rate);
+                    Ok (())
+                },
+                _ => Err (format! ("{value} is not a valid exchange rate")),
+            }
+        }
+
+        // makes another currency the one balances are worked out in. any rates
+        // already given are turned into rates against the new base currency,
+        // which needs a rate of its own for that
+        fn set_base_currency (&mut self, code: &str) -> PaymentResult
+        {
+            if self.currencies.base.as_deref () == Some (code)
+            {
+                return Ok (());
+            }
+            if self.currencies.rates.is_empty ()
+            {
+                self.currencies.base = Some (String::from (code));
+                return Ok (());
+            }
+            let Some (old) = self.currencies.base.clone () else
+            {
+                return Err (String::from ("Name the current base currency before changing it"));
+            };
+            let Some (rate) = self.currencies.rates.remove (code) else
+            {
+                return Err (format! ("No exchange rate for {code}, add one with rate {code} AMOUNT"));
+            };
+            self.currencies.base = Some (String::from (code));
+            for other in self.currencies.rates.values_mut ()
+            {
+                *other /= rate;
+            }
+            self.currencies.rates.insert (old.clone (), 1f64 / rate);
+            let rebase = |currency: &mut Option<String>| *currency = match currency.take ()
+            {
+                Some (c) if c == code => None,
+                None => Some (old.clone ()),
+                c => c,
+            };
+            for task in self.tasks.values_mut ()
+            {
+                rebase (&mut task.currency);
+            }
+            for transfer in &mut self.transfers
+            {
+                rebase (&mut transfer.currency);
+            }
+            Ok (())
+        }
+
+        // rewrites everything paid in one currency into another at the given
+        // rate, as in convert USD EUR 0.92. converting the ledger's own
+        // currency converts every amount in it, payments and all, and the
+        // ledger is in the new currency from then on. nothing is changed
+        // unless all of it can be
+        fn convert (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [from, to, rate] = args else
+            {
+                return Err (String::from ("convert must be called with two currencies and a rate"));
+            };
+            let (from, to) = (Currencies::code (from)?, Currencies::code (to)?);
+            if from == to
+            {
+                return Err (format! ("{from} is already in {to}"));
+            }
+            let rate = match rate.parse::<f64> ()
+            {
+                Ok (r) if r.is_finite () && r > 0f64 => r,
+                _ => return Err (format! ("{rate} is not a valid exchange rate")),
+            };
+            let mut converted = self.clone ();
+            if !converted.convert_currency (&from, &to, rate)?
+            {
+                return Err (format! ("Nothing was paid in {from}"));
+            }
+            *self = converted;
+            Ok (())
+        }
+
+        // does the converting for convert, here and in every sub-ledger, each
+        // going by its own currency. tasks include archived ones and the ones
+        // recurring tasks are copied from. returns whether anything was in
+        // the currency converted from
+        fn convert_currency (&mut self, from: &str, to: &str, rate: f64) -> Result<bool, String>
+        {
+            let unit = Currencies::minor_unit (to);
+            let convert = move |m: Money| Money::from_cents ((m.cents () as f64 * rate / unit as f64).round () as i64 * unit);
+            let base = self.currencies.base.clone ().or (self.settings.currency.clone ());
+            // the ledger's own currency, or only what was paid in it
+            let whole = base.as_deref () == Some (from);
+            let paid_in = self.tasks.values ()
+                .chain (self.archived.values ())
+                .chain (self.recurring.values ().map (|r| &r.task))
+                .map (|t| &t.currency)
+                .chain (self.transfers.iter ().map (|t| &t.currency))
+                .any (|c| c.as_deref () == Some (from));
+            let currency = if !whole && paid_in { self.currencies.task_currency (to)? } else { None };
+            for task in self.tasks.values_mut ()
+                .chain (self.archived.values_mut ())
+                .chain (self.recurring.values_mut ().map (|r| &mut r.task))
+            {
+                match task.currency.as_deref ()
+                {
+                    None if whole => task.convert (&convert),
+                    // paid in the new currency, which becomes the ledger's own
+                    Some (c) if whole && c == to => task.currency = None,
+                    Some (c) if !whole && c == from =>
+                    {
+                        task.convert (&convert);
+                        task.currency = currency.clone ();
+                    },
+                    _ => (),
+                }
+            }
+            for transfer in &mut self.transfers
+            {
+                match transfer.currency.as_deref ()
+                {
+                    None if whole => transfer.amount = convert (transfer.amount),
+                    Some (c) if whole && c == to => transfer.currency = None,
+                    Some (c) if !whole && c == from =>
+                    {
+                        transfer.amount = convert (transfer.amount);
+                        transfer.currency = currency.clone ();
+                    },
+                    _ => (),
+                }
+            }
+            let mut changed = whole || paid_in;
+            for sub in self.subledgers.values_mut ()
+            {
+                changed |= sub.convert_currency (from, to, rate)?;
+            }
+            if !whole
+            {
+                return Ok (changed);
+            }
+            for entry in &mut self.cashbox.entries
+            {
+                entry.amount = convert (entry.amount);
+            }
+            if let Some ((_, counted)) = &mut self.cashbox.counted
+            {
+                *counted = convert (*counted);
+            }
+            if let Some (dues) = &mut self.dues
+            {
+                dues.amount = convert (dues.amount);
+                for paid in dues.paid.values_mut ()
+                {
+                    *paid = convert (*paid);
+                }
+            }
+            for part in self.participants.values_mut ()
+            {
+                part.budget = part.budget.map (convert);
+                part.sum = None;
+            }
+            self.limits.amount = self.limits.amount.map (convert);
+            for rule in &mut self.rules
+            {
+                if let Rule::MaxCost { max, .. } = rule
+                {
+                    *max = convert (*max);
+                }
+            }
+            // rates were worth so much of the old currency
+            self.currencies.rates.remove (to);
+            for other in self.currencies.rates.values_mut ()
+            {
+                *other *= rate;
+            }
+            if self.currencies.base.is_some ()
+            {
+                self.currencies.base = Some (String::from (to));
+            }
+            if self.settings.currency.is_some ()
+            {
+                self.settings.currency = Some (String::from (to));
+            }
+            Ok (changed)
+        }
+
+        // drops the tasks and payments from before a day, as in prune --before
+        // 2023-01-01, archived tasks too. with --keep-balances they are
+        // replaced by one opening balance task on that day, so everyone still
+        // owes what they did. tasks from before tasks had dates are kept
+        fn prune (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (before, keep) = match args
+            {
+                ["--before", d] => (self.date_arg (&[d])?, false),
+                ["--before", d, "--keep-balances"] | ["--keep-balances", "--before", d] => (self.date_arg (&[d])?, true),
+                _ => return Err (String::from ("prune must be called with --before DATE and can keep balances with --keep-balances")),
+            };
+            if keep && self.interest.is_some ()
+            {
+                return Err (String::from ("Interest goes by when each debt was run up, so balances with interest can't be kept through prune"));
+            }
+            let old = |date: &Option<String>| date.as_ref ().is_some_and (|d| *d < before);
+            let names = self.tasks.values ().filter (|t| old (&t.date)).map (|t| t.name.clone ()).collect::<Vec<String>> ();
+            if names.is_empty () && !self.transfers.iter ().any (|t| old (&t.date))
+            {
+                return Err (format! ("Nothing was paid before {before}"));
+            }
+            // nothing changes unless all of it can
+            let mut pruned = self.clone ();
+            pruned.calculate ();
+            let balances = pruned.participants.values ()
+                .map (|p| (p.name.clone (), p.sum.unwrap_or (Money::ZERO)))
+                .collect::<BTreeMap<String, Money>> ();
+            for name in &names
+            {
+                pruned.remove_task (name)?;
+            }
+            pruned.archived.retain (|_, t| !old (&t.date));
+            if let Some (settled) = &mut pruned.settled
+            {
+                settled.transfers -= self.transfers.iter ().take (settled.transfers).filter (|t| old (&t.date)).count ();
+                settled.tasks.retain (|t| !names.contains (t));
+            }
+            pruned.transfers.retain (|t| !old (&t.date));
+            for transfer in &mut pruned.transfers
+            {
+                if transfer.task.as_ref ().is_some_and (|t| names.contains (t))
+                {
+                    transfer.task = None;
+                }
+            }
+            if keep
+            {
+                pruned.open_balances (&balances, &before)?;
+            }
+            *self = pruned;
+            Ok (())
+        }
+
+        // adds a task on the given day that charges and credits everyone the
+        // difference between their balance now and what it was
+        fn open_balances (&mut self, balances: &BTreeMap<String, Money>, date: &str) -> PaymentResult
+        {
+            self.calculate ();
+            let mut owed = balances.iter ()
+                .map (|(name, was)| (name.as_str (), *was - self.participants[name].sum.unwrap_or (Money::ZERO)))
+                .filter (|(_, owed)| *owed != Money::ZERO)
+                .collect::<Vec<(&str, Money)>> ();
+            // whoever is owed the most pays for it, and the others who are
+            // owed something paid part of it
+            owed.sort_by (|a, b| a.1.cmp (&b.1).then_with (|| a.0.cmp (b.0)));
+            let Some (&(owner, _)) = owed.first ().filter (|o| o.1 < Money::ZERO) else
+            {
+                return Ok (());
+            };
+            let name = format! ("opening-{date}");
+            if self.tasks.contains_key (&name) || self.participants.contains_key (&name)
+            {
+                return Err (format! ("{name} already exists, so there is nowhere to keep the balances"));
+            }
+            let cost = owed.iter ().map (|o| o.1).filter (|o| *o > Money::ZERO).sum::<Money> ().to_string ();
+            self.pay (&[owner, &name, &cost, "--date", date, "--confirm"])?;
+            for (payer, amount) in owed.iter ().skip (1).filter (|o| o.1 < Money::ZERO)
+            {
+                self.pay (&[payer, &name, &format! ("+{}", -*amount), "--confirm"])?;
+            }
+            let shares = owed.iter ()
+                .map (|(who, amount)| format! ("{who}={}", (*amount).max (Money::ZERO)))
+                .collect::<Vec<String>> ();
+            let mut part = vec! [name.as_str ()];
+            part.extend (shares.iter ().map (String::as_str));
+            self.part (&part)
+        }
+
+        // shrinks the ledger without changing anyone's balance. tasks the owner
+        // took part in alone are dropped since they cancel out, tasks with the
+        // same owner and participants are folded into one, and participants
+        // left with nothing to do with any task are removed
+        fn compact (&mut self)
+        {
+            let solo = self.tasks.values ()
+                .filter (|t| t.participants.len () == 1 && t.participants.contains (&t.owner))
+                .map (|t| t.name.clone ())
+                .collect::<Vec<String>> ();
+            for name in solo
+            {
+                self.remove_task (&name).unwrap ();
+            }
+            type Group = (String, BTreeSet<String>, Option<String>, BTreeMap<String, u32>, BTreeMap<String, u32>, bool
+                          , BTreeSet<String>, Option<u32>, Option<String>);
+            let mut groups: BTreeMap<Group, Vec<String>> = BTreeMap::new ();
+            // tasks split by amount, with items, several payers, a tip, a note,
+            // fields, a discount, refunds or payments for them are left alone,
+            // those are for one task
+            for task in self.tasks.values ().filter (|t| t.amounts.is_empty () && t.items.is_empty () && t.payers.is_empty ()
+                                                     && t.tip.is_none () && t.note.is_none () && t.fields.is_empty () && !t.reduced ()
+                                                     && !self.transfers.iter ().any (|p| p.task.as_ref () == Some (&t.name)))
+            {
+                // settled tasks are only folded together with other settled ones
+                let settled = self.settled.as_ref ().is_some_and (|s| s.tasks.contains (&task.name));
+                // and only tasks of the same day, so interest and the history
+                // stay the same
+                groups.entry ((task.owner.clone (), task.participants.clone (), task.currency.clone ()
+                               , task.weights.clone (), task.percents.clone (), settled, task.tags.clone (), task.day
+                               , task.date.clone ()))
+                    .or_default ()
+                    .push (task.name.clone ());
+            }
+            // the first task of each group takes in the others, which are
+            // kept by name in what it folded
+            for names in groups.into_values ().filter (|n| n.len () > 1)
+            {
+                let mut folded = BTreeMap::new ();
+                for name in &names[1..]
+                {
+                    let task = self.tasks.remove (name).unwrap ();
+                    folded.insert (name.clone (), task.cost);
+                    folded.extend (task.folded);
+                    for part_name in &task.participants
+                    {
+                        let part = self.participants.get_mut (part_name).unwrap ();
+                        part.tasks.remove (name);
+                        part.paid_tasks.remove (name);
+                    }
+                }
+                let task = self.tasks.get_mut (&names[0]).unwrap ();
+                task.cost += folded.values ().sum::<Money> ();
+                task.folded.extend (folded);
+            }
+            // members who pay dues still have something to do with the ledger
+            let members = if self.dues.is_some () { self.dues_members () } else { BTreeSet::new () };
+            let transfers = &self.transfers;
+            self.participants.retain (|_, p| !p.tasks.is_empty () || !p.paid_tasks.is_empty () || members.contains (&p.name)
+                                      || transfers.iter ().any (|t| t.from == p.name || t.to == p.name));
+            // nobody removed stays left out of a task
+            let participants = &self.participants;
+            for task in self.tasks.values_mut ()
+            {
+                task.excluded.retain (|n| participants.contains_key (n));
+            }
+            // or in a group, and groups nobody is left in go too
+            for members in self.groups.values_mut ()
+            {
+                members.retain (|n| participants.contains_key (n));
+            }
+            self.groups.retain (|_, members| !members.is_empty ());
+        }
+
+        fn stats (&self, args: &[&str]) -> PaymentResult
+        {
+            match args
+            {
+                ["--usage"] => self.stats_usage (),
+                ["--memory"] => self.stats_memory (),
+                [] => return Err (String::from ("Not enough arguments")),
+                [a, ..] => return Err (format! ("{a} is not a kind of stats")),
+            }
+            Ok (())
+        }
+
+        // a rough idea of how much memory the ledger takes up. it counts the
+        // bytes of every string and the size of every struct and set entry,
+        // but not what the allocator and the maps add on top
+        fn memory_estimate (&self) -> BTreeMap<&'static str, usize>
+        {
+            use std::mem::size_of;
+            let set_size = |set: &BTreeSet<String>| -> usize
+            {
+                set.iter ().map (|n| size_of::<String> () + n.capacity ()).sum ()
+            };
+            let mut sizes = BTreeMap::new ();
+            let mut names = 0;
+            let mut sets = 0;
+            let mut structs = 0;
+            for (key, part) in &self.participants
+            {
+                names += key.capacity () + part.name.capacity ()
+                    + part.email.as_ref ().map_or (0, String::capacity)
+                    + part.phone.as_ref ().map_or (0, String::capacity);
+                sets += set_size (&part.tasks) + set_size (&part.paid_tasks);
+                structs += size_of::<String> () + size_of::<Participant> ();
+            }
+            for (key, task) in &self.tasks
+            {
+                names += key.capacity () + task.name.capacity () + task.owner.capacity ()
+                    + task.currency.as_ref ().map_or (0, String::capacity)
+                    + task.date.as_ref ().map_or (0, String::capacity);
+                sets += set_size (&task.participants)
+                    + task.weights.keys ().map (|n| size_of::<String> () + n.capacity () + size_of::<u32> ()).sum::<usize> ()
+                    + task.amounts.keys ().map (|n| size_of::<String> () + n.capacity () + size_of::<Money> ()).sum::<usize> ()
+                    + task.percents.keys ().map (|n| size_of::<String> () + n.capacity () + size_of::<u32> ()).sum::<usize> ()
+                    + task.payers.keys ().map (|n| size_of::<String> () + n.capacity () + size_of::<Money> ()).sum::<usize> ()
+                    + set_size (&task.tags)
+                    + task.note.as_ref ().map_or (0, String::capacity)
+                    + task.refunds.iter ()
+                        .map (|r| size_of::<Refund> () + r.date.capacity () + r.memo.as_ref ().map_or (0, String::capacity))
+                        .sum::<usize> ()
+                    + task.fields.iter ().map (|(k, v)| 2 * size_of::<String> () + k.capacity () + v.capacity ()).sum::<usize> ()
+                    + task.items.iter ()
+                        .map (|(n, i)| size_of::<String> () + n.capacity () + size_of::<Item> () + set_size (&i.participants))
+                        .sum::<usize> ();
+                structs += size_of::<String> () + size_of::<Task> ();
+            }
+            for (name, members) in &self.groups
+            {
+                names += name.capacity ();
+                sets += set_size (members);
+            }
+            for transfer in &self.transfers
+            {
+                names += transfer.from.capacity () + transfer.to.capacity ()
+                    + transfer.memo.as_ref ().map_or (0, String::capacity)
+                    + transfer.currency.as_ref ().map_or (0, String::capacity)
+                    + transfer.tag.as_ref ().map_or (0, String::capacity)
+                    + transfer.date.as_ref ().map_or (0, String::capacity);
+                structs += size_of::<Transfer> ();
+            }
+            sizes.insert ("names", names);
+            sizes.insert ("sets", sets);
+            sizes.insert ("structs", structs);
+            for sub in self.subledgers.values ()
+            {
+                for (kind, size) in sub.memory_estimate ()
+                {
+                    *sizes.entry (kind).or_insert (0) += size;
+                }
+                *sizes.entry ("structs").or_insert (0) += size_of::<Payment> ();
+            }
+            sizes
+        }
+
+        fn stats_memory (&self)
+        {
+            let sizes = self.memory_estimate ();
+            for (kind, size) in &sizes
+            {
+                say! (self, "  {kind}: {}", Self::bytes (*size));
+            }
+            say! (self, "total: about {}", Self::bytes (sizes.values ().sum ()));
+        }
+
+        fn bytes (size: usize) -> String
+        {
+            match size
+            {
+                s if s >= 1 << 20 => format! ("{:.1} MiB", s as f32 / (1 << 20) as f32),
+                s if s >= 1 << 10 => format! ("{:.1} KiB", s as f32 / (1 << 10) as f32),
+                s => format! ("{s} bytes"),
+            }
+        }
+
+        fn stats_usage (&self)
+        {
+            let usage = self.usage_counts ();
+            let mut counts = usage.iter ().collect::<Vec<(&String, &u64)>> ();
+            counts.sort_by (|a, b| b.1.cmp (a.1).then_with (|| a.0.cmp (b.0)));
+            match &self.usage
+            {
+                Some (file) => say! (self, "commands run: {}, counted in {}", usage.values ().sum::<u64> (), file.display ()),
+                None => say! (self, "commands aren't counted, there is no data directory to count them in"),
+            }
+            for (command, count) in counts
+            {
+                say! (self, "  {command}: {count}");
+            }
+            say! (self, "ledger size: {} participants, {} tasks, {} sub-ledgers"
+                      , self.participants.len ()
+                      , self.tasks.len ()
+                      , self.subledgers.len ());
+        }
+
+        fn explain (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let name = match args
+            {
+                [name] => self.resolve (name)?,
+                [] => return Err (String::from ("Not enough arguments")),
+                _ => return Err (String::from ("Too many arguments")),
+            };
+            self.calculate ();
+            if let Some (part) = self.participants.get (&name)
+            {
+                self.explain_participant (part);
+            }
+            else if let Some (task) = self.tasks.get (&name)
+            {
+                self.explain_task (task);
+            }
+            else if let Some (task) = self.folded_into (&name)
+            {
+                let cost = self.currencies.to_base (task.folded[&name], task.currency.as_deref ());
+                say! (self, "{name} cost {} and was folded into {} by compact", self.settings.show (cost), task.name);
+                self.explain_task (task);
+            }
+            else
+            {
+                return Err (format! ("No task or participant found named {name}"));
+            }
+            Ok (())
+        }
+
+        fn explain_task (&self, task: &Task)
+        {
+            say! (self, "{} paid {} for {}", task.owner, self.amount (task), task.name);
+            self.print_payers (task);
+            let count = task.participants.len ();
+            if count == 0
+            {
+                say! (self, "  nobody takes part, so nobody owes anything for it");
+                return;
+            }
+            if !task.amounts.is_empty ()
+            {
+                say! (self, "  split by the amounts given to {} participants", count);
+            }
+            else if !task.percents.is_empty ()
+            {
+                say! (self, "  split by percentage between {} participants", count);
+            }
+            else if task.weights.is_empty ()
+            {
+                say! (self, "  split evenly between {} participants: {} / {} = {}"
+                          , count
+                          , self.settings.show (self.shared_cost (task))
+                          , count
+                          , self.settings.show (self.shared_cost (task).share (count)));
+            }
+            else
+            {
+                say! (self, "  split by weight between {} participants, {} shares in all"
+                          , count
+                          , task.total_weight ());
+            }
+            let shares = self.charged_shares (task);
+            for (name, share) in &shares
+            {
+                if task.weights.is_empty () && task.percents.is_empty () && task.items.is_empty () && task.tip.is_none ()
+                    && !task.reduced ()
+                {
+                    say! (self, "    {name} {}", self.settings.show (*share));
+                }
+                else
+                {
+                    say! (self, "    {name} {} = {}", self.formula (task, name), self.settings.show (*share));
+                }
+            }
+            self.print_items (task);
+            self.print_tip (task);
+            self.print_reductions (task);
+            say! (self, "  {}", self.leftover (task, &shares));
+        }
+
+        // walks through the same steps as calculate, showing each one
+        fn explain_participant (&self, part: &Participant)
+        {
+            let show = |m| self.settings.show (m);
+            say! (self, "{} owes {}", part.name, show (part.sum.unwrap ()));
+            let mut sum = ExactSum::default ();
+            for task_name in &part.tasks
+            {
+                let task = self.tasks.get (task_name).unwrap ();
+                let share = self.charged_share (task, &part.name);
+                if self.splits_exactly ()
+                {
+                    sum.add (share);
+                }
+                else
+                {
+                    self.add_share (&mut sum, task, &part.name);
+                }
+                say! (self, "  + share of {task_name}: {} = {}  (running total {})"
+                          , self.formula (task, &part.name)
+                          , show (share)
+                          , show (sum.rounded ()));
+            }
+            for task_name in &part.paid_tasks
+            {
+                let task = self.tasks.get (task_name).unwrap ();
+                sum.sub (self.paid_by (task, &part.name));
+                say! (self, "  - paid for {task_name}: {}  (running total {})"
+                          , self.paid_amount (task, &part.name)
+                          , show (sum.rounded ()));
+            }
+            for transfer in &self.transfers
+            {
+                if transfer.from == part.name
+                {
+                    sum.sub (self.transferred (transfer));
+                    say! (self, "  - paid {}: {}  (running total {})", transfer.to, self.transfer_amount (transfer), show (sum.rounded ()));
+                }
+                else if transfer.to == part.name
+                {
+                    sum.add (self.transferred (transfer));
+                    say! (self, "  + got from {}: {}  (running total {})", transfer.from, self.transfer_amount (transfer), show (sum.rounded ()));
+                }
+            }
+            match part.interest
+            {
+                Some (i) if i > Money::ZERO =>
+                {
+                    sum.add (i);
+                    say! (self, "  + interest at {}: {}  (running total {})", self.interest.unwrap (), show (i), show (sum.rounded ()));
+                },
+                Some (i) =>
+                {
+                    sum.add (i);
+                    say! (self, "  - interest owed to them: {}  (running total {})", show (-i), show (sum.rounded ()));
+                },
+                None => (),
+            }
+            say! (self, "  = {}", show (sum.rounded ()));
+        }
+
+        // the cost of a task in the base currency
+        fn cost (&self, task: &Task) -> Money
+        {
+            self.currencies.to_base (task.total () - task.discounted () - task.refunded (), task.currency.as_deref ())
+        }
+
+        // the cost of a task before any discount or refund, in the base currency
+        fn gross (&self, task: &Task) -> Money
+        {
+            self.currencies.to_base (task.total (), task.currency.as_deref ())
+        }
+
+        // the cost of a task without its tip, in the base currency
+        fn bill (&self, task: &Task) -> Money
+        {
+            self.currencies.to_base (task.cost, task.currency.as_deref ())
+        }
+
+        // what a direct payment came to in the base currency
+        fn transferred (&self, transfer: &Transfer) -> Money
+        {
+            self.currencies.to_base (transfer.amount, transfer.currency.as_deref ())
+        }
+
+        // what each payer of a task paid in the base currency, the owner
+        // first. the owner paid what the others didn't, so these add up to the
+        // cost exactly
+        fn paid (&self, task: &Task) -> Vec<(String, Money)>
+        {
+            let mut paid = task.payers.iter ()
+                .map (|(name, amount)| (name.clone (), self.currencies.to_base (*amount, task.currency.as_deref ())))
+                .collect::<Vec<(String, Money)>> ();
+            let others = paid.iter ().map (|p| p.1).sum::<Money> ();
+            paid.insert (0, (task.owner.clone (), self.cost (task) - others));
+            paid
+        }
+
+        fn paid_by (&self, task: &Task, name: &str) -> Money
+        {
+            self.paid (task).into_iter ()
+                .find (|p| p.0 == name)
+                .map_or (Money::ZERO, |p| p.1)
+        }
+
+        // who a participant owes their share of a task to. with several payers
+        // the share is owed to each of them in proportion to what they paid,
+        // and nothing is owed for the part they paid themselves
+        fn owed_for (&self, task: &Task, name: &str) -> Vec<(String, Money)>
+        {
+            let share = self.charged_share (task, name);
+            let cost = self.cost (task);
+            let mut owed = Vec::new ();
+            let mut left = share;
+            for (payer, paid) in self.paid (task).into_iter ().skip (1)
+            {
+                let part = if cost == Money::ZERO
+                {
+                    Money::ZERO
+                }
+                else
+                {
+                    Money::from_cents ((share.cents () as i128 * paid.cents () as i128 / cost.cents () as i128) as i64)
+                };
+                left -= part;
+                owed.push ((payer, part));
+            }
+            // the owner gets what the others' parts leave, so nothing is lost
+            // to rounding
+            owed.insert (0, (task.owner.clone (), left));
+            owed.retain (|o| o.0 != name && o.1 != Money::ZERO);
+            owed
+        }
+
+        // what a participant paid towards a task, and out of how much if
+        // somebody else paid part of it
+        fn paid_amount (&self, task: &Task, name: &str) -> String
+        {
+            if task.payers.is_empty ()
+            {
+                return self.amount (task);
+            }
+            format! ("{} of {}", self.settings.show (self.paid_by (task, name)), self.amount (task))
+        }
+
+        fn print_payers (&self, task: &Task)
+        {
+            if task.payers.is_empty ()
+            {
+                return;
+            }
+            say! (self, "  paid by: {}", self.paid (task).iter ()
+                      .map (|(name, paid)| format! ("{name} {}", self.settings.show (*paid)))
+                      .collect::<Vec<String>> ()
+                      .join (", "));
+        }
+
+        // the cost of a task as it was paid, along with what that is in the
+        // base currency if it was paid in another one
+        fn amount (&self, task: &Task) -> String
+        {
+            match &task.currency
+            {
+                Some (code) => match (&self.currencies.base, &self.settings.currency)
+                {
+                    (Some (base), None) => format! ("{} {code} ({} {base})", task.total (), self.cost (task)),
+                    _ => format! ("{} {code} ({})", task.total (), self.settings.show (self.cost (task))),
+                },
+                None => self.settings.show (task.total ()),
+            }
+        }
+
+        // a direct payment as it's shown, in what it was paid in and then in
+        // the base currency
+        fn transfer_amount (&self, transfer: &Transfer) -> String
+        {
+            match &transfer.currency
+            {
+                Some (code) => match (&self.currencies.base, &self.settings.currency)
+                {
+                    (Some (base), None) => format! ("{} {code} ({} {base})", transfer.amount, self.transferred (transfer)),
+                    _ => format! ("{} {code} ({})", transfer.amount, self.settings.show (self.transferred (transfer))),
+                },
+                None => self.settings.show (transfer.amount),
+            }
+        }
+
+        // whether every task is split into shares that add up to its cost
+        // exactly, which balances are then worked out from
+        fn splits_exactly (&self) -> bool
+        {
+            self.double_entry || self.settings.rounding.is_some ()
+        }
+
+        // what each participant of a task is charged. the cents that don't
+        // divide evenly go where the rounding policy says, or in double entry
+        // mode one each to the first participants by name. otherwise every
+        // share is just rounded
+        fn charged_shares (&self, task: &Task) -> Vec<(String, Money)>
+        {
+            let mut shares = match self.settings.rounding
+            {
+                _ if task.participants.is_empty () => Vec::new (),
+                _ if !task.amounts.is_empty () => self.given_amounts (task),
+                Some (policy) => self.apportion (task, Some (policy)),
+                None if self.double_entry => self.apportion (task, None),
+                None => task.participants.iter ()
+                    .map (|p| (self.shared_cost (task) * task.weight (p) as i64).share (task.total_weight ()))
+                    .collect (),
+            };
+            // items are split evenly between whoever shares them
+            for item in task.items.values ()
+            {
+                let cost = self.item_cost (task, item);
+                let count = item.participants.len ();
+                let item_shares = if self.splits_exactly ()
+                {
+                    cost.split (count)
+                }
+                else
+                {
+                    vec! [cost.share (count); count]
+                };
+                for (name, share) in item.participants.iter ().zip (item_shares)
+                {
+                    if let Some (i) = task.participants.iter ().position (|p| p == name)
+                    {
+                        shares[i] += share;
+                    }
+                }
+            }
+            if task.tip.is_some () && !shares.is_empty ()
+            {
+                let tips = self.tip_shares (task, &shares);
+                for (share, tip) in shares.iter_mut ().zip (tips)
+                {
+                    *share += tip;
+                }
+            }
+            // a discount or refund takes the same part off everyone's share
+            if task.reduced () && !shares.is_empty ()
+            {
+                shares = self.cost (task).split_by (&shares);
+            }
+            task.participants.iter ().cloned ().zip (shares).collect ()
+        }
+
+        // the tip of a task split between its participants, either equally or
+        // in proportion to what they're charged for the rest of it. the cents
+        // left over go one each to the first participants by name
+        fn tip_shares (&self, task: &Task, shares: &[Money]) -> Vec<Money>
+        {
+            let tip = self.tip (task);
+            if task.tip.is_some_and (|t| t.split == TipSplit::Equal) || shares.iter ().sum::<Money> () <= Money::ZERO
+            {
+                return tip.split (shares.len ());
+            }
+            tip.split_by (shares)
+        }
+
+        // what the tip of a task came to in the base currency
+        fn tip (&self, task: &Task) -> Money
+        {
+            self.gross (task) - self.bill (task)
+        }
+
+        // how a participant's share of a task is worked out, like 30.00 / 3,
+        // with the items they share added on as in 10.00 / 3 + wine 20.00 / 2
+        fn formula (&self, task: &Task, name: &str) -> String
+        {
+            let mut text = format! ("{} {}", self.settings.show (self.shared_cost (task)), task.portion (name));
+            for (item_name, item) in task.items.iter ().filter (|i| i.1.participants.contains (name))
+            {
+                text.push_str (&format! (" + {item_name} {} / {}"
+                                         , self.settings.show (self.item_cost (task, item))
+                                         , item.participants.len ()));
+            }
+            match task.tip
+            {
+                Some (Tip { split: TipSplit::Equal, .. }) => text.push_str (&format! (" + tip {} / {}"
+                                                                                     , self.settings.show (self.tip (task))
+                                                                                     , task.participants.len ())),
+                Some (tip) => text.push_str (&format! (" + {}% tip", tip.percent)),
+                None => (),
+            }
+            if task.reduced ()
+            {
+                text.push_str (&format! (" x {}/{}", self.settings.show (self.cost (task)), self.settings.show (self.gross (task))));
+            }
+            text
+        }
+
+        fn item_cost (&self, task: &Task, item: &Item) -> Money
+        {
+            self.currencies.to_base (item.cost, task.currency.as_deref ())
+        }
+
+        // what's left of the cost of a task once its items are taken out, in
+        // the base currency
+        fn shared_cost (&self, task: &Task) -> Money
+        {
+            self.bill (task) - task.items.values ().map (|i| self.item_cost (task, i)).sum::<Money> ()
+        }
+
+        fn charged_share (&self, task: &Task, name: &str) -> Money
+        {
+            self.charged_shares (task)
+                .into_iter ()
+                .find (|s| s.0 == name)
+                .map_or (Money::ZERO, |s| s.1)
+        }
+
+        // adds a participant's share of a task to a running total without
+        // rounding it first
+        fn add_share (&self, sum: &mut ExactSum, task: &Task, name: &str)
+        {
+            if !task.amounts.is_empty () || task.tip.is_some () || task.reduced ()
+            {
+                sum.add (self.charged_share (task, name));
+                return;
+            }
+            sum.add_share (self.shared_cost (task) * task.weight (name) as i64, task.total_weight ());
+            for item in task.items.values ().filter (|i| i.participants.contains (name))
+            {
+                sum.add_share (self.item_cost (task, item), item.participants.len ());
+            }
+        }
+
+        // the amounts a task is split by, in the base currency. converting
+        // each one can leave a cent or so over, which goes to whoever paid
+        fn given_amounts (&self, task: &Task) -> Vec<Money>
+        {
+            let mut shares = task.participants.iter ()
+                .map (|p| task.amounts.get (p).copied ().unwrap_or (Money::ZERO))
+                .map (|a| self.currencies.to_base (a, task.currency.as_deref ()))
+                .collect::<Vec<Money>> ();
+            let residue = self.bill (task) - shares.iter ().sum::<Money> ();
+            let owner = task.participants.iter ().position (|p| *p == task.owner).unwrap_or (0);
+            shares[owner] += residue;
+            shares
+        }
+
+        // splits the cost of a task by weight, rounding every share down, then
+        // hands out the cents that are left over as the policy says, or one
+        // each to the first participants by name without one
+        fn apportion (&self, task: &Task, policy: Option<Rounding>) -> Vec<Money>
+        {
+            let count = task.participants.len ();
+            let total = task.total_weight () as i64;
+            let cents = self.shared_cost (task).cents ();
+            let mut shares = task.participants.iter ()
+                .map (|p| Money::from_cents ((cents * task.weight (p) as i64).div_euclid (total)))
+                .collect::<Vec<Money>> ();
+            let extra = cents - shares.iter ().map (|s| s.cents ()).sum::<i64> ();
+            match policy
+            {
+                None =>
+                {
+                    for share in shares.iter_mut ().take (extra as usize)
+                    {
+                        *share += Money::from_cents (1);
+                    }
+                },
+                Some (Rounding::Owner) =>
+                {
+                    let owner = task.participants.iter ().position (|p| *p == task.owner).unwrap_or (0);
+                    shares[owner] += Money::from_cents (extra);
+                },
+                Some (Rounding::Largest) =>
+                {
+                    // the first largest share, since max_by_key would pick the last
+                    let largest = shares.iter ().enumerate ()
+                        .fold (0, |best, (i, s)| if *s > shares[best] { i } else { best });
+                    shares[largest] += Money::from_cents (extra);
+                },
+                Some (Rounding::RoundRobin) =>
+                {
+                    let start = self.tasks.keys ().position (|t| *t == task.name).unwrap_or (0);
+                    for i in 0..extra as usize
+                    {
+                        shares[(start + i) % count] += Money::from_cents (1);
+                    }
+                },
+            }
+            shares
+        }
+
+        // says what happened to the cents the shares of a task don't cover
+        fn leftover (&self, task: &Task, shares: &[(String, Money)]) -> String
+        {
+            let residue = self.cost (task) - shares.iter ().map (|s| s.1).sum::<Money> ();
+            if residue == Money::ZERO
+            {
+                String::from ("shares add up to the cost exactly")
+            }
+            else if residue < Money::ZERO
+            {
+                format! ("shares add up to {} more than the cost", self.settings.show (-residue))
+            }
+            else if self.double_entry
+            {
+                let extra = shares.iter ()
+                    .take (residue.cents ().unsigned_abs () as usize)
+                    .map (|s| s.0.as_str ())
+                    .collect::<Vec<&str>> ();
+                format! ("{} left over, one cent each to {}", self.settings.show (residue), extra.join (", "))
+            }
+            else
+            {
+                format! ("{} left over, not charged to anyone", self.settings.show (residue))
+            }
+        }
+
+        // the exact share of a task against the share actually charged, and
+        // where the leftover cents of the task went
+        fn print_rounding (&self, task: &Task, indent: &str, only: Option<&str>)
+        {
+            if task.participants.is_empty ()
+            {
+                return;
+            }
+            if !task.amounts.is_empty ()
+            {
+                say! (self, "{indent}split by the amounts given, nothing is rounded");
+                return;
+            }
+            let shares = self.charged_shares (task);
+            for (name, share) in &shares
+            {
+                if only.is_some_and (|o| o != name)
+                {
+                    continue;
+                }
+                let mut exact = self.shared_cost (task).cents () as f64 * task.weight (name) as f64
+                    / task.total_weight () as f64 / 100f64;
+                for item in task.items.values ().filter (|i| i.participants.contains (name))
+                {
+                    exact += self.item_cost (task, item).cents () as f64 / item.participants.len () as f64 / 100f64;
+                }
+                say! (self, "{indent}{name}: exactly {} = {:.4}, charged {}"
+                          , self.formula (task, name)
+                          , exact
+                          , self.settings.show (*share));
+            }
+            say! (self, "{indent}{}", self.leftover (task, &shares));
+        }
+
+        // a task is unsettled while somebody other than whoever paid for it
+        // still owes money, it's settled once every balance is back at zero
+        pub fn summary (&mut self) -> Summary
+        {
+            self.calculate ();
+            let spent = self.tasks.values ().map (|t| self.cost (t)).sum::<Money> ();
+            let outstanding = self.participants.values ()
+                .map (|p| p.sum.unwrap ())
+                .filter (|&m| m > Money::ZERO)
+                .sum::<Money> ();
+            let unsettled_tasks = if outstanding == Money::ZERO
+            {
+                0
+            }
+            else
+            {
+                self.tasks.values ()
+                    .filter (|t| t.participants.iter ().any (|p| *p != t.owner))
+                    .count ()
+            };
+            Summary {
+                spent,
+                outstanding,
+                unsettled_tasks,
+            }
+        }
+
+        // what evcxr shows when a ledger is the value of a notebook cell: the
+        // balances, the tasks and how to settle up, worked out on a copy so
+        // the ledger itself can stay borrowed
+        pub fn evcxr_display (&self)
+        {
+            evcxr_html (&self.clone ().report_model ().to_html ());
+        }
+
+        // the tasks and payments behind a participant's debt, grouped by who
+        // they owe. these are before anything owed back is taken off, which
+        // the balance already has
+        fn owed_by (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [name] = args else
+            {
+                return Err (String::from ("owed-by must be called with one participant"));
+            };
+            let name = self.resolve (name)?;
+            self.calculate ();
+            let Some (part) = self.participants.get (&name) else
+            {
+                return Err (format! ("No participant named {name} exists"));
+            };
+            let balance = part.sum.unwrap ();
+            if balance <= Money::ZERO
+            {
+                say! (self, "{name} doesn't owe anything");
+                return Ok (());
+            }
+            say! (self, "{name} owes {}", self.settings.show (balance));
+            for (creditor, lines) in self.debts (part)
+            {
+                say! (self, "  to {creditor}: {}", self.settings.show (lines.iter ().map (|l| l.1).sum ()));
+                for (what, amount) in lines
+                {
+                    say! (self, "    {what} {}", self.settings.show (amount));
+                }
+            }
+            Ok (())
+        }
+
+        // the people someone else owes, through which tasks and payments.
+        // like owed-by it counts what they owe before anything owed back
+        fn owed_to (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [name] = args else
+            {
+                return Err (String::from ("owed-to must be called with one participant"));
+            };
+            let name = self.resolve (name)?;
+            self.calculate ();
+            let Some (creditor) = self.participants.get (&name) else
+            {
+                return Err (format! ("No participant named {name} exists"));
+            };
+            let owed = -creditor.sum.unwrap ();
+            if owed <= Money::ZERO
+            {
+                say! (self, "{name} isn't owed anything");
+                return Ok (());
+            }
+            say! (self, "{name} is owed {}", self.settings.show (owed));
+            let mut anyone = false;
+            for part in self.participants.values ().filter (|p| p.name != name)
+            {
+                let Some (lines) = self.debts (part).remove (&name) else
+                {
+                    continue;
+                };
+                let total = lines.iter ().map (|l| l.1).sum::<Money> ();
+                if total <= Money::ZERO
+                {
+                    continue;
+                }
+                anyone = true;
+                say! (self, "  {} owes {}", part.name, self.settings.show (total));
+                for (what, amount) in lines
+                {
+                    say! (self, "    {what} {}", self.settings.show (amount));
+                }
+            }
+            if !anyone
+            {
+                say! (self, "  nobody owes {name} anything");
+            }
+            Ok (())
+        }
+
+        // caps what a participant's shares may add up to, as in budget alice
+        // 200. going over it is allowed but warned about
+        fn budget (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [name, amount] = args else
+            {
+                return Err (String::from ("budget must be called with a participant and an amount"));
+            };
+            let name = self.resolve (name)?;
+            let budget = match *amount
+            {
+                "none" => None,
+                a => match Money::input (a)
+                {
+                    Ok (b) if b >= Money::ZERO => Some (b),
+                    Ok (b) => return Err (format! ("{b} is not a valid budget")),
+                    Err (e) => return Err (format! ("Invalid amount: {e}")),
+                },
+            };
+            let Some (part) = self.participants.get_mut (&name) else
+            {
+                return Err (format! ("No participant named {name} exists"));
+            };
+            part.budget = budget;
+            Ok (())
+        }
+
+        // what a participant's shares of every task they take part in add up to
+        fn spent_by (&self, part: &Participant) -> Money
+        {
+            part.tasks.iter ().map (|t| self.charged_share (&self.tasks[t], &part.name)).sum ()
+        }
+
+        // everyone whose shares add up to more than their budget, with what
+        // they add up to and the budget
+        fn over_budget (&self) -> Vec<(String, Money, Money)>
+        {
+            self.participants.values ()
+                .filter_map (|p| p.budget.map (|b| (p.name.clone (), self.spent_by (p), b)))
+                .filter (|(_, spent, budget)| spent > budget)
+                .collect ()
+        }
+
+        fn warn_over_budget (&mut self, (name, spent, budget): &(String, Money, Money))
+        {
+            self.warnings.push (format! ("{name}'s shares add up to {}, over their budget of {}"
+                                         , self.settings.show (*spent)
+                                         , self.settings.show (*budget)));
+        }
+
+        // what a participant owes each of the others for every task they
+        // share and every payment between them, by who they owe
+        fn debts (&self, part: &Participant) -> BTreeMap<String, Vec<(String, Money)>>
+        {
+            let mut creditors: BTreeMap<String, Vec<(String, Money)>> = BTreeMap::new ();
+            for task_name in &part.tasks
+            {
+                for (creditor, amount) in self.owed_for (&self.tasks[task_name], &part.name)
+                {
+                    creditors.entry (creditor).or_default ().push ((task_name.clone (), amount));
+                }
+            }
+            for transfer in &self.transfers
+            {
+                let label = match &transfer.memo
+                {
+                    Some (memo) => format! ("payment ({memo})"),
+                    None => String::from ("payment"),
+                };
+                if transfer.from == part.name
+                {
+                    creditors.entry (transfer.to.clone ()).or_default ().push ((label, -self.transferred (transfer)));
+                }
+                else if transfer.to == part.name
+                {
+                    creditors.entry (transfer.from.clone ()).or_default ().push ((label, self.transferred (transfer)));
+                }
+            }
+            creditors
+        }
+
+        fn print_participant (&self, part: &Participant, verbose: bool)
+        {
+            say! (self, "{} owes {}", part.name, self.settings.show (part.sum.unwrap ()));
+            if let Some (weight) = part.weight
+            {
+                say! (self, "  default weight: {weight}");
+            }
+            if let Some (budget) = part.budget
+            {
+                say! (self, "  budget: {}, {} spent", self.settings.show (budget), self.settings.show (self.spent_by (part)));
+            }
+            match part.interest
+            {
+                Some (i) if i > Money::ZERO => say! (self, "  interest: {} ({})", self.settings.show (i), self.interest.unwrap ()),
+                Some (i) => say! (self, "  interest: {} owed to them", self.settings.show (-i)),
+                None => (),
+            }
+            if !part.tasks.is_empty ()
+            {
+                say! (self, "  participated in:");
+            }
+            for task_name in &part.tasks
+            {
+                let task = self.tasks.get (task_name).unwrap ();
+                let share = self.charged_share (task, &part.name);
+                say! (self, "    {task_name}: {} = {}"
+                          , self.formula (task, &part.name)
+                          , self.settings.show (share));
+                if verbose
+                {
+                    self.print_rounding (task, "      ", Some (&part.name));
+                }
+            }
+            if !part.paid_tasks.is_empty ()
+            {
+                say! (self, "  paid for:");
+            }
+            for task_name in &part.paid_tasks
+            {
+                say! (self, "    {task_name}: {}", self.paid_amount (self.tasks.get (task_name).unwrap (), &part.name));
+            }
+            let put_in = self.cashbox.entries.iter ()
+                .filter (|e| e.from.as_ref () == Some (&part.name))
+                .map (|e| e.amount)
+                .sum::<Money> ();
+            if put_in != Money::ZERO
+            {
+                say! (self, "  put in the cash box: {}", self.settings.show (put_in));
+            }
+            let transfers = self.transfers.iter ()
+                .filter (|t| t.from == part.name || t.to == part.name)
+                .collect::<Vec<&Transfer>> ();
+            if !transfers.is_empty ()
+            {
+                say! (self, "  payments:");
+            }
+            for transfer in transfers
+            {
+                let line = if transfer.from == part.name
+                {
+                    format! ("    paid {} {}", transfer.to, self.transfer_amount (transfer))
+                }
+                else
+                {
+                    format! ("    got {} from {}", self.transfer_amount (transfer), transfer.from)
+                };
+                match &transfer.memo
+                {
+                    Some (memo) => say! (self, "{line}: {memo}"),
+                    None => say! (self, "{line}"),
+                }
+            }
+        }
+
+        fn print_task (&self, task: &Task, verbose: bool)
+        {
+            match &task.date
+            {
+                Some (date) => say! (self, "{} paid {} for {} on {}", task.owner, self.amount (task), task.name, self.settings.show_date (date)),
+                None => say! (self, "{} paid {} for {}", task.owner, self.amount (task), task.name),
+            }
+            if !task.tags.is_empty ()
+            {
+                say! (self, "  tags: {}", task.tags.iter ().cloned ().collect::<Vec<String>> ().join (", "));
+            }
+            if let Some (note) = &task.note
+            {
+                say! (self, "  note: {note}");
+            }
+            for (key, value) in &task.fields
+            {
+                say! (self, "  {key}: {value}");
+            }
+            if self.transfers.iter ().any (|t| t.task.as_ref () == Some (&task.name))
+            {
+                say! (self, "  {}", self.reimbursement (task));
+            }
+            self.print_payers (task);
+            if !task.excluded.is_empty ()
+            {
+                say! (self, "  left out: {}", task.excluded.iter ().cloned ().collect::<Vec<String>> ().join (", "));
+            }
+            say! (self, "  participants: {}", task.participants.len ());
+            for (part, share) in self.charged_shares (task)
+            {
+                match task.weight (&part)
+                {
+                    p if !task.percents.is_empty () => say! (self, "    {} {} ({}%)", part, self.settings.show (share), p),
+                    1 => say! (self, "    {} {}", part, self.settings.show (share)),
+                    w => say! (self, "    {} {} (weight {})", part, self.settings.show (share), w),
+                }
+            }
+            self.print_items (task);
+            self.print_tip (task);
+            self.print_reductions (task);
+            if verbose
+            {
+                say! (self, "  rounding:");
+                self.print_rounding (task, "    ", None);
+            }
+        }
+
+        // the discount and refunds of a task, and what it cost after them
+        fn print_reductions (&self, task: &Task)
+        {
+            match task.discount
+            {
+                Some (Discount::Percent (percent)) => say! (self, "  discount {percent}% off {}: {}"
+                                                                , self.settings.show (self.gross (task))
+                                                                , self.settings.show (task.discounted ())),
+                Some (Discount::Amount (amount)) => say! (self, "  discount off {}: {}"
+                                                              , self.settings.show (self.gross (task))
+                                                              , self.settings.show (amount)),
+                None => (),
+            }
+            for refund in &task.refunds
+            {
+                let line = format! ("  refunded {} on {}", self.settings.show (refund.amount), self.settings.show_date (&refund.date));
+                match &refund.memo
+                {
+                    Some (memo) => say! (self, "{line}: {memo}"),
+                    None => say! (self, "{line}"),
+                }
+            }
+            if task.reduced ()
+            {
+                say! (self, "  so it cost {}", self.settings.show (self.cost (task)));
+            }
+        }
+
+        fn print_tip (&self, task: &Task)
+        {
+            let Some (tip) = task.tip else
+            {
+                return;
+            };
+            say! (self, "  tip: {}% of {}, {}, split {}"
+                      , tip.percent
+                      , self.settings.show (self.bill (task))
+                      , self.settings.show (self.tip (task))
+                      , match tip.split
+                      {
+                          TipSplit::Proportional => "in proportion to the shares",
+                          TipSplit::Equal => "equally",
+                      });
+        }
+
+        fn print_items (&self, task: &Task)
+        {
+            if task.items.is_empty ()
+            {
+                return;
+            }
+            say! (self, "  items:");
+            for (name, item) in &task.items
+            {
+                say! (self, "    {name} {}: {}"
+                          , self.settings.show (self.item_cost (task, item))
+                          , item.participants.iter ().cloned ().collect::<Vec<String>> ().join (", "));
+            }
+        }
+
+        fn print (&mut self, args: &[&str]) -> PaymentResult
+        {
+            self.calculate ();
+            let mut normal = true;
+            let mut missing = Vec::new ();
+            let verbose = args.contains (&"-v");
+            match args.iter ().filter (|&&a| a != "-v").copied ().collect::<Vec<&str>> ()[..]
+            {
+                ["--tag", tag] =>
+                {
+                    for task in self.tasks.values ().filter (|t| t.tags.contains (tag))
+                    {
+                        self.print_task (task, verbose);
+                    }
+                    return Ok (());
+                },
+                ["--by-tag"] =>
+                {
+                    self.print_by_tag ();
+                    return Ok (());
+                },
+                ["--tag"] => return Err (String::from ("Not enough arguments")),
+                ["--by-day"] =>
+                {
+                    self.print_by_day (verbose, None);
+                    return Ok (());
+                },
+                ["--day", day] =>
+                {
+                    let day = day.parse::<u32> ().map_err (|_| format! ("{day} is not the number of a day"))?;
+                    self.print_by_day (verbose, Some (day));
+                    return Ok (());
+                },
+                ["--day"] => return Err (String::from ("Not enough arguments")),
+                ["--reimbursed"] =>
+                {
+                    for task in self.tasks.values ().filter (|t| self.reimbursed (t).1 > Money::ZERO)
+                    {
+                        say! (self, "{}: {}", task.name, self.reimbursement (task));
+                    }
+                    return Ok (());
+                },
+                ["--archived"] =>
+                {
+                    for task in self.archived.values ()
+                    {
+                        self.print_task (task, verbose);
+                    }
+                    return Ok (());
+                },
+                _ => (),
+            }
+            for &arg in args.iter ().filter (|&&a| a != "-v")
+            {
+                let arg = &self.resolve (arg)?;
+                if arg == "-a"
+                {
+                    for part in self.participants.values ()
+                    {
+                        self.print_participant (part, verbose);
+                    }
+                    return Ok (());
+                }
+                if arg == "-u"
+                {
+                    for (name, sum) in self.umbrella_balances ()
+                    {
+                        say! (self, "{} owes {}", name, self.settings.show (sum));
+                    }
+                    for (name, sub) in &self.subledgers
+                    {
+                        say! (self, "  {name}:");
+                        for (part, sum) in sub.umbrella_balances ()
+                        {
+                            say! (self, "    {} owes {}", part, self.settings.show (sum));
+                        }
+                    }
+                    return Ok (());
+                }
+                if arg == "-t"
+                {
+                    for task in self.tasks.values ()
+                    {
+                        self.print_task (task, verbose);
+                    }
+                    return Ok (());
+                }
+                if let Some (part) = self.participants.get (arg)
+                {
+                    self.print_participant (part, verbose);
+                }
+                else if let Some (task) = self.tasks.get (arg)
+                {
+                    self.print_task (task, verbose);
+                }
+                else
+                {
+                    // keep going so every name that didn't match is reported together
+                    let names = self.participants.keys ().chain (self.tasks.keys ()).map (String::as_str);
+                    missing.push (match closest (arg, names)
+                    {
+                        Some (name) => format! ("{arg} (did you mean {name}?)"),
+                        None => arg.clone (),
+                    });
+                }
+                normal = false;
+            }
+            if !missing.is_empty ()
+            {
+                return Err (format! ("Not a task or participant: {}", missing.join (", ")));
+            }
+            if normal
+            {
+                for part in self.participants.values ()
+                {
+                    say! (self, "{} owes {}", part.name, self.settings.show (part.sum.unwrap ()));
+                }
+                for over in self.over_budget ()
+                {
+                    self.warn_over_budget (&over);
+                }
+                let summary = self.summary ();
+                say! (self, "total spent {}, outstanding {}, unsettled tasks {}"
+                          , self.settings.show (summary.spent)
+                          , self.settings.show (summary.outstanding)
+                          , summary.unsettled_tasks);
+            }
+            Ok (())
+        }
+
+        // how much of what the participants owe for a task was paid back
+        // with payments for it, and out of how much
+        fn reimbursed (&self, task: &Task) -> (Money, Money)
+        {
+            let owed = task.participants.iter ()
+                .flat_map (|p| self.owed_for (task, p))
+                .map (|o| o.1)
+                .sum::<Money> ();
+            let paid = self.transfers.iter ()
+                .filter (|t| t.task.as_ref () == Some (&task.name))
+                .map (|t| self.transferred (t))
+                .sum::<Money> ();
+            (paid.min (owed), owed)
+        }
+
+        fn reimbursement (&self, task: &Task) -> String
+        {
+            match self.reimbursed (task)
+            {
+                (paid, owed) if paid == owed => String::from ("paid back in full"),
+                (paid, owed) => format! ("paid back {} of {}", self.settings.show (paid), self.settings.show (owed)),
+            }
+        }
+
+        // the day of the trip a task belongs to, the one given with --day or
+        // else counted from the first dated task as day 1
+        fn day (&self, task: &Task) -> Option<u32>
+        {
+            let first = self.tasks.values ().filter_map (|t| t.date.as_deref ()).min ()?;
+            task.day.or_else (|| Some (clock::days_between (first, task.date.as_deref ()?) as u32 + 1))
+        }
+
+        // the tasks day by day, in the order they were paid for, or just the
+        // tasks of one day
+        fn print_by_day (&self, verbose: bool, only: Option<u32>)
+        {
+            let mut days: BTreeMap<Option<u32>, Vec<&Task>> = BTreeMap::new ();
+            for task in self.tasks.values ()
+            {
+                days.entry (self.day (task)).or_default ().push (task);
+            }
+            // undated tasks come after all the others
+            let undated = days.remove (&None).map (|t| (String::from ("undated"), t));
+            let days = days.into_iter ()
+                .filter (|(d, _)| only.is_none () || *d == only)
+                .map (|(d, t)| (format! ("day {}", d.unwrap ()), t))
+                .chain (undated.filter (|_| only.is_none ()));
+            for (day, mut tasks) in days
+            {
+                tasks.sort_by (|a, b| a.date.cmp (&b.date));
+                say! (self, "{day}: {} in {} tasks"
+                          , self.settings.show (tasks.iter ().map (|t| self.cost (t)).sum::<Money> ())
+                          , tasks.len ());
+                for task in tasks
+                {
+                    self.print_task (task, verbose);
+                }
+            }
+        }
+
+        // how much was spent on each tag. a task with several tags counts
+        // towards each of them, so the totals can add up to more than was spent
+        fn print_by_tag (&self)
+        {
+            let mut totals: BTreeMap<&str, (Money, usize)> = BTreeMap::new ();
+            for task in self.tasks.values ()
+            {
+                let tags = task.tags.iter ().map (String::as_str).collect::<Vec<&str>> ();
+                for tag in if tags.is_empty () { vec! ["untagged"] } else { tags }
+                {
+                    let total = totals.entry (tag).or_insert ((Money::ZERO, 0));
+                    total.0 += self.cost (task);
+                    total.1 += 1;
+                }
+            }
+            for (tag, (spent, count)) in totals
+            {
+                say! (self, "{tag}: {} in {count} tasks", self.settings.show (spent));
+            }
+        }
+
+        fn add (&mut self, args: &[&str]) -> PaymentResult
+        {
+            if args.is_empty ()
+            {
+                return Err (String::from ("Not enough arguments"));
+            }
+            // check every name before adding any of them, so a bad name doesn't
+            // leave the ones before it added
+            let mut problems = Vec::new ();
+            let mut names = BTreeSet::new ();
+            let mut new = Vec::new ();
+            for &arg in args
+            {
+                // details can follow the name, as in alice:email=alice@example.com
+                let mut fields = arg.split (':');
+                let name = match fields.next ().unwrap_or ("")
+                {
+                    "" => return Err (String::from ("Not enough arguments")),
+                    "-a" => return Err (String::from ("invalid name")),
+                    n => n,
+                };
+                let mut part = Participant::new (name);
+                for field in fields
+                {
+                    let result = match field.split_once ('=')
+                    {
+                        Some ((key, value)) => part.set_attribute (key, value),
+                        None => Err (format! ("{field} should look like key=value")),
+                    };
+                    if let Err (e) = result
+                    {
+                        problems.push (format! ("{name}: {e}"));
+                    }
+                }
+                // if there is already a participant with this name, we don't want
+                // to overwrite them
+                if self.participants.contains_key (name)
+                {
+                    problems.push (format! ("participant {name} was already added"));
+                }
+                else if self.tasks.contains_key (name)
+                {
+                    problems.push (format! ("A task named {name} exists"));
+                }
+                else if self.groups.contains_key (name)
+                {
+                    problems.push (format! ("A group named {name} exists"));
+                }
+                else if !names.insert (name)
+                {
+                    problems.push (format! ("{name} was given more than once"));
+                }
+                new.push (part);
+            }
+            if !problems.is_empty ()
+            {
+                if args.len () == 1
+                {
+                    return Err (problems.remove (0));
+                }
+                return Err (format! ("Nobody was added:\n  {}", problems.join ("\n  ")));
+            }
+            self.check_limits (new.len (), 0)?;
+            for part in new
+            {
+                self.participants.insert (part.name.clone (), part);
+            }
+            Ok (())
+        }
+
+        fn pay (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let confirmed = args.contains (&"--confirm");
+            // --tip PERCENT, --tip-split, --day and --field can be given
+            // anywhere after the task
+            let mut percent = None;
+            let mut split = None;
+            let mut day = None;
+            let mut fields = Vec::new ();
+            let mut rest = Vec::new ();
+            let mut iter = args.iter ().filter (|a| **a != "--confirm").copied ();
+            while let Some (arg) = iter.next ()
+            {
+                match arg
+                {
+                    "--tip" => percent = Some (iter.next ()
+                                               .and_then (|p| p.trim_end_matches ('%').parse::<u32> ().ok ())
+                                               .ok_or ("--tip must be followed by a percent")?),
+                    "--tip-split" => split = Some (TipSplit::parse (iter.next ().unwrap_or (""))?),
+                    "--field" => match iter.next ().and_then (|f| f.split_once ('='))
+                    {
+                        Some ((key, value)) if self.fields.contains (key) => fields.push ((key, value)),
+                        Some ((key, _)) => return Err (format! ("{key} is not a field of this ledger, add it with field {key}")),
+                        None => return Err (String::from ("--field must be followed by FIELD=VALUE")),
+                    },
+                    "--day" => day = Some (iter.next ()
+                                           .and_then (|d| d.parse::<u32> ().ok ())
+                                           .filter (|d| *d > 0)
+                                           .ok_or ("--day must be followed by the number of a day, from 1")?),
+                    a => rest.push (a),
+                }
+            }
+            let (args, date) = self.split_date (&rest)?;
+            let now = self.now ();
+            let name = match args.first ()
+            {
+                Some (&"") => return Err (String::from ("Not enough arguments")),
+                Some (&n) => n,
+                None => return Err (String::from ("Not enough arguments")),
+            };
+            let resolved = self.resolve (name)?;
+            let name = resolved.as_str ();
+            let task_name = match args.get (1)
+            {
+                Some (&"") => return Err (String::from ("Not enough arguments")),
+                Some (&n) => n,
+                None => return Err (String::from ("Not enough arguments")),
+            };
+            if self.participants.contains_key (task_name)
+            {
+                return Err (format! ("Cannot add {task_name}, a participant exists with that name"));
+            }
+            if self.groups.contains_key (task_name) || self.groups.contains_key (name)
+            {
+                return Err (String::from ("Groups can't pay for tasks, their members can"));
+            }
+            let price_string = match args.get (2)
+            {
+                Some (&"") => return Err (String::from ("Not enough arguments")),
+                Some (&n) => n,
+                None => return Err (String::from ("Not enough arguments")),
+            };
+            if let Some (part) = price_string.strip_prefix ('+')
+            {
+                if args.len () > 3 || date.is_some ()
+                {
+                    return Err (String::from ("Part of a task is paid in the currency and on the date of the task"));
+                }
+                if percent.is_some () || split.is_some () || day.is_some () || !fields.is_empty ()
+                {
+                    return Err (String::from ("A tip, day or field is given for the whole task, not part of it"));
+                }
+                return self.pay_part (name, task_name, part, confirmed);
+            }
+            let price = match Money::input (price_string)
+            {
+                Ok (p) => p,
+                Err (e) => return Err (format! ("Invalid price: {e}")),
+            };
+            self.check_amount (price, confirmed)?;
+            let currency = match args.get (3)
+            {
+                Some (&c) => self.currencies.task_currency (c)?,
+                None => None,
+            };
+            if let Some (task) = self.tasks.get (task_name)
+            {
+                if task.items.values ().map (|i| i.cost).sum::<Money> () > price
+                {
+                    return Err (format! ("The items of {task_name} add up to more than {price}"));
+                }
+                if task.payers.iter ().filter (|p| p.0 != name).map (|p| *p.1).sum::<Money> () > price
+                {
+                    return Err (format! ("The other payers of {task_name} paid more than {price}"));
+                }
+            }
+            if args.len () > 4
+            {
+                return Err (String::from ("Too many arguments"));
+            }
+            if split.is_some () && percent.is_none () && self.tasks.get (task_name).is_none_or (|t| t.tip.is_none ())
+            {
+                return Err (format! ("{task_name} has no tip to split, give one with --tip"));
+            }
+            self.check_limits (!self.participants.contains_key (name) as usize
+                               , !self.tasks.contains_key (task_name) as usize)?;
+            // if this participant doesn't yet exist, add them
+            if !self.participants.contains_key (name)
+            {
+                self.participants.insert (String::from (name), Participant::new (name));
+            }
+            // see if the task is being edited or added
+            let task = match self.tasks.get_mut (task_name)
+            {
+                Some (val) =>
+                {
+                    // this task already exists, check if the owner should be
+                    // changed and change the cost
+                    if val.owner != name
+                    {
+                        self.warnings.extend (val.clear_split ());
+                    }
+                    else if val.cost != price
+                    {
+                        self.warnings.extend (val.clear_amounts ());
+                    }
+                    val.cost = price;
+                    val.currency = currency;
+                    if date.is_some ()
+                    {
+                        val.date = date;
+                    }
+                    if val.owner != name
+                    {
+                        let owner = val.owner.clone ();
+                        let part = self.participants.get_mut (&owner).unwrap ();
+                        part.tasks.remove (&val.name);
+                        part.paid_tasks.remove (&val.name);
+                        val.participants.remove (&owner);
+                        val.weights.remove (&owner);
+                        val.leave_items (&owner);
+                        val.payers.remove (name);
+                        val.owner = String::from (name);
+                    }
+                    val
+                },
+                None =>
+                {
+                    self.tasks.insert (String::from (task_name), Task
+                                  {
+                                      name: String::from (task_name),
+                                      owner: String::from (name),
+                                      participants: BTreeSet::new (),
+                                      cost: price,
+                                      currency,
+                                      weights: BTreeMap::new (),
+                                      amounts: BTreeMap::new (),
+                                      percents: BTreeMap::new (),
+                                      items: BTreeMap::new (),
+                                      payers: BTreeMap::new (),
+                                      date: Some (date.unwrap_or_else (|| clock::date (now))),
+                                      tags: BTreeSet::new (),
+                                      tip: None,
+                                      note: None,
+                                      day: None,
+                                      fields: BTreeMap::new (),
+                                      refunds: Vec::new (),
+                                      discount: None,
+                                      excluded: BTreeSet::new (),
+                                      folded: BTreeMap::new (),
+                                  });
+                    self.tasks.get_mut (task_name).unwrap ()
+                },
+            };
+            // --tip 0 takes the tip off again
+            if let Some (percent) = percent
+            {
+                task.tip = (percent > 0).then_some (Tip { percent, split: split.unwrap_or_default () });
+            }
+            else if let (Some (split), Some (tip)) = (split, &mut task.tip)
+            {
+                tip.split = split;
+            }
+            if day.is_some ()
+            {
+                task.day = day;
+            }
+            for (key, value) in fields
+            {
+                task.fields.insert (String::from (key), String::from (value));
+            }
+            // add this task to the paid tasks of the participant
+            let participant = self.participants.get_mut (name).unwrap ();
+            participant.paid_tasks.insert (String::from (task_name));
+            participant.tasks.insert (String::from (task_name));
+            //self.tasks.get_mut (task_name)
+            //    .unwrap ()
+            // the payer joins with their default weight like anyone else
+            if task.participants.insert (String::from (name))
+            {
+                if let Some (w) = participant.weight
+                {
+                    task.weights.insert (String::from (name), w);
+                }
+            }
+            Ok (())
+        }
+
+        // money one participant handed another, with an optional memo saying
+        // what it was for, as in payment alice bob 20 "venmo for pizza". with
+        // --for TASK it pays back that task in particular
+        fn payment (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let confirmed = args.contains (&"--confirm");
+            let mut args = args.iter ().filter (|a| **a != "--confirm").copied ().collect::<Vec<&str>> ();
+            let task = match args.iter ().position (|a| *a == "--for")
+            {
+                Some (i) if i + 1 < args.len () =>
+                {
+                    let task = self.resolve (args[i + 1])?;
+                    if !self.tasks.contains_key (&task)
+                    {
+                        return Err (format! ("Task {task} has not yet been added"));
+                    }
+                    args.drain (i..i + 2);
+                    Some (task)
+                },
+                Some (_) => return Err (String::from ("--for must be followed by a task")),
+                None => None,
+            };
+            let (args, date) = self.split_date (&args)?;
+            let [from, to, amount, memo @ ..] = args else
+            {
+                return Err (String::from ("Not enough arguments"));
+            };
+            let from = self.resolve (from)?;
+            let to = self.resolve (to)?;
+            for name in [&from, &to]
+            {
+                if !self.participants.contains_key (name)
+                {
+                    return Err (format! ("No participant named {name} exists"));
+                }
+            }
+            if from == to
+            {
+                return Err (format! ("{from} can't pay themselves"));
+            }
+            let amount = match Money::input (amount)
+            {
+                Ok (a) => a,
+                Err (e) => return Err (format! ("Invalid amount: {e}")),
+            };
+            self.check_amount (amount, confirmed)?;
+            // the amount can be followed by what it was paid in, like for pay,
+            // as long as the ledger knows that currency
+            let known = |c: &str| Currencies::code (c)
+                .is_ok_and (|c| self.currencies.base.as_ref () == Some (&c) || self.currencies.rates.contains_key (&c));
+            let (currency, memo) = match memo.split_first ()
+            {
+                Some ((c, rest)) if known (c) => (self.currencies.task_currency (c)?, rest),
+                _ => (None, memo),
+            };
+            let memo = memo.join (" ");
+            let memo = memo.strip_prefix ('"').and_then (|m| m.strip_suffix ('"')).unwrap_or (&memo);
+            self.transfers.push (Transfer
+                                 {
+                                     from,
+                                     to,
+                                     amount,
+                                     currency,
+                                     memo: Some (String::from (memo)).filter (|m| !m.is_empty ()),
+                                     date: Some (date.unwrap_or_else (|| clock::date (self.now ()))),
+                                     task,
+                                     tag: None,
+                                     id: Some (self.ids.0.next_id ()),
+                                 });
+            Ok (())
+        }
+
+        // a task entered as what each participant had, as in uneven lunch
+        // alice=12.50 bob=9.00 --payer bob. the cost is what the amounts add
+        // up to, and the task is split by exactly those amounts
+        fn uneven (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let confirm = args.contains (&"--confirm");
+            let args = args.iter ().filter (|a| **a != "--confirm").copied ().collect::<Vec<&str>> ();
+            let [task_name, shares @ .., "--payer", payer] = args.as_slice () else
+            {
+                return Err (String::from ("uneven must be called with a task, the amounts and --payer NAME"));
+            };
+            if shares.is_empty ()
+            {
+                return Err (String::from ("Not enough arguments"));
+            }
+            if self.tasks.contains_key (*task_name)
+            {
+                return Err (format! ("{task_name} already exists, use part to change how it's split"));
+            }
+            let payer = self.resolve (payer)?;
+            let mut total = Money::ZERO;
+            let mut payer_has_share = false;
+            // everything is checked before the task is added, so a bad amount
+            // doesn't leave half of it behind
+            for share in shares
+            {
+                let (name, Given::Amount (amount)) = Given::parse (share)? else
+                {
+                    return Err (format! ("{share} should look like NAME=AMOUNT"));
+                };
+                let name = self.resolve (name)?;
+                if !self.participants.contains_key (&name) && name != payer
+                {
+                    return Err (format! ("{name} is not a participant"));
+                }
+                payer_has_share |= name == payer;
+                total += amount;
+            }
+            let total = total.to_string ();
+            let mut pay = vec! [payer.as_str (), task_name, &total];
+            if confirm
+            {
+                pay.push ("--confirm");
+            }
+            self.pay (&pay)?;
+            // whoever paid takes part in the task, with nothing to pay if they
+            // didn't have anything
+            let nothing = format! ("{payer}=0");
+            let mut part = vec! [*task_name];
+            part.extend_from_slice (shares);
+            if !payer_has_share
+            {
+                part.push (&nothing);
+            }
+            self.part (&part)
+        }
+
+        // NAME paid part of a task somebody else paid for, which the owner no
+        // longer has to have paid. paying +0 takes it back again
+        fn pay_part (&mut self, name: &str, task_name: &str, amount: &str, confirmed: bool) -> PaymentResult
+        {
+            let amount = match Money::input (amount)
+            {
+                Ok (a) if a >= Money::ZERO => a,
+                Ok (_) => return Err (String::from ("Invalid price: it can't be negative")),
+                Err (e) => return Err (format! ("Invalid price: {e}")),
+            };
+            self.check_amount (amount, confirmed)?;
+            let Some (task) = self.tasks.get (task_name) else
+            {
+                return Err (format! ("No task named {task_name} exists, pay for it first"));
+            };
+            if task.owner == name
+            {
+                return Err (format! ("{name} paid for {task_name}, give its whole cost with pay instead"));
+            }
+            let others = task.payers.iter ().filter (|p| p.0 != name).map (|p| *p.1).sum::<Money> ();
+            if others + amount > task.total ()
+            {
+                return Err (format! ("{name} can't pay {amount} of {task_name}, it only cost {} and the others paid {others}", task.total ()));
+            }
+            self.check_limits (!self.participants.contains_key (name) as usize, 0)?;
+            let part = self.participants.entry (String::from (name))
+                .or_insert_with (|| Participant::new (name));
+            let task = self.tasks.get_mut (task_name).unwrap ();
+            if amount == Money::ZERO
+            {
+                // they still took part, they just didn't pay any of it
+                if task.payers.remove (name).is_some ()
+                {
+                    part.paid_tasks.remove (task_name);
+                }
+                return Ok (());
+            }
+            task.payers.insert (String::from (name), amount);
+            part.paid_tasks.insert (String::from (task_name));
+            part.tasks.insert (String::from (task_name));
+            if task.participants.insert (String::from (name))
+            {
+                self.warnings.extend (task.clear_split ());
+            }
+            Ok (())
+        }
+
+        // the amounts or percentages a task will be split by once the
+        // participants given to part are added. once a task has either, every
+        // participant needs one and they have to add up to the cost or to 100%
+        fn check_split (&self, task: &Task, names: &[(String, Given)])
+                        -> Result<(BTreeMap<String, Money>, Percents), String>
+        {
+            let mut amounts = task.amounts.clone ();
+            let mut percents = task.percents.clone ();
+            let mut participants = task.participants.clone ();
+            for (name, given) in names.iter ().filter (|n| self.participants.contains_key (&n.0))
+            {
+                participants.insert (name.clone ());
+                match given
+                {
+                    Given::Amount (amount) =>
+                    {
+                        amounts.insert (name.clone (), *amount);
+                    },
+                    Given::Percent (percent) =>
+                    {
+                        percents.insert (name.clone (), *percent);
+                    },
+                    _ => (),
+                }
+            }
+            if !amounts.is_empty () && !task.items.is_empty ()
+            {
+                return Err (format! ("{} has items, so it can't be split by exact amounts", task.name));
+            }
+            let (kind, example) = match (amounts.is_empty (), percents.is_empty ())
+            {
+                (true, true) => return Ok ((amounts, percents)),
+                (false, false) => return Err (format! ("{} can't be split by amounts and percentages at once", task.name)),
+                (false, true) => ("exact amounts", "=AMOUNT"),
+                (true, false) => ("percentage", "=PERCENT%"),
+            };
+            if names.iter ().any (|n| matches! (n.1, Given::Weight (_)))
+            {
+                return Err (format! ("{} is split by {kind}, so it can't be given weights", task.name));
+            }
+            if let Some (missing) = participants.iter ().find (|p| !amounts.contains_key (*p) && !percents.contains_key (*p))
+            {
+                return Err (format! ("{} is split by {kind}, give {missing} one as in {missing}{example}", task.name));
+            }
+            let total = amounts.values ().sum::<Money> ();
+            if !amounts.is_empty () && total != task.cost
+            {
+                return Err (format! ("The amounts for {} add up to {total}, not {}", task.name, task.cost));
+            }
+            let total = percents.values ().sum::<u32> ();
+            if !percents.is_empty () && total != 100
+            {
+                return Err (format! ("The percentages for {} add up to {total}%, not 100%", task.name));
+            }
+            Ok ((amounts, percents))
+        }
+
+        // makes a task come back every so often, starting from the day it was
+        // paid for. tick adds the copies, none stops it coming back
+        fn recur (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [task_name, every] = args else
+            {
+                return Err (String::from ("recur must be called with a task and how often it comes back"));
+            };
+            let task_name = self.resolve (task_name)?;
+            if *every == "none"
+            {
+                return match self.recurring.remove (&task_name)
+                {
+                    Some (_) => Ok (()),
+                    None => Err (format! ("{task_name} doesn't recur")),
+                };
+            }
+            let every = Every::parse (every)?;
+            let Some (task) = self.tasks.get (&task_name) else
+            {
+                return Err (format! ("Task {task_name} has not yet been added"));
+            };
+            let start = task.date.clone ().unwrap_or_else (|| clock::date (self.now ()));
+            self.recurring.insert (task_name, Recurring { task: task.clone (), every, start, count: 0 });
+            Ok (())
+        }
+
+        // adds a copy of every recurring task for each time it has come due up
+        // to today, named after the day it's for, like rent-2024-06-01
+        fn tick (&mut self) -> PaymentResult
+        {
+            let today = clock::date (self.now ());
+            let mut due = Vec::new ();
+            for (name, recurring) in &self.recurring
+            {
+                let mut times = recurring.count + 1;
+                loop
+                {
+                    let date = recurring.every.after (&recurring.start, times);
+                    if date > today
+                    {
+                        break;
+                    }
+                    due.push ((name.clone (), date));
+                    times += 1;
+                }
+            }
+            self.check_limits (0, due.len ())?;
+            for (name, date) in due
+            {
+                let recurring = self.recurring.get_mut (&name).unwrap ();
+                recurring.count += 1;
+                let mut task = recurring.task.clone ();
+                task.name = format! ("{name}-{date}");
+                task.date = Some (date);
+                task.day = None;
+                if self.tasks.contains_key (&task.name) || self.participants.contains_key (&task.name)
+                {
+                    self.warnings.push (format! ("{} already exists, skipped", task.name));
+                    continue;
+                }
+                for part_name in &task.participants
+                {
+                    let part = self.participants.get_mut (part_name).unwrap ();
+                    part.tasks.insert (task.name.clone ());
+                    if *part_name == task.owner || task.payers.contains_key (part_name)
+                    {
+                        part.paid_tasks.insert (task.name.clone ());
+                    }
+                }
+                self.tasks.insert (task.name.clone (), task);
+            }
+            Ok (())
+        }
+
+        fn print_recurring (&self)
+        {
+            for (name, recurring) in &self.recurring
+            {
+                say! (self, "{name}: {} paid {} {}, next on {}"
+                          , recurring.task.owner
+                          , self.amount (&recurring.task)
+                          , recurring.every
+                          , self.settings.show_date (&recurring.next ()));
+            }
+        }
+
+        // a name standing for several participants, which part adds all of at
+        // once. giving a group again replaces its members, none removes it
+        fn group (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [name, members @ ..] = args else
+            {
+                return Err (String::from ("Not enough arguments"));
+            };
+            match members
+            {
+                [] => return Err (String::from ("Not enough arguments")),
+                ["none"] =>
+                {
+                    return match self.groups.remove (*name)
+                    {
+                        Some (_) => Ok (()),
+                        None => Err (format! ("There is no group named {name}")),
+                    };
+                },
+                _ => (),
+            }
+            if self.participants.contains_key (*name) || self.tasks.contains_key (*name)
+            {
+                return Err (format! ("{name} already exists"));
+            }
+            let mut group = BTreeSet::new ();
+            for member in members
+            {
+                let member = self.resolve (member)?;
+                if !self.participants.contains_key (&member)
+                {
+                    return Err (format! ("{member} is not a participant"));
+                }
+                group.insert (member);
+            }
+            self.groups.insert (String::from (*name), group);
+            Ok (())
+        }
+
+        // puts tasks away once they're done with. they leave every balance
+        // but can still be seen with print --archived
+        fn archive (&mut self, args: &[&str]) -> PaymentResult
+        {
+            if args.is_empty ()
+            {
+                return Err (String::from ("Not enough arguments"));
+            }
+            let mut task_names = Vec::new ();
+            for arg in args
+            {
+                let task_name = self.resolve (arg)?;
+                if !self.tasks.contains_key (&task_name)
+                {
+                    return Err (format! ("Task {task_name} has not yet been added"));
+                }
+                if self.archived.contains_key (&task_name)
+                {
+                    return Err (format! ("An archived task is already called {task_name}, rename this one first"));
+                }
+                task_names.push (task_name);
+            }
+            for task_name in task_names
+            {
+                let task = self.tasks[&task_name].clone ();
+                self.remove_task (&task_name)?;
+                self.archived.insert (task_name, task);
+            }
+            Ok (())
+        }
+
+        // adds fields tasks can be given, or takes them away with -NAME along
+        // with every value given for them
+        fn field (&mut self, args: &[&str]) -> PaymentResult
+        {
+            if args.is_empty ()
+            {
+                return Err (String::from ("Not enough arguments"));
+            }
+            if let Some (field) = args.iter ().find (|f| f.is_empty () || **f == "-" || f.contains ('='))
+            {
+                return Err (format! ("{field} is not a valid field name"));
+            }
+            for field in args
+            {
+                match field.strip_prefix ('-')
+                {
+                    Some (f) =>
+                    {
+                        self.fields.remove (f);
+                        for task in self.tasks.values_mut ()
+                        {
+                            task.fields.remove (f);
+                        }
+                    },
+                    None =>
+                    {
+                        self.fields.insert (String::from (*field));
+                    },
+                }
+            }
+            Ok (())
+        }
+
+        fn print_fields (&self)
+        {
+            for field in &self.fields
+            {
+                let count = self.tasks.values ().filter (|t| t.fields.contains_key (field)).count ();
+                say! (self, "{field}: set on {count} tasks");
+            }
+        }
+
+        fn print_groups (&self)
+        {
+            for (name, members) in &self.groups
+            {
+                say! (self, "{name}: {}", members.iter ().cloned ().collect::<Vec<String>> ().join (", "));
+            }
+        }
+
+        // puts a task in categories like food or lodging. a tag starting with -
+        // is taken off again, and none takes all of them off
+        fn tag (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [task_name, tags @ ..] = args else
+            {
+                return Err (String::from ("Not enough arguments"));
+            };
+            if tags.is_empty ()
+            {
+                return Err (String::from ("Not enough arguments"));
+            }
+            let task_name = self.resolve (task_name)?;
+            let Some (task) = self.tasks.get_mut (&task_name) else
+            {
+                return Err (format! ("Task {task_name} has not yet been added"));
+            };
+            if tags == ["none"]
+            {
+                task.tags.clear ();
+                return Ok (());
+            }
+            if let Some (tag) = tags.iter ().find (|t| t.is_empty () || **t == "-" || **t == "none")
+            {
+                return Err (format! ("{tag} is not a valid tag"));
+            }
+            for tag in tags
+            {
+                match tag.strip_prefix ('-')
+                {
+                    Some (t) => task.tags.remove (t),
+                    None => task.tags.insert (String::from (*tag)),
+                };
+            }
+            Ok (())
+        }
+
+        // takes an amount or a percent off a task, as in discount dinner 15 or
+        // discount dinner 10%. every share goes down by the same part
+        fn discount (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [task_name, amount] = args else
+            {
+                return Err (String::from ("discount must be called with a task and an amount or percent"));
+            };
+            let task_name = self.resolve (task_name)?;
+            let Some (task) = self.tasks.get_mut (&task_name) else
+            {
+                return Err (format! ("Task {task_name} has not yet been added"));
+            };
+            let discount = match amount.strip_suffix ('%')
+            {
+                _ if *amount == "none" => None,
+                Some (percent) => match percent.parse::<u32> ()
+                {
+                    Ok (p) if p > 0 && p <= 100 => Some (Discount::Percent (p)),
+                    _ => return Err (format! ("{amount} is not a percent between 1% and 100%")),
+                },
+                None => match Money::input (amount)
+                {
+                    Ok (a) if a > Money::ZERO && a <= task.total () => Some (Discount::Amount (a)),
+                    Ok (a) => return Err (format! ("{task_name} cost {}, {a} can't be taken off it", task.total ())),
+                    Err (e) => return Err (format! ("Invalid amount: {e}")),
+                },
+            };
+            let old = std::mem::replace (&mut task.discount, discount);
+            if task.refunded () > task.total () - task.discounted ()
+            {
+                task.discount = old;
+                return Err (format! ("More of {task_name} was refunded than it would cost after the discount"));
+            }
+            Ok (())
+        }
+
+        // money given back for a task, as in refund cabin 150 deposit. every
+        // share of it goes down by the same part. none takes the refunds back
+        fn refund (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [task_name, amount, memo @ ..] = args else
+            {
+                return Err (String::from ("refund must be called with a task and an amount"));
+            };
+            let task_name = self.resolve (task_name)?;
+            let now = clock::date (self.now ());
+            let Some (task) = self.tasks.get_mut (&task_name) else
+            {
+                return Err (format! ("Task {task_name} has not yet been added"));
+            };
+            if *amount == "none"
+            {
+                task.refunds.clear ();
+                return Ok (());
+            }
+            let amount = match Money::input (amount)
+            {
+                Ok (a) if a > Money::ZERO => a,
+                Ok (a) => return Err (format! ("{a} can't be refunded")),
+                Err (e) => return Err (format! ("Invalid amount: {e}")),
+            };
+            if task.refunded () + amount > task.total () - task.discounted ()
+            {
+                return Err (format! ("{task_name} only cost {} and {} was refunded already"
+                                     , task.total () - task.discounted ()
+                                     , task.refunded ()));
+            }
+            let memo = memo.join (" ");
+            let memo = memo.strip_prefix ('"').and_then (|m| m.strip_suffix ('"')).unwrap_or (&memo);
+            task.refunds.push (Refund
+                               {
+                                   amount,
+                                   date: now,
+                                   memo: Some (String::from (memo)).filter (|m| !m.is_empty ()),
+                               });
+            Ok (())
+        }
+
+        // a few words about a task, as in note dinner "Luigi's, incl. tip".
+        // none takes the note off again
+        fn note (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [task_name, words @ ..] = args else
+            {
+                return Err (String::from ("Not enough arguments"));
+            };
+            if words.is_empty ()
+            {
+                return Err (String::from ("Not enough arguments"));
+            }
+            let task_name = self.resolve (task_name)?;
+            let Some (task) = self.tasks.get_mut (&task_name) else
+            {
+                return Err (format! ("Task {task_name} has not yet been added"));
+            };
+            let note = words.join (" ");
+            let note = note.strip_prefix ('"').and_then (|n| n.strip_suffix ('"')).unwrap_or (&note);
+            task.note = Some (String::from (note)).filter (|n| !n.is_empty () && n != "none");
+            Ok (())
+        }
+
+        // adds a part of a task's cost that only some of its participants
+        // share, or takes it out again with none. whoever shares an item takes
+        // part in the task too
+        fn item (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let (task_name, item_name, cost, names) = match args
+            {
+                [task, item, cost, names @ ..] if !names.is_empty () || *cost == "none" => (*task, *item, *cost, names),
+                _ => return Err (String::from ("item must be called with a task, a name, a cost and who shares it")),
+            };
+            let task_name = self.resolve (task_name)?;
+            let Some (task) = self.tasks.get (&task_name) else
+            {
+                return Err (format! ("Task {task_name} has not yet been added"));
+            };
+            if cost == "none"
+            {
+                if !names.is_empty ()
+                {
+                    return Err (String::from ("Too many arguments"));
+                }
+                return match self.tasks.get_mut (&task_name).unwrap ().items.remove (item_name)
+                {
+                    Some (_) => Ok (()),
+                    None => Err (format! ("{task_name} has no item named {item_name}")),
+                };
+            }
+            let cost = match Money::input (cost)
+            {
+                Ok (c) if c >= Money::ZERO => c,
+                Ok (_) => return Err (format! ("{cost} is not a valid cost for an item")),
+                Err (e) => return Err (format! ("Invalid cost: {e}")),
+            };
+            if !task.amounts.is_empty ()
+            {
+                return Err (format! ("{task_name} is split by exact amounts, so it can't have items"));
+            }
+            let others = task.items.iter ()
+                .filter (|i| i.0 != item_name)
+                .map (|i| i.1.cost)
+                .sum::<Money> ();
+            if others + cost > task.cost
+            {
+                return Err (format! ("The items of {task_name} would add up to more than its cost of {}", task.cost));
+            }
+            let mut participants = BTreeSet::new ();
+            for name in names
+            {
+                let name = self.resolve (name)?;
+                if !self.participants.contains_key (&name)
+                {
+                    return Err (format! ("{name} is not a participant"));
+                }
+                participants.insert (name);
+            }
+            let task = self.tasks.get_mut (&task_name).unwrap ();
+            if participants.iter ().any (|p| !task.participants.contains (p))
+            {
+                // percentages were given for the participants there were
+                self.warnings.extend (task.clear_split ());
+            }
+            for name in &participants
+            {
+                let part = self.participants.get_mut (name).unwrap ();
+                part.tasks.insert (task_name.clone ());
+                part.sum = None;
+                task.participants.insert (name.clone ());
+            }
+            task.items.insert (String::from (item_name), Item { cost, participants });
+            Ok (())
+        }
+
+        // takes participants out of a task and keeps them out, so part TASK
+        // all doesn't put them back in
+        fn unpart (&mut self, args: &[&str]) -> PaymentResult
+        {
+            let [task_name, args @ ..] = args else
+            {
+                return Err (String::from ("Not enough arguments"));
+            };
+            if args.is_empty ()
+            {
+                return Err (String::from ("Not enough arguments"));
+            }
+            let task_name = self.resolve (task_name)?;
+            if !self.tasks.contains_key (&task_name)
+            {
+                return Err (format! ("Task {task_name} has not yet been added"));
+            }
+            let mut names = Vec::new ();
+            for arg in args
+            {
+                match self.groups.get (*arg)
+                {
+                    Some (members) => names.extend (members.iter ().cloned ()),
+                    None => names.push (self.resolve (arg)?),
+                }
+            }
+            if let Some (name) = names.iter ().find (|n| !self.participants.contains_key (*n))
+            {
+                return Err (format! ("No participant named {name} exists"));
+            }
+            for name in &names
+            {
+                self.remove_from (name, &task_name)?;
+                self.tasks.get_mut (&task_name).unwrap ().excluded.insert (name.clone ());
+            }
+            Ok (())
+        }
+
+        fn part (&mut self, args: &[&str]) -> PaymentResult
+        {
+            if args.len () <= 1
+            {
+                return Err (String::from ("Not enough arguments"));
+            }
+            let task_name = match args.first ()
+            {
+                Some (&"") => return Err (String::from ("Not enough arguments")),
+                Some (&n) => n,
+                None => return Err (String::from ("Not enough arguments")),
+            };
+            let task_name = &self.resolve (task_name)?;
+            let excluded = self.tasks.get (task_name).map (|t| t.excluded.clone ()).unwrap_or_default ();
+            let mut names = Vec::new ();
+            for &arg in &args[1..]
+            {
+                let (name, given) = Given::parse (arg)?;
+                // all stands for every participant, each with their default
+                // weight, except whoever was taken out with unpart
+                if name == "all" && !self.participants.contains_key (name) && !self.groups.contains_key (name)
+                {
+                    if !matches! (given, Given::Nothing)
+                    {
+                        return Err (String::from ("all can't be given a weight or an amount"));
+                    }
+                    names.extend (self.participants.keys ()
+                                  .filter (|p| !excluded.contains (*p))
+                                  .map (|p| (p.clone (), Given::Nothing)));
+                    continue;
+                }
+                // a group stands for each of its members, with the same weight
+                if let Some (members) = self.groups.get (name)
+                {
+                    if matches! (given, Given::Amount (_) | Given::Percent (_))
+                    {
+                        return Err (format! ("{name} is a group, give its members their amounts one by one"));
+                    }
+                    names.extend (members.iter ().map (|m| (m.clone (), given)));
+                    continue;
+                }
+                names.push ((self.resolve (name)?, given));
+            }
+            let Some (task) = self.tasks.get (task_name) else
+            {
+                return Err (format! ("Task {task_name} has not yet been added"));
+            };
+            if self.settings.strict
+            {
+                if let Some (name) = names.iter ().find (|n| !self.participants.contains_key (&n.0))
+                {
+                    return Err (format! ("{} is not a participant", name.0));
+                }
+            }
+            let (amounts, percents) = self.check_split (task, &names)?;
+            let task = self.tasks.get_mut (task_name).unwrap ();
+            for (arg, given) in &names
+            {
+                let Some (participant) = self.participants.get_mut (arg) else
+                {
+                    // ignore it if they entered a bad name
+                    self.warnings.push (format! ("{arg} is not a participant, skipped"));
+                    continue;
+                };
+                // if this participant is paying for this task, don't add it to their list of tasks
+                //if participant.paid_tasks.contains (task_name)
+                //{
+                //    return Ok (());
+                //}
+                // done with preparing, add stuff together
+                participant.tasks.insert (String::from (task_name));
+                participant.sum = None;
+                let joined = task.participants.insert (String::from (arg));
+                // naming somebody puts them back in even if they were taken out
+                task.excluded.remove (arg);
+                match given
+                {
+                    Given::Weight (1) =>
+                    {
+                        task.weights.remove (arg);
+                    },
+                    Given::Weight (w) =>
+                    {
+                        task.weights.insert (arg.clone (), *w);
+                    },
+                    // somebody already in the task keeps the weight they have
+                    // there, so per task weights aren't undone
+                    Given::Nothing if joined =>
+                    {
+                        if let Some (w) = participant.weight
+                        {
+                            task.weights.insert (arg.clone (), w);
+                        }
+                    },
+                    _ => (),
+                }
+            }
+            if !amounts.is_empty () || !percents.is_empty ()
+            {
+                task.weights.clear ();
+            }
+            task.amounts = amounts;
+            task.percents = percents;
+            Ok (())
+        }
+    }
+}
+
+pub mod shared
+{
+    use std::sync::{Arc, RwLock};
+    use crate::payments::{Outcome, Payment};
+
+    // commands that only look at the ledger, these don't advance the sequence
+    // number so readers don't invalidate each other's optimistic writes
+    const READ_ONLY: [&str; 19] = ["print", "save", "settle", "export", "explain", "stats", "journal", "paths", "rules", "report"
+                                   , "owed-by", "owed-to", "groups", "recurring", "fields", "help", "parse", "notify", "verify"];
+
+    struct Ledger
+    {
+        payment: Payment,
+        seq: u64,
+    }
+
+    // what came of a command: the sequence number of the ledger after it and
+    // whatever it printed, since a server has no terminal to print to
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Reply
+    {
+        pub seq: u64,
+        pub output: String,
+    }
+
+    // a ledger that can be handed to several frontends at once. every command
+    // runs under the write lock so commands never interleave, and every
+    // change bumps a sequence number that clients can use to detect that
+    // somebody else changed the ledger since they last looked at it
+    #[derive(Clone)]
+    pub struct SharedLedger
+    {
+        inner: Arc<RwLock<Ledger>>,
+    }
+
+    impl SharedLedger
+    {
+        pub fn new (mut payment: Payment) -> Self
+        {
+            payment.capture_output (true);
+            Self {
+                inner: Arc::new (RwLock::new (Ledger { payment, seq: 0 })),
+            }
+        }
+
+        pub fn seq (&self) -> Result<u64, String>
+        {
+            match self.inner.read ()
+            {
+                Ok (ledger) => Ok (ledger.seq),
+                Err (_) => Err (String::from ("ledger lock was poisoned")),
+            }
+        }
+
+        // runs a command, a trailing newline is optional
+        pub fn command (&self, com: &str) -> Result<Reply, String>
+        {
+            self.apply (com, None)
+        }
+
+        // runs a command only if nobody has changed the ledger since `expected`
+        pub fn command_at (&self, com: &str, expected: u64) -> Result<Reply, String>
+        {
+            self.apply (com, Some (expected))
+        }
+
+        // runs a command tagged with a client chosen key. if a command with the
+        // same key was already run, it is not run again and the original
+        // outcome is returned, so retried requests can't book an expense twice.
+        // outcomes are kept in the ledger, so they last as long as it's saved
+        pub fn command_once (&self, key: &str, com: &str) -> Result<Reply, String>
+        {
+            let Ok (mut ledger) = self.inner.write () else
+            {
+                return Err (String::from ("ledger lock was poisoned"));
+            };
+            if let Some (outcome) = ledger.payment.outcome (key)
+            {
+                if outcome.command != com.trim ()
+                {
+                    return Err (format! ("{key} was already used for a different command: {}", outcome.command));
+                }
+                return outcome.result.clone ().map (|(seq, output)| Reply { seq, output });
+            }
+            let result = Self::run (&mut ledger, com, None);
+            ledger.payment.record_outcome (Outcome
+                                           {
+                                               key: String::from (key),
+                                               command: String::from (com.trim ()),
+                                               result: result.clone ().map (|r| (r.seq, r.output)),
+                                           });
+            result
+        }
+
+        // hands over the warnings left by commands since the last call
+        pub fn take_warnings (&self) -> Result<Vec<String>, String>
+        {
+            match self.inner.write ()
+            {
+                Ok (mut ledger) => Ok (ledger.payment.take_warnings ()),
+                Err (_) => Err (String::from ("ledger lock was poisoned")),
+            }
+        }
+
+        // gives read access to the ledger along with its current sequence number
+        pub fn read<R> (&self, f: impl FnOnce (&Payment) -> R) -> Result<(u64, R), String>
+        {
+            match self.inner.read ()
+            {
+                Ok (ledger) => Ok ((ledger.seq, f (&ledger.payment))),
+                Err (_) => Err (String::from ("ledger lock was poisoned")),
+            }
+        }
+
+        fn apply (&self, com: &str, expected: Option<u64>) -> Result<Reply, String>
+        {
+            let Ok (mut ledger) = self.inner.write () else
+            {
+                return Err (String::from ("ledger lock was poisoned"));
+            };
+            Self::run (&mut ledger, com, expected)
+        }
+
+        fn run (ledger: &mut Ledger, com: &str, expected: Option<u64>) -> Result<Reply, String>
+        {
+            if let Some (expected) = expected
+            {
+                if expected != ledger.seq
+                {
+                    return Err (format! ("ledger changed since version {expected}, it is now at version {}"
+                                         , ledger.seq));
+                }
+            }
+            // anything left over from outside a command isn't this command's
+            ledger.payment.take_output ();
+            let result = ledger.payment.command (com);
+            let output = ledger.payment.take_output ();
+            result?;
+            if Self::changes (&ledger.payment, com)
+            {
+                ledger.seq += 1;
+            }
+            Ok (Reply { seq: ledger.seq, output })
+        }
+
+        // whether a command that worked could have changed the ledger. in NAME
+        // runs the rest on a sub-ledger, so that's the part that counts
+        fn changes (payment: &Payment, com: &str) -> bool
+        {
+            let mut words = com.split_whitespace ();
+            while let Some (typed) = words.next ()
+            {
+                let Ok (name) = payment.command_name (typed) else
+                {
+                    return true;
+                };
+                if name != "in"
+                {
+                    // settling only changes the ledger when it's applied
+                    return !READ_ONLY.contains (&name.as_str ()) || words.any (|a| a == "--apply");
+                }
+                words.next ();
+            }
+            true
+        }
+    }
+}
+
+pub mod clock
+{
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // where the ledger gets the current time from. anything dated should go
+    // through this instead of asking the system so it can be pinned down
+    pub trait Clock: Send + Sync
+    {
+        // seconds since the unix epoch
+        fn now (&self) -> u64;
+    }
+
+    pub struct SystemClock;
+
+    impl Clock for SystemClock
+    {
+        fn now (&self) -> u64
+        {
+            SystemTime::now ()
+                .duration_since (UNIX_EPOCH)
+                .map (|d| d.as_secs ())
+                .unwrap_or (0)
+        }
+    }
+
+    // a clock that only moves when told to, for tests and replaying history
+    pub struct FixedClock
+    {
+        time: AtomicU64,
+    }
+
+    impl FixedClock
+    {
+        pub fn new (time: u64) -> Self
+        {
+            Self { time: AtomicU64::new (time) }
+        }
+
+        pub fn set (&self, time: u64)
+        {
+            self.time.store (time, Ordering::SeqCst);
+        }
+
+        pub fn advance (&self, seconds: u64)
+        {
+            self.time.fetch_add (seconds, Ordering::SeqCst);
+        }
+    }
+
+    impl Clock for FixedClock
+    {
+        fn now (&self) -> u64
+        {
+            self.time.load (Ordering::SeqCst)
+        }
+    }
+
+    // the day a time falls on as YYYY-MM-DD, in UTC
+    pub fn date (time: u64) -> String
+    {
+        // counting from 0000-03-01 puts leap days at the end of the year
+        let days = time / 86400 + 719468;
+        let era = days / 146097;
+        let day_of_era = days % 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + (month <= 2) as u64;
+        format! ("{year:04}-{month:02}-{day:02}")
+    }
+
+    // checks a date typed as YYYY-MM-DD
+    pub fn parse_date (text: &str) -> Result<String, String>
+    {
+        let invalid = || format! ("{text} is not a date like 2024-05-31");
+        let parts = text.split ('-').collect::<Vec<&str>> ();
+        let [year, month, day] = parts[..] else
+        {
+            return Err (invalid ());
+        };
+        if year.len () != 4 || month.len () != 2 || day.len () != 2
+            || !text.chars ().all (|c| c.is_ascii_digit () || c == '-')
+        {
+            return Err (invalid ());
+        }
+        let (year, month, day) = (year.parse::<u32> ().unwrap (), month.parse::<u32> ().unwrap (), day.parse::<u32> ().unwrap ());
+        if !(1..=12).contains (&month) || day == 0 || day > days_in_month (year, month)
+        {
+            return Err (invalid ());
+        }
+        Ok (String::from (text))
+    }
+
+    fn days_in_month (year: u32, month: u32) -> u32
+    {
+        let leap = year.is_multiple_of (4) && (!year.is_multiple_of (100) || year.is_multiple_of (400));
+        match month
+        {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    // the year, month and day of a date from date or parse_date
+    fn parts (date: &str) -> (u32, u32, u32)
+    {
+        let mut parts = date.split ('-').map (|p| p.parse::<u32> ().unwrap_or (1));
+        (parts.next ().unwrap_or (1970), parts.next ().unwrap_or (1), parts.next ().unwrap_or (1))
+    }
+
+    // the start of a date's day in seconds since the unix epoch, the other way
+    // around from date
+    fn time (date: &str) -> u64
+    {
+        let (year, month, day) = parts (date);
+        let year = year as i64 - (month <= 2) as i64;
+        let era = year.div_euclid (400);
+        let year_of_era = year - era * 400;
+        let month = month as i64;
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+        days.max (0) as u64 * 86400
+    }
+
+    // how many days one date is after another
+    pub fn days_between (from: &str, to: &str) -> i64
+    {
+        (time (to) as i64 - time (from) as i64) / 86400
+    }
+
+    // a date some number of days later
+    pub fn add_days (date: &str, days: u32) -> String
+    {
+        self::date (time (date) + days as u64 * 86400)
+    }
+
+    // a date some number of days earlier, no earlier than 1970-01-01
+    pub fn days_before (date: &str, days: u32) -> String
+    {
+        self::date (time (date).saturating_sub (days as u64 * 86400))
+    }
+
+    // the day of the week of a date, from 0 for monday to 6 for sunday
+    pub fn weekday (date: &str) -> usize
+    {
+        // 1970-01-01 was a thursday
+        ((time (date) / 86400 + 3) % 7) as usize
+    }
+
+    // a date some number of months later, on the last day of the month when
+    // the month is too short, so a month after 01-31 is 02-28
+    pub fn add_months (date: &str, months: u32) -> String
+    {
+        let (year, month, day) = parts (date);
+        let index = year * 12 + month - 1 + months;
+        let (year, month) = (index / 12, index % 12 + 1);
+        format! ("{year:04}-{month:02}-{:02}", day.min (days_in_month (year, month)))
+    }
+
+    #[cfg (test)]
+    mod tests
+    {
+        use super::*;
+
+        #[test]
+        fn date_and_time_round_trip ()
+        {
+            assert_eq! (date (0), "1970-01-01");
+            assert_eq! (date (951782400), "2000-02-29");
+            assert_eq! (date (951782400 + 86399), "2000-02-29");
+            // a little over four centuries, so 2100 and 2400 are in there
+            for day in 0..160000
+            {
+                let day_date = date (day * 86400);
+                assert_eq! (time (&day_date), day * 86400, "{day_date}");
+                assert_eq! (parse_date (&day_date), Ok (day_date.clone ()));
+            }
+        }
+
+        #[test]
+        fn days_between_counts_leap_days ()
+        {
+            assert_eq! (days_between ("2024-02-28", "2024-03-01"), 2);
+            assert_eq! (days_between ("2023-02-28", "2023-03-01"), 1);
+            assert_eq! (days_between ("2024-03-01", "2024-02-28"), -2);
+            assert_eq! (days_between ("2023-12-31", "2024-01-01"), 1);
+            assert_eq! (days_between ("2000-01-01", "2001-01-01"), 366);
+            assert_eq! (days_between ("2100-01-01", "2101-01-01"), 365);
+            for days in [0, 1, 29, 365, 366, 1000]
+            {
+                assert_eq! (days_between ("2024-01-31", &add_days ("2024-01-31", days)), days as i64);
+            }
+        }
+
+        #[test]
+        fn add_months_stops_at_the_end_of_short_months ()
+        {
+            assert_eq! (add_months ("2024-01-31", 1), "2024-02-29");
+            assert_eq! (add_months ("2023-01-31", 1), "2023-02-28");
+            assert_eq! (add_months ("2024-01-31", 2), "2024-03-31");
+            assert_eq! (add_months ("2024-11-15", 3), "2025-02-15");
+            assert_eq! (add_months ("2024-12-31", 12), "2025-12-31");
+        }
+
+        #[test]
+        fn weekdays_and_days_before_cross_months_and_years ()
+        {
+            assert_eq! (weekday ("1970-01-01"), 3);
+            assert_eq! (weekday ("2024-02-29"), 3);
+            assert_eq! (weekday ("2024-06-02"), 6);
+            assert_eq! (days_before ("2024-03-01", 1), "2024-02-29");
+            assert_eq! (days_before ("2025-01-03", 7), "2024-12-27");
+            assert_eq! (days_before ("1970-01-02", 5), "1970-01-01");
+        }
+
+        #[test]
+        fn parse_date_refuses_days_that_dont_exist ()
+        {
+            assert! (parse_date ("2023-02-29").is_err ());
+            assert! (parse_date ("2100-02-29").is_err ());
+            assert! (parse_date ("2024-04-31").is_err ());
+            assert! (parse_date ("2024-13-01").is_err ());
+            assert! (parse_date ("2024-1-01").is_err ());
+            assert_eq! (parse_date ("2024-02-29"), Ok (String::from ("2024-02-29")));
+        }
+    }
+}
+
+pub mod paths
+{
+    use std::env;
+    use std::path::PathBuf;
+
+    // where payments keeps its files on this platform. these follow the XDG
+    // base directory spec on linux and the usual places on macos and windows,
+    // None when the environment doesn't say where home is. PAYMENTS_CONFIG_DIR,
+    // PAYMENTS_DATA_DIR and PAYMENTS_CACHE_DIR take precedence over all of that
+    pub fn config_dir () -> Option<PathBuf>
+    {
+        if let Some (dir) = overridden ("PAYMENTS_CONFIG_DIR")
+        {
+            return Some (dir);
+        }
+        if cfg! (windows)
+        {
+            return from_env ("APPDATA");
+        }
+        if cfg! (target_os = "macos")
+        {
+            return home ("Library/Application Support");
+        }
+        from_env ("XDG_CONFIG_HOME").or_else (|| home (".config"))
+    }
+
+    // default ledgers, backups, history and attachments live under here
+    pub fn data_dir () -> Option<PathBuf>
+    {
+        if let Some (dir) = overridden ("PAYMENTS_DATA_DIR")
+        {
+            return Some (dir);
+        }
+        if cfg! (windows)
+        {
+            return from_env ("APPDATA");
+        }
+        if cfg! (target_os = "macos")
+        {
+            return home ("Library/Application Support");
+        }
+        from_env ("XDG_DATA_HOME").or_else (|| home (".local/share"))
+    }
+
+    pub fn cache_dir () -> Option<PathBuf>
+    {
+        if let Some (dir) = overridden ("PAYMENTS_CACHE_DIR")
+        {
+            return Some (dir);
+        }
+        if cfg! (windows)
+        {
+            return from_env ("LOCALAPPDATA");
+        }
+        if cfg! (target_os = "macos")
+        {
+            return home ("Library/Caches");
+        }
+        from_env ("XDG_CACHE_HOME").or_else (|| home (".cache"))
+    }
+
+    // a directory inside the data directory, like ledgers or backups
+    pub fn data_subdir (name: &str) -> Option<PathBuf>
+    {
+        data_dir ().map (|d| d.join (name))
+    }
+
+    // a directory named outright, used as it is
+    fn overridden (var: &str) -> Option<PathBuf>
+    {
+        env::var_os (var)
+            .filter (|v| !v.is_empty ())
+            .map (PathBuf::from)
+    }
+
+    // the directory a variable names with payments added on, the specs say
+    // to ignore the variable if it's empty or relative
+    fn from_env (var: &str) -> Option<PathBuf>
+    {
+        env::var_os (var)
+            .map (PathBuf::from)
+            .filter (|p| p.is_absolute ())
+            .map (|p| p.join ("payments"))
+    }
+
+    fn home (rest: &str) -> Option<PathBuf>
+    {
+        let var = if cfg! (windows) { "USERPROFILE" } else { "HOME" };
+        env::var_os (var)
+            .map (PathBuf::from)
+            .filter (|p| p.is_absolute ())
+            .map (|p| p.join (rest).join ("payments"))
+    }
+}
+
+pub mod config
+{
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+    use serde::{Serialize, Deserialize};
+
+    // defaults for a group of people, applied when the program starts
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    #[serde(default)]
+    pub struct Profile
+    {
+        // the ledger to load
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub file: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub currency: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub rounding: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub strict: Option<bool>,
+        // iso, day-first or month-first, see set dates
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub dates: Option<String>,
+        // added to the ledger if they aren't in it already
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub participants: Vec<String>,
+        // whoever uses the program, a participant like the others
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub name: Option<String>,
+        // save the ledger to its file after every change
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub autosave: Option<bool>,
+        // single letters standing for commands, as in p = "print"
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        pub shortcuts: BTreeMap<String, String>,
+    }
+
+    impl Profile
+    {
+        // this profile with anything it leaves out taken from another
+        fn or (self, defaults: &Profile) -> Profile
+        {
+            Profile {
+                file: self.file.or_else (|| defaults.file.clone ()),
+                currency: self.currency.or_else (|| defaults.currency.clone ()),
+                rounding: self.rounding.or_else (|| defaults.rounding.clone ()),
+                strict: self.strict.or (defaults.strict),
+                dates: self.dates.or_else (|| defaults.dates.clone ()),
+                name: self.name.or_else (|| defaults.name.clone ()),
+                autosave: self.autosave.or (defaults.autosave),
+                // a profile's own shortcuts win over the default ones
+                shortcuts: defaults.shortcuts.clone ().into_iter ().chain (self.shortcuts).collect (),
+                participants: if self.participants.is_empty ()
+                {
+                    defaults.participants.clone ()
+                }
+                else
+                {
+                    self.participants
+                },
+            }
+        }
+    }
+
+    // config.toml in the config directory. keys at the top are the defaults,
+    // and every [profile.NAME] table can override them for one group
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    #[serde(default)]
+    pub struct Config
+    {
+        #[serde(flatten)]
+        pub defaults: Profile,
+        #[serde(rename = "profile", skip_serializing_if = "BTreeMap::is_empty")]
+        pub profiles: BTreeMap<String, Profile>,
+    }
+
+    impl Config
+    {
+        pub fn path () -> Option<PathBuf>
+        {
+            crate::paths::config_dir ().map (|d| d.join ("config.toml"))
+        }
+
+        // the config file, or None if there isn't one
+        pub fn load () -> Result<Option<Config>, String>
+        {
+            let Some (path) = Self::path () else
+            {
+                return Ok (None);
+            };
+            let Ok (text) = std::fs::read_to_string (&path) else
+            {
+                return Ok (None);
+            };
+            match toml::from_str (&text)
+            {
+                Ok (config) => Ok (Some (config)),
+                Err (e) => Err (format! ("Error reading {}:\n{}", path.display (), e)),
+            }
+        }
+
+        pub fn save (&self) -> Result<(), String>
+        {
+            let Some (path) = Self::path () else
+            {
+                return Err (String::from ("There is no config directory to save to"));
+            };
+            let text = match toml::to_string (self)
+            {
+                Ok (text) => text,
+                Err (e) => return Err (format! ("Error serializing the config:\n{}", e)),
+            };
+            let written = path.parent ()
+                .map_or (Ok (()), std::fs::create_dir_all)
+                .and_then (|_| std::fs::write (&path, text));
+            match written
+            {
+                Ok (_) => Ok (()),
+                Err (_) => Err (format! ("Unable to open file {}", path.display ())),
+            }
+        }
+
+        // the defaults with a profile's settings on top, if one is named
+        pub fn profile (&self, name: Option<&str>) -> Result<Profile, String>
+        {
+            let Some (name) = name else
+            {
+                return Ok (self.defaults.clone ());
+            };
+            match self.profiles.get (name)
+            {
+                Some (profile) => Ok (profile.clone ().or (&self.defaults)),
+                None => Err (format! ("There is no profile named {name}")),
+            }
+        }
+    }
+}
+
+pub mod ids
+{
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // hands out ids and random numbers. everything that needs either should
+    // ask this so a seeded source can make the results reproducible
+    pub trait IdSource: Send + Sync
+    {
+        fn next_u64 (&self) -> u64;
+
+        // a 32 character hex id
+        fn next_id (&self) -> String
+        {
+            format! ("{:016x}{:016x}", self.next_u64 (), self.next_u64 ())
+        }
+    }
+
+    // different every time, seeded by the standard library's hash keys
+    pub struct RandomIds;
+
+    impl IdSource for RandomIds
+    {
+        fn next_u64 (&self) -> u64
+        {
+            RandomState::new ().build_hasher ().finish ()
+        }
+    }
+
+    // the same sequence for the same seed, using splitmix64
+    pub struct SeededIds
+    {
+        state: AtomicU64,
+    }
+
+    impl SeededIds
+    {
+        pub fn new (seed: u64) -> Self
+        {
+            Self { state: AtomicU64::new (seed) }
+        }
+    }
+
+    impl IdSource for SeededIds
+    {
+        fn next_u64 (&self) -> u64
+        {
+            let mut z = self.state
+                .fetch_add (0x9e3779b97f4a7c15, Ordering::SeqCst)
+                .wrapping_add (0x9e3779b97f4a7c15);
+            z = (z ^ (z >> 30)).wrapping_mul (0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul (0x94d049bb133111eb);
+            z ^ (z >> 31)
+        }
+    }
+}
+
+pub mod money
+{
+    use std::fmt;
+    use std::iter::Sum;
+    use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // an amount of money in cents. all of the arithmetic is done on whole
+    // cents so amounts like 10.10 stay exact. it's saved as a plain number of
+    // cents, which is how costs have always been saved
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    #[cfg_attr (feature = "schema", derive (schemars::JsonSchema))]
+    #[serde(transparent)]
+    pub struct Money (i64);
+
+    impl Money
+    {
+        pub const ZERO: Money = Money (0);
+
+        pub fn from_cents (cents: i64) -> Self
+        {
+            Self (cents)
+        }
+
+        pub fn cents (self) -> i64
+        {
+            self.0
+        }
+
+        // reads a decimal amount like 12, 12.5 or -12.50 without going through
+        // floating point
+        pub fn parse (text: &str) -> Result<Self, String>
+        {
+            let invalid = || format! ("{text} is not a valid amount");
+            let (negative, digits) = match text.strip_prefix ('-')
+            {
+                Some (rest) => (true, rest),
+                None => (false, text),
+            };
+            let (whole, fraction) = digits.split_once ('.').unwrap_or ((digits, ""));
+            if (whole.is_empty () && fraction.is_empty ())
+                || !whole.chars ().chain (fraction.chars ()).all (|c| c.is_ascii_digit ())
+            {
+                return Err (invalid ());
+            }
+            if fraction.len () > 2
+            {
+                return Err (format! ("{text} has more than two decimal places"));
+            }
+            let whole = match whole
+            {
+                "" => 0,
+                w => w.parse::<i64> ().map_err (|_| invalid ())?,
+            };
+            let fraction = format! ("{fraction:0<2}").parse::<i64> ().map_err (|_| invalid ())?;
+            let cents = whole.checked_mul (100)
+                .and_then (|c| c.checked_add (fraction))
+                .ok_or_else (|| format! ("{text} is too large"))?;
+            Ok (Self (if negative { -cents } else { cents }))
+        }
+
+        // reads an amount that can be written as arithmetic, like
+        // 17.50+23.10+4*3.2 when adding up a receipt. it's worked out exactly
+        // and only the result is rounded to the cent
+        pub fn eval (text: &str) -> Result<Self, String>
+        {
+            if !text.starts_with ('(') && !text.chars ().skip (1).any (|c| "+-*/()".contains (c))
+            {
+                return Self::parse (text);
+            }
+            let mut expression = Expression { text, chars: text.chars ().collect (), pos: 0 };
+            let value = expression.sum ()?;
+            if expression.pos < expression.chars.len ()
+            {
+                return Err (format! ("{text} has {} where an operator should be", expression.chars[expression.pos]));
+            }
+            value.to_cents ().map (Self).ok_or_else (|| format! ("{text} is too large"))
+        }
+
+        // reads an amount the way people write one, like $1,234.50 or +20 €,
+        // as well as anything eval takes. currency symbols are left out and
+        // commas are taken as thousands separators, so they have to fall
+        // between groups of three digits
+        pub fn input (text: &str) -> Result<Self, String>
+        {
+            let unsigned = text.strip_prefix ('+').unwrap_or (text);
+            let without = unsigned.chars ()
+                .filter (|c| !"$€£¥₹".contains (*c))
+                .collect::<String> ();
+            let chars = without.chars ().collect::<Vec<char>> ();
+            let mut cleaned = String::new ();
+            for (i, c) in chars.iter ().enumerate ()
+            {
+                if *c != ','
+                {
+                    cleaned.push (*c);
+                    continue;
+                }
+                // a digit before it, and exactly three after it
+                let group = chars[i + 1..].iter ().take_while (|c| c.is_ascii_digit ()).count ();
+                let fraction = chars[..i].iter ().rev ().take_while (|c| c.is_ascii_digit ()).count ();
+                let decimal = chars[..i - fraction.min (i)].last () == Some (&'.');
+                if i == 0 || fraction == 0 || group != 3 || decimal
+                {
+                    return Err (format! ("{text} has a comma that isn't a thousands separator"));
+                }
+            }
+            if cleaned.is_empty ()
+            {
+                return Err (format! ("{text} is not a valid amount"));
+            }
+            if cleaned == unsigned
+            {
+                return Self::eval (unsigned);
+            }
+            // errors say what was typed, not what it was cleaned up to
+            Self::eval (&cleaned).map_err (|e| e.replacen (&cleaned, text, 1))
+        }
+
+        // one of `count` even shares of this amount, rounded to the cent
+        pub fn share (self, count: usize) -> Self
+        {
+            let mut exact = ExactSum::default ();
+            exact.add_share (self, count);
+            exact.rounded ()
+        }
+
+        // splits the amount into `count` shares that add back up to it exactly,
+        // the cents that don't divide evenly go one each to the first shares
+        pub fn split (self, count: usize) -> Vec<Money>
+        {
+            let count = count as i64;
+            (0..count)
+                .map (|i| Money (self.0.div_euclid (count) + (self.0.rem_euclid (count) > i) as i64))
+                .collect ()
+        }
+
+        // splits the amount in proportion to some other amounts so the shares
+        // add back up to it exactly, the cents left over going one each to
+        // the first shares. with nothing to go by it is split evenly
+        pub fn split_by (self, amounts: &[Money]) -> Vec<Money>
+        {
+            let total = amounts.iter ().map (|a| a.0 as i128).sum::<i128> ();
+            if total == 0
+            {
+                return self.split (amounts.len ());
+            }
+            let mut shares = amounts.iter ()
+                .map (|a| Money ((self.0 as i128 * a.0 as i128).div_euclid (total) as i64))
+                .collect::<Vec<Money>> ();
+            let extra = self.0 - shares.iter ().map (|s| s.0).sum::<i64> ();
+            for share in shares.iter_mut ().take (extra.max (0) as usize)
+            {
+                share.0 += 1;
+            }
+            shares
+        }
+
+        // this amount multiplied by a factor, rounded to the cent
+        pub fn scale (self, factor: f64) -> Self
+        {
+            Self ((self.0 as f64 * factor).round () as i64)
+        }
+    }
+
+    // a fraction of whole numbers, so an expression like 10/3*3 comes out
+    // at exactly 10
+    #[derive(Clone, Copy)]
+    struct Fraction
+    {
+        num: i128,
+        den: i128,
+    }
+
+    impl Fraction
+    {
+        // keeps the denominator positive and as small as it can be
+        fn new (num: i128, den: i128) -> Self
+        {
+            let (mut a, mut b) = (num.abs (), den.abs ());
+            while b != 0
+            {
+                (a, b) = (b, a % b);
+            }
+            let gcd = a.max (1) * den.signum ();
+            Self { num: num / gcd, den: den / gcd }
+        }
+
+        fn combine (self, op: char, other: Self) -> Option<Self>
+        {
+            match op
+            {
+                '+' => Some (Self::new (self.num.checked_mul (other.den)?.checked_add (other.num.checked_mul (self.den)?)?
+                                        , self.den.checked_mul (other.den)?)),
+                '-' => Some (Self::new (self.num.checked_mul (other.den)?.checked_sub (other.num.checked_mul (self.den)?)?
+                                        , self.den.checked_mul (other.den)?)),
+                '*' => Some (Self::new (self.num.checked_mul (other.num)?, self.den.checked_mul (other.den)?)),
+                _ => Some (Self::new (self.num.checked_mul (other.den)?, self.den.checked_mul (other.num)?)),
+            }
+        }
+
+        // rounded to the nearest cent, halves away from zero
+        fn to_cents (self) -> Option<i64>
+        {
+            let hundreds = self.num.checked_mul (100)?;
+            let (whole, rest) = (hundreds / self.den, hundreds % self.den);
+            let cents = if rest.abs () * 2 >= self.den { whole + hundreds.signum () } else { whole };
+            i64::try_from (cents).ok ()
+        }
+    }
+
+    // reads sum := term (+|- term)*, term := factor (*|/ factor)* and
+    // factor := -factor | (sum) | number
+    struct Expression<'a>
+    {
+        text: &'a str,
+        chars: Vec<char>,
+        pos: usize,
+    }
+
+    impl Expression<'_>
+    {
+        fn peek (&self) -> Option<char>
+        {
+            self.chars.get (self.pos).copied ()
+        }
+
+        fn sum (&mut self) -> Result<Fraction, String>
+        {
+            let mut value = self.term ()?;
+            while let Some (op @ ('+' | '-')) = self.peek ()
+            {
+                self.pos += 1;
+                let other = self.term ()?;
+                value = value.combine (op, other).ok_or_else (|| format! ("{} is too large", self.text))?;
+            }
+            Ok (value)
+        }
+
+        fn term (&mut self) -> Result<Fraction, String>
+        {
+            let mut value = self.factor ()?;
+            while let Some (op @ ('*' | '/')) = self.peek ()
+            {
+                self.pos += 1;
+                let other = self.factor ()?;
+                if op == '/' && other.num == 0
+                {
+                    return Err (format! ("{} divides by zero", self.text));
+                }
+                value = value.combine (op, other).ok_or_else (|| format! ("{} is too large", self.text))?;
+            }
+            Ok (value)
+        }
+
+        fn factor (&mut self) -> Result<Fraction, String>
+        {
+            match self.peek ()
+            {
+                Some ('-') =>
+                {
+                    self.pos += 1;
+                    let value = self.factor ()?;
+                    Ok (Fraction { num: -value.num, den: value.den })
+                },
+                Some ('(') =>
+                {
+                    self.pos += 1;
+                    let value = self.sum ()?;
+                    if self.peek () != Some (')')
+                    {
+                        return Err (format! ("{} is missing a )", self.text));
+                    }
+                    self.pos += 1;
+                    Ok (value)
+                },
+                _ => self.number (),
+            }
+        }
+
+        // a decimal number, which unlike an amount can have any number of
+        // decimal places, as in 1.075 for sales tax
+        fn number (&mut self) -> Result<Fraction, String>
+        {
+            let start = self.pos;
+            while self.peek ().is_some_and (|c| c.is_ascii_digit () || c == '.')
+            {
+                self.pos += 1;
+            }
+            let number = self.chars[start..self.pos].iter ().collect::<String> ();
+            let (whole, fraction) = number.split_once ('.').unwrap_or ((&number, ""));
+            if (whole.is_empty () && fraction.is_empty ()) || fraction.contains ('.')
+            {
+                return Err (match self.peek ()
+                {
+                    Some (c) if number.is_empty () => format! ("{} has {c} where a number should be", self.text),
+                    _ if number.is_empty () => format! ("{} ends where a number should be", self.text),
+                    _ => format! ("{number} is not a valid number"),
+                });
+            }
+            let too_large = || format! ("{} is too large", self.text);
+            let num = format! ("{whole}{fraction}").parse::<i128> ().map_err (|_| too_large ())?;
+            let den = 10i128.checked_pow (fraction.len () as u32).ok_or_else (too_large)?;
+            Ok (Fraction::new (num, den))
+        }
+    }
+
+    impl fmt::Display for Money
+    {
+        fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+        {
+            let sign = if self.0 < 0 { "-" } else { "" };
+            let text = format! ("{sign}{}.{:02}", self.0.unsigned_abs () / 100, self.0.unsigned_abs () % 100);
+            f.pad (&text)
+        }
+    }
+
+    impl Add for Money
+    {
+        type Output = Money;
+
+        fn add (self, other: Money) -> Money
+        {
+            Money (self.0 + other.0)
+        }
+    }
+
+    impl Sub for Money
+    {
+        type Output = Money;
+
+        fn sub (self, other: Money) -> Money
+        {
+            Money (self.0 - other.0)
+        }
+    }
+
+    impl Neg for Money
+    {
+        type Output = Money;
+
+        fn neg (self) -> Money
+        {
+            Money (-self.0)
+        }
+    }
+
+    impl Mul<i64> for Money
+    {
+        type Output = Money;
+
+        fn mul (self, times: i64) -> Money
+        {
+            Money (self.0 * times)
+        }
+    }
+
+    impl AddAssign for Money
+    {
+        fn add_assign (&mut self, other: Money)
+        {
+            self.0 += other.0;
+        }
+    }
+
+    impl SubAssign for Money
+    {
+        fn sub_assign (&mut self, other: Money)
+        {
+            self.0 -= other.0;
+        }
+    }
+
+    impl Sum for Money
+    {
+        fn sum<I: Iterator<Item = Money>> (iter: I) -> Money
+        {
+            Money (iter.map (|m| m.0).sum ())
+        }
+    }
+
+    impl<'a> Sum<&'a Money> for Money
+    {
+        fn sum<I: Iterator<Item = &'a Money>> (iter: I) -> Money
+        {
+            Money (iter.map (|m| m.0).sum ())
+        }
+    }
+
+    // adds up uneven shares like a third of a cost exactly, as a fraction of
+    // cents, so rounding only happens once at the end
+    #[derive(Clone, Copy, Debug)]
+    pub struct ExactSum
+    {
+        numerator: i128,
+        denominator: i128,
+    }
+
+    impl Default for ExactSum
+    {
+        fn default () -> Self
+        {
+            Self { numerator: 0, denominator: 1 }
+        }
+    }
+
+    impl ExactSum
+    {
+        // adds amount / count
+        pub fn add_share (&mut self, amount: Money, count: usize)
+        {
+            if count == 0
+            {
+                return;
+            }
+            let count = count as i128;
+            self.numerator = self.numerator * count + amount.0 as i128 * self.denominator;
+            self.denominator *= count;
+            let divisor = gcd (self.numerator.abs (), self.denominator);
+            if divisor > 1
+            {
+                self.numerator /= divisor;
+                self.denominator /= divisor;
+            }
+        }
+
+        pub fn add (&mut self, amount: Money)
+        {
+            self.add_share (amount, 1);
+        }
+
+        pub fn sub (&mut self, amount: Money)
+        {
+            self.add_share (-amount, 1);
+        }
+
+        // the sum rounded to the nearest cent, halves away from zero
+        pub fn rounded (&self) -> Money
+        {
+            let half_up = (2 * self.numerator.abs () + self.denominator) / (2 * self.denominator);
+            Money (if self.numerator < 0 { -half_up } else { half_up } as i64)
+        }
+    }
+
+    fn gcd (mut a: i128, mut b: i128) -> i128
+    {
+        while b != 0
+        {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+
+    // saves an optional amount as a decimal number of whole units, the way
+    // balances have always been saved
+    pub mod decimal
+    {
+        use super::*;
+
+        pub fn serialize<S: Serializer> (value: &Option<Money>, serializer: S) -> Result<S::Ok, S::Error>
+        {
+            match value
+            {
+                Some (m) => serializer.serialize_f64 (m.0 as f64 / 100f64),
+                None => serializer.serialize_none (),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>> (deserializer: D) -> Result<Option<Money>, D::Error>
+        {
+            let value = Option::<f64>::deserialize (deserializer)?;
+            Ok (value.map (|v| Money ((v * 100f64).round () as i64)))
+        }
     }
 }
@@ -1,35 +1,303 @@
 use std::io;
-use std::io::Write;
-use payments::payments::Payment;
+use std::io::{IsTerminal, Write};
+use std::env;
+use std::path::{Path, PathBuf};
+use payments::config::{Config, Profile};
+use payments::paths;
+use payments::payments::{Confirmer, Disambiguator, Payment, USAGE};
+
+// asks the user which of the names they meant
+fn choose (typed: &str, candidates: &[String]) -> Option<String>
+{
+    println! ("{typed} could be:");
+    for (i, name) in candidates.iter ().enumerate ()
+    {
+        println! ("  {}) {}", i + 1, name);
+    }
+    print! ("which one? ");
+    io::stdout ().flush ().ok ()?;
+    let mut input = String::new ();
+    io::stdin ().read_line (&mut input).ok ()?;
+    let input = input.trim ();
+    match input.parse::<usize> ()
+    {
+        Ok (i) if i >= 1 && i <= candidates.len () => Some (candidates[i - 1].clone ()),
+        _ => Some (String::from (input)),
+    }
+}
+
+// whether a PAYMENTS_* switch like PAYMENTS_NO_COLOR is turned on
+fn env_flag (name: &str) -> bool
+{
+    env::var (name).is_ok_and (|v| !v.is_empty () && v != "0")
+}
+
+// asks a question and returns the trimmed answer
+fn ask (question: &str) -> String
+{
+    print! ("{question}");
+    io::stdout ().flush ().ok ();
+    let mut input = String::new ();
+    io::stdin ().read_line (&mut input).ok ();
+    String::from (input.trim ())
+}
+
+// what was typed so far when it carries on onto the next line, which it
+// does after a trailing backslash or while a quote is left open
+fn unfinished (input: &str) -> Option<String>
+{
+    let input = input.trim_end ();
+    if let Some (rest) = input.strip_suffix ('\\')
+    {
+        return Some (String::from (rest));
+    }
+    (input.matches ('"').count () % 2 == 1).then (|| String::from (input))
+}
+
+// walks a new user through writing a config file. saying no still writes an
+// empty one so the question isn't asked every time
+fn setup () -> Config
+{
+    let mut config = Config::default ();
+    if !ask ("No config file was found, set one up now? [Y/n] ").to_lowercase ().starts_with ('n')
+    {
+        let profile = &mut config.defaults;
+        loop
+        {
+            let currency = ask ("Currency amounts are in, like USD (blank for none): ");
+            if currency.is_empty ()
+            {
+                break;
+            }
+            match Payment::new ().set_setting ("currency", &currency)
+            {
+                Ok (()) =>
+                {
+                    profile.currency = Some (currency.to_uppercase ());
+                    break;
+                },
+                Err (msg) => println! ("{msg}"),
+            }
+        }
+        let default_file = paths::data_subdir ("ledgers").map (|d| d.join ("default.json"));
+        let file = match &default_file
+        {
+            Some (d) => ask (&format! ("Ledger to open at startup [{}]: ", d.display ())),
+            None => ask ("Ledger to open at startup (blank for none): "),
+        };
+        let file = if file.is_empty () { default_file } else { Some (PathBuf::from (file)) };
+        // so the first save has somewhere to go
+        if let Some (dir) = file.as_ref ().and_then (|f| f.parent ())
+        {
+            std::fs::create_dir_all (dir).ok ();
+        }
+        profile.file = file.map (|f| f.display ().to_string ());
+        loop
+        {
+            let name = ask ("Your name (blank to skip): ");
+            if !name.contains (char::is_whitespace)
+            {
+                profile.name = Some (name).filter (|n| !n.is_empty ());
+                break;
+            }
+            println! ("names can't contain spaces");
+        }
+        profile.autosave = Some (ask ("Save the ledger after every change? [y/N] ").to_lowercase ().starts_with ('y'));
+    }
+    match config.save ()
+    {
+        Ok (()) =>
+        {
+            if let Some (path) = Config::path ()
+            {
+                println! ("wrote {}", path.display ());
+            }
+        },
+        Err (msg) => eprintln! ("{}", msg),
+    }
+    config
+}
+
+// sets the ledger up the way a profile from the config file says
+fn apply_profile (pay: &mut Payment, profile: &Profile)
+{
+    // a ledger that hasn't been saved yet is created by the first save
+    if let Some (file) = profile.file.as_ref ().filter (|f| Path::new (f).exists ())
+    {
+        if let Err (msg) = pay.command (&format! ("load {file}\n"))
+        {
+            eprintln! ("{}", msg);
+        }
+    }
+    let strict = profile.strict.map (|s| String::from (if s { "on" } else { "off" }));
+    for (key, value) in [("currency", &profile.currency), ("rounding", &profile.rounding), ("strict", &strict), ("dates", &profile.dates)]
+    {
+        if let Some (value) = value
+        {
+            if let Err (msg) = pay.set_setting (key, value)
+            {
+                eprintln! ("{key}: {msg}");
+            }
+        }
+    }
+    for (letter, command) in &profile.shortcuts
+    {
+        if let Err (msg) = pay.set_shortcut (letter, command)
+        {
+            eprintln! ("shortcut {letter}: {msg}");
+        }
+    }
+    for name in profile.name.iter ().chain (&profile.participants)
+    {
+        // anyone already in the ledger is left as they are
+        pay.command (&format! ("add {name}\n")).ok ();
+    }
+}
 
 fn main ()
 {
-    // 'add' adds a particpant 
-    // 'part' sets a participant as a participant of a task, so they have to
-    // pay as part of that task
-    // 'pay' is for the people that paid for a task and need money back, they
-    // are automatically added as participants of that task
-    // 'print' prints out the list of participants and how much they pay
-    println! ("usage:\n\
-			  add NAME...\n\
-			  part TASK PARTICIPANT...\n\
-			  pay PARTICIPANT TASK AMOUNT\n\
-			  print [-a|NAME...]\n");
+    // `payments paths` shows where files are kept without starting the prompt
+    if env::args ().nth (1).as_deref () == Some ("paths")
+    {
+        Payment::new ().command ("paths\n").ok ();
+        return;
+    }
+    println! ("usage:");
+    for line in USAGE
+    {
+        println! ("{line}");
+    }
+    println! ();
     let mut pay = Payment::new ();
+    // --profile NAME picks one of the profiles in the config file
+    let profile_name = env::args ().skip_while (|arg| arg != "--profile").nth (1);
+    let profile = match Config::load ()
+    {
+        Ok (Some (config)) => config.profile (profile_name.as_deref ()),
+        Ok (None) if profile_name.is_some () => Err (String::from ("There is no config file to take profiles from")),
+        // only ask on the first run when somebody is there to answer
+        Ok (None) if io::stdin ().is_terminal () && Config::path ().is_some () => Ok (setup ().defaults),
+        Ok (None) => Ok (Profile::default ()),
+        Err (msg) => Err (msg),
+    };
+    let mut profile = match profile
+    {
+        Ok (profile) => profile,
+        Err (msg) =>
+        {
+            eprintln! ("{}", msg);
+            return;
+        },
+    };
+    // PAYMENTS_* variables do the same as the flags, so the program can be
+    // set up without a command line, as in a container
+    // --prefix lets names be shortened to any unambiguous prefix
+    if env::args ().any (|arg| arg == "--prefix") || env_flag ("PAYMENTS_PREFIX")
+    {
+        pay.set_disambiguator (Some (Disambiguator::new (choose)));
+    }
+    // amounts over the ledger's limit are asked about when somebody is there
+    // to answer, and need --confirm otherwise
+    if io::stdin ().is_terminal ()
+    {
+        pay.set_confirmer (Some (Confirmer::new (|question| ask (&format! ("{question} [y/N] ")).to_lowercase ().starts_with ('y'))));
+    }
+    pay.set_recent_file (paths::data_dir ().map (|d| d.join ("recent")));
+    pay.set_usage_file (paths::data_dir ().map (|d| d.join ("usage")));
+    pay.set_key_file (paths::data_dir ().map (|d| d.join ("signing-key")));
+    let recent = pay.recent_files ();
+    if !recent.is_empty ()
+    {
+        println! ("recent ledgers:");
+        for (i, file) in recent.iter ().enumerate ()
+        {
+            println! ("  {}) {}", i + 1, file);
+        }
+    }
+    // --timings reports how long each part of every command took
+    pay.set_timings (env::args ().any (|arg| arg == "--timings") || env_flag ("PAYMENTS_TIMINGS"));
+    if let Ok (file) = env::var ("PAYMENTS_FILE")
+    {
+        profile.file = Some (file);
+    }
+    apply_profile (&mut pay, &profile);
+    for (var, key) in [("PAYMENTS_CURRENCY", "currency"), ("PAYMENTS_ROUNDING", "rounding")]
+    {
+        if let Ok (value) = env::var (var)
+        {
+            if let Err (msg) = pay.set_setting (key, &value)
+            {
+                eprintln! ("{var}: {msg}");
+            }
+        }
+    }
+    let color = !env_flag ("PAYMENTS_NO_COLOR") && !env_flag ("NO_COLOR");
     loop
     {
         print! ("payments$ ");
         io::stdout ().flush ().expect ("Something went wrong");
         let mut input = String::new ();
-        io::stdin ()
+        let read = io::stdin ()
             .read_line (&mut input)
             .expect ("failed to read from stdin");
-        let result = pay.command (&input);
-        match result
+        // the input ran out, as at the end of a piped script
+        if read == 0
+        {
+            println! ();
+            break;
+        }
+        while let Some (start) = unfinished (&input)
+        {
+            print! ("... ");
+            io::stdout ().flush ().expect ("Something went wrong");
+            let mut more = String::new ();
+            let read = io::stdin ()
+                .read_line (&mut more)
+                .expect ("failed to read from stdin");
+            // nothing more is coming, run what there is
+            if read == 0
+            {
+                input = start + "\n";
+                break;
+            }
+            input = start + " " + &more;
+        }
+        // several commands can go on one line, separated by semicolons
+        let results = pay.command_line (&input);
+        let several = results.len () > 1;
+        for (com, result) in results
+        {
+            match result
+            {
+                Ok (()) => (),
+                Err (msg) if several => eprintln! ("{com}: {msg}"),
+                Err (msg) => eprintln! ("{}", msg),
+            }
+        }
+        if let (Some (true), Some (file)) = (profile.autosave, &profile.file)
+        {
+            if pay.is_dirty ()
+            {
+                if let Err (msg) = pay.command (&format! ("save {file}\n"))
+                {
+                    eprintln! ("{}", msg);
+                }
+            }
+        }
+        for warning in pay.take_warnings ()
+        {
+            if color
+            {
+                eprintln! ("\x1b[33mwarning: {}\x1b[0m", warning);
+            }
+            else
+            {
+                eprintln! ("warning: {}", warning);
+            }
+        }
+        for (phase, elapsed) in pay.take_timings ()
         {
-            Ok (()) => (),
-            Err (msg) => eprintln! ("{}", msg),
+            eprintln! ("{phase}: {elapsed:?}");
         }
-        //dbg! (&pay);
     }
 }
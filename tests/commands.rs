@@ -0,0 +1,742 @@
+use payments::payments::Payment;
+
+// runs every command in order, failing on the first that doesn't work
+fn ledger (commands: &[&str]) -> Payment
+{
+    let mut pay = Payment::new ();
+    for com in commands
+    {
+        if let Err (e) = pay.command (&format! ("{com}\n"))
+        {
+            panic! ("{com}: {e}");
+        }
+    }
+    pay
+}
+
+#[test]
+fn compact_forgets_exclusions_of_removed_participants ()
+{
+    let mut pay = ledger (&["add alice bob carol", "pay alice rent 30", "part rent bob", "unpart rent carol", "compact"]);
+    assert_eq! (pay.command ("export --anonymize\n"), Ok (()));
+}
+
+#[test]
+fn compact_keeps_dues_members ()
+{
+    let mut pay = ledger (&["add alice bob carol", "pay alice rent 30", "part rent bob", "dues 10/month carol", "compact"]);
+    assert_eq! (pay.command ("dues remind\n"), Ok (()));
+    assert_eq! (pay.command ("remove carol\n"), Ok (()));
+    assert_eq! (pay.command ("dues remind\n"), Ok (()));
+}
+
+#[test]
+fn compact_removes_participants_from_groups ()
+{
+    let mut pay = ledger (&["add alice bob carol dave", "pay alice rent 30", "part rent bob", "group kids carol bob"
+                            , "group guests dave", "compact", "set strict on"]);
+    assert_eq! (pay.command ("pay alice food 20\n"), Ok (()));
+    assert_eq! (pay.command ("part food kids\n"), Ok (()));
+    assert! (pay.take_warnings ().is_empty ());
+    // guests was left empty, so the name is free again
+    assert_eq! (pay.command ("add guests\n"), Ok (()));
+}
+
+// the ledger the way it's saved
+fn saved (pay: &mut Payment, name: &str) -> serde_json::Value
+{
+    let file = std::env::temp_dir ().join (format! ("payments-test-{}-{name}.json", std::process::id ()));
+    pay.command (&format! ("save {}\n", file.display ())).unwrap ();
+    let json = serde_json::from_str (&std::fs::read_to_string (&file).unwrap ()).unwrap ();
+    std::fs::remove_file (&file).ok ();
+    json
+}
+
+#[test]
+fn convert_reaches_archived_recurring_and_subledger_tasks ()
+{
+    let setup = ["rate base USD", "rate EUR 2", "add a b", "pay a old 10 EUR", "part old b", "archive old"
+                 , "pay a rent 10 EUR", "recur rent monthly"
+                 , "in trip rate base USD", "in trip rate EUR 2", "in trip add a", "in trip pay a hotel 10 EUR"];
+    let mut pay = ledger (&setup);
+    pay.command ("convert EUR USD 2\n").unwrap ();
+    let json = saved (&mut pay, "convert");
+    for task in [&json["archived"]["old"], &json["recurring"]["rent"]["task"], &json["tasks"]["rent"]
+                 , &json["subledgers"]["trip"]["tasks"]["hotel"]]
+    {
+        assert_eq! (task["cost"], 2000, "{task}");
+        assert! (task.get ("currency").is_none (), "{task}");
+    }
+}
+
+#[test]
+fn payments_keep_their_currency_through_convert ()
+{
+    let mut pay = ledger (&["rate base USD", "rate EUR 2", "add a b", "pay a x 10", "part x b", "payment b a 2 EUR taxi"]);
+    // 2 EUR are worth 4 USD, which is what b still owes of their 5
+    let print = output (&mut pay, "print b");
+    assert! (print.starts_with ("b owes 1.00\n") && print.contains ("paid a 2.00 EUR (4.00 USD): taxi"), "{print}");
+    assert! (pay.command ("rate EUR none\n").is_err ());
+    // only what was paid in EUR
+    pay.command ("convert EUR GBP 1.5\n").unwrap_err ();
+    pay.command ("rate GBP 2.5\n").unwrap ();
+    pay.command ("convert EUR GBP 0.8\n").unwrap ();
+    let transfer = &saved (&mut pay, "payment-in-gbp")["transfers"][0];
+    assert_eq! ((&transfer["amount"], &transfer["currency"]), (&serde_json::json! (160), &serde_json::json! ("GBP")));
+    // the whole ledger, with the payment already in the new currency
+    pay.command ("convert USD GBP 0.4\n").unwrap ();
+    let json = saved (&mut pay, "ledger-in-gbp");
+    assert_eq! (json["transfers"][0]["amount"], 160);
+    assert! (json["transfers"][0].get ("currency").is_none (), "{json}");
+    assert_eq! (json["tasks"]["x"]["cost"], 400);
+}
+
+#[test]
+fn convert_changes_nothing_when_it_fails ()
+{
+    let mut pay = ledger (&["rate base USD", "rate EUR 2", "add a", "pay a x 10 EUR", "in trip add a"]);
+    // GBP has no rate, so the task can't be paid in it
+    assert! (pay.command ("convert EUR GBP 0.85\n").is_err ());
+    assert! (pay.command ("convert CHF USD 1.1\n").is_err ());
+    assert_eq! (saved (&mut pay, "failed")["tasks"]["x"]["cost"], 1000);
+}
+
+#[test]
+fn interest_rates_finer_than_a_basis_point_are_refused ()
+{
+    let mut pay = Payment::new ();
+    assert! (pay.command ("interest 1.125% monthly\n").is_err ());
+    assert! (pay.command ("interest 0% monthly\n").is_err ());
+    assert_eq! (pay.command ("interest 1.250% monthly after 10\n"), Ok (()));
+    assert_eq! (saved (&mut pay, "interest")["interest"]["rate"], 125);
+}
+
+#[cfg (feature = "schema")]
+#[test]
+fn schema_covers_everything_saved ()
+{
+    let mut pay = ledger (&["add a b", "pay a x 10 --tip 10", "part x b", "interest 1.5% monthly", "dues 5/month all"
+                            , "cashbox in 3 --from a", "rule max-cost 100", "recur x monthly", "payment b a 2 --for x"]);
+    let schema: serde_json::Value = serde_json::from_str (&Payment::json_schema ()).unwrap ();
+    for key in saved (&mut pay, "schema").as_object ().unwrap ().keys ()
+    {
+        assert! (schema["properties"].get (key).is_some (), "{key} is missing from the schema");
+    }
+}
+
+#[test]
+fn bundles_load_back_as_the_same_ledger ()
+{
+    let mut pay = ledger (&["add a b", "pay a x 10", "part x b", "payment b a 2 \"back\""]);
+    let file = std::env::temp_dir ().join (format! ("payments-test-{}-bundle.zip", std::process::id ()));
+    pay.command (&format! ("export bundle {}\n", file.display ())).unwrap ();
+    let mut copy = Payment::new ();
+    let loaded = copy.command (&format! ("import bundle {}\n", file.display ()));
+    std::fs::remove_file (&file).ok ();
+    assert_eq! (loaded, Ok (()));
+    assert_eq! (saved (&mut copy, "bundle-copy"), saved (&mut pay, "bundle"));
+}
+
+#[test]
+fn refunds_stay_within_the_cost ()
+{
+    let mut pay = ledger (&["add x y", "pay x food 10", "part food y"]);
+    assert! (pay.command ("refund food 11\n").is_err ());
+    assert! (pay.command ("refund food -1\n").is_err ());
+    assert_eq! (pay.command ("refund food 4\n"), Ok (()));
+    assert_eq! (pay.command ("refund food 7\n"), Err (String::from ("food only cost 10.00 and 4.00 was refunded already")));
+    assert_eq! (pay.command ("refund food 6\n"), Ok (()));
+}
+
+#[test]
+fn discounts_stay_within_the_cost ()
+{
+    let mut pay = ledger (&["add x y", "pay x food 10", "part food y"]);
+    assert! (pay.command ("discount food 101%\n").is_err ());
+    assert! (pay.command ("discount food 0%\n").is_err ());
+    assert! (pay.command ("discount food 11\n").is_err ());
+    assert! (pay.command ("discount food -1\n").is_err ());
+    assert_eq! (pay.command ("refund food 10\n"), Ok (()));
+    assert_eq! (pay.command ("discount food 1\n")
+                , Err (String::from ("More of food was refunded than it would cost after the discount")));
+}
+
+#[test]
+fn tick_adds_each_month_up_to_today ()
+{
+    use payments::clock::FixedClock;
+    use std::sync::Arc;
+
+    let clock = Arc::new (FixedClock::new (0));
+    let mut pay = Payment::new ();
+    pay.set_clock (clock.clone ());
+    for com in ["add a b", "pay a rent 10 --date 2024-01-31", "part rent b", "recur rent monthly"]
+    {
+        pay.command (&format! ("{com}\n")).unwrap ();
+    }
+    // 2024-04-30
+    clock.set (1714435200);
+    pay.command ("tick\n").unwrap ();
+    let tasks = saved (&mut pay, "tick")["tasks"].as_object ().unwrap ().keys ().cloned ().collect::<Vec<_>> ();
+    assert_eq! (tasks, ["rent", "rent-2024-02-29", "rent-2024-03-31", "rent-2024-04-30"]);
+    // nothing new is due until the next month comes around
+    clock.advance (86400 * 30);
+    pay.command ("tick\n").unwrap ();
+    assert_eq! (saved (&mut pay, "tick")["tasks"].as_object ().unwrap ().len (), 4);
+    clock.advance (86400);
+    pay.command ("tick\n").unwrap ();
+    assert! (saved (&mut pay, "tick")["tasks"].get ("rent-2024-05-31").is_some ());
+}
+
+#[test]
+fn shared_ledgers_take_bare_commands_and_hand_back_the_output ()
+{
+    use payments::shared::SharedLedger;
+
+    let shared = SharedLedger::new (ledger (&["add a b", "pay a x 10", "part x b", "in trip add c"]));
+    let reply = shared.command ("print").unwrap ();
+    assert_eq! (reply.seq, 0);
+    assert! (reply.output.contains ("b owes 5.00"), "{}", reply.output);
+    assert_eq! (shared.command ("settle").unwrap ().output, "b pays a 5.00\n");
+    assert_eq! (shared.command ("in trip print").unwrap ().seq, 0);
+    assert! (shared.command ("").is_err ());
+    assert! (shared.command ("pay b").is_err ());
+    assert_eq! (shared.command ("add d").unwrap ().seq, 1);
+    assert_eq! (shared.command ("in trip add e\n").unwrap ().seq, 2);
+    assert_eq! (shared.seq (), Ok (2));
+}
+
+// what a command prints
+fn output (pay: &mut Payment, com: &str) -> String
+{
+    pay.capture_output (true);
+    let result = pay.command (&format! ("{com}\n"));
+    assert_eq! (result, Ok (()), "{com}");
+    pay.take_output ()
+}
+
+#[test]
+fn settle_pairs_the_largest_debts_first ()
+{
+    let mut pay = ledger (&["add a b c d", "pay a x 20", "part x c", "pay b y 10", "part y d"]);
+    assert_eq! (output (&mut pay, "settle"), "c pays a 10.00\nd pays b 5.00\n");
+    // one debtor and three creditors can't take fewer than three transfers
+    let mut pay = ledger (&["add a b c d", "pay b x 12", "pay c y 12", "pay d z 12", "part x a", "part y a", "part z a"]);
+    assert_eq! (output (&mut pay, "settle"), "a pays b 6.00\na pays c 6.00\na pays d 6.00\n");
+    // nothing to do for a ledger that is already even
+    let mut pay = ledger (&["add a b", "pay a x 10", "pay b y 10", "part x b", "part y a"]);
+    assert_eq! (output (&mut pay, "settle"), "");
+}
+
+#[test]
+fn settle_leaves_only_the_cent_rounding_left_over ()
+{
+    let setup = ["add a b c", "pay a x 10", "part x b c"];
+    // without a rounding policy the cent a third doesn't cover is nobody's
+    let mut pay = ledger (&setup);
+    assert_eq! (output (&mut pay, "settle --apply"), "b pays a 3.33\nc pays a 3.33\n");
+    assert_eq! (output (&mut pay, "settle"), "");
+    assert! (output (&mut pay, "print").contains ("a owes -0.01"));
+    // with one it goes to somebody, so settling evens everyone out
+    let mut pay = ledger (&setup);
+    pay.command ("set rounding owner\n").unwrap ();
+    assert_eq! (output (&mut pay, "settle --apply"), "b pays a 3.33\nc pays a 3.33\n");
+    assert! (output (&mut pay, "print").contains ("a owes 0.00"));
+}
+
+#[test]
+fn settle_by_currency_pays_back_in_the_currency_lent ()
+{
+    let mut pay = ledger (&["rate base USD", "rate EUR 2", "add a b c", "pay a x 30", "part x b c", "pay b y 9 EUR", "part y a c"]);
+    assert_eq! (output (&mut pay, "settle"), "c pays a 14.00\nc pays b 2.00\n");
+    // a owes b 3 EUR, which is taken off the 10 USD b owes a
+    assert_eq! (output (&mut pay, "settle --by-currency"), "b pays a 4.00\nc pays a 10.00\nc pays b 3.00 EUR\n");
+    pay.command ("settle --by-currency --apply\n").unwrap ();
+    assert_eq! (output (&mut pay, "settle"), "");
+    assert_eq! (saved (&mut pay, "by-currency")["transfers"][2]["currency"], "EUR");
+}
+
+#[test]
+fn settle_by_tag_leaves_the_other_balances_running ()
+{
+    let mut pay = ledger (&["add a b c", "pay a x 30", "part x b c", "tag x trip", "pay b y 12", "part y c", "pay c z 6", "part z a b"
+                            , "tag z trip", "payment c a 1 --for z"]);
+    assert_eq! (output (&mut pay, "settle --tag trip"), "b pays a 12.00\nc pays a 5.00\n");
+    pay.command ("settle --tag trip --apply\n").unwrap ();
+    assert_eq! (output (&mut pay, "settle --tag trip"), "");
+    assert_eq! (output (&mut pay, "settle"), "c pays b 6.00\n");
+    assert_eq! (saved (&mut pay, "by-tag")["transfers"][2]["tag"], "trip");
+    assert_eq! (pay.command ("settle --tag food\n"), Err (String::from ("No task is tagged food")));
+}
+
+#[test]
+fn since_settlement_starts_from_the_settled_balances ()
+{
+    let mut pay = ledger (&["add a b c", "pay a x 30", "part x b c", "settle --apply"]);
+    assert_eq! (output (&mut pay, "settle"), "");
+    assert! (output (&mut pay, "report --since-last-settlement").ends_with ("  nothing happened\n"));
+    pay.command ("pay c y 6\n").unwrap ();
+    pay.command ("part y a\n").unwrap ();
+    let since = output (&mut pay, "report --since-last-settlement");
+    assert! (!since.contains (" x"), "{since}");
+    assert! (since.contains ("  c paid 6.00 for y\n  a owes 3.00 more\n  c is owed 3.00 more\n"), "{since}");
+    assert_eq! (output (&mut pay, "settle"), "a pays c 3.00\n");
+}
+
+#[test]
+fn ledgerlog_is_in_date_order_and_balances ()
+{
+    let file = std::env::temp_dir ().join (format! ("payments-test-{}-ledgerlog.csv", std::process::id ()));
+    let mut pay = ledger (&["add a b c", "pay a x 30 --date 2024-03-01", "pay b x +10", "part x c"
+                            , "pay b y 10 --date 2024-01-05", "part y a", "payment c a 4 --date 2024-02-01"]);
+    pay.command (&format! ("export ledgerlog {}\n", file.display ())).unwrap ();
+    let log = std::fs::read_to_string (&file).unwrap ();
+    std::fs::remove_file (&file).ok ();
+    assert_eq! (log, "date,event,task,participant,debit,credit,memo\n\
+                      2024-01-05,paid,y,b,,10.00,\n\
+                      2024-01-05,share,y,a,5.00,,\n\
+                      2024-01-05,share,y,b,5.00,,\n\
+                      2024-02-01,payment,,c,,4.00,\n\
+                      2024-02-01,payment,,a,4.00,,\n\
+                      2024-03-01,paid,x,a,,20.00,\n\
+                      2024-03-01,paid,x,b,,10.00,\n\
+                      2024-03-01,share,x,a,10.00,,\n\
+                      2024-03-01,share,x,b,10.00,,\n\
+                      2024-03-01,share,x,c,10.00,,\n");
+}
+
+#[test]
+fn ledgerlog_gives_refunds_their_own_entries ()
+{
+    let file = std::env::temp_dir ().join (format! ("payments-test-{}-refunds.csv", std::process::id ()));
+    let mut pay = ledger (&["add a b c", "pay a x 30 --date 2024-03-01", "part x b c", "refund x 6 deposit"]);
+    pay.command (&format! ("export ledgerlog {}\n", file.display ())).unwrap ();
+    let log = std::fs::read_to_string (&file).unwrap ();
+    std::fs::remove_file (&file).ok ();
+    let refunds = log.lines ().filter (|l| l.contains (",refund,")).map (|l| l.split_once (',').unwrap ().1).collect::<Vec<&str>> ();
+    assert_eq! (refunds, ["refund,x,a,6.00,,deposit", "refund,x,a,,2.00,deposit", "refund,x,b,,2.00,deposit", "refund,x,c,,2.00,deposit"]);
+    // every debit has a matching credit
+    let column = |i: usize| log.lines ().skip (1)
+        .map (|l| l.split (',').nth (i).unwrap ().parse::<f64> ().unwrap_or (0.0))
+        .sum::<f64> ();
+    assert! ((column (4) - column (5)).abs () < 0.001);
+}
+
+#[test]
+fn usage_is_counted_outside_the_ledger ()
+{
+    let file = std::env::temp_dir ().join (format! ("payments-test-{}-usage", std::process::id ()));
+    let mut pay = Payment::new ();
+    pay.set_usage_file (Some (file.clone ()));
+    for com in ["add a b", "pay a x 10", "part x b", "print", "print"]
+    {
+        pay.command (&format! ("{com}\n")).unwrap ();
+    }
+    let stats = output (&mut pay, "stats --usage");
+    let counts = std::fs::read_to_string (&file).unwrap ();
+    std::fs::remove_file (&file).ok ();
+    assert_eq! (counts, "add 1\npart 1\npay 1\nprint 2\nstats 1\n");
+    assert! (stats.contains ("  print: 2\n  add: 1\n"), "{stats}");
+    assert! (saved (&mut pay, "usage").get ("usage").is_none ());
+}
+
+// a file in the temp directory only this test run uses
+fn temp_file (name: &str) -> std::path::PathBuf
+{
+    std::env::temp_dir ().join (format! ("payments-test-{}-{name}", std::process::id ()))
+}
+
+#[test]
+fn merging_keeps_payments_made_twice_and_everything_only_the_file_has ()
+{
+    let (base, file) = (temp_file ("base.json"), temp_file ("merge.json"));
+    ledger (&["add a b", "pay a x 10", "part x b", "payment b a 1", &format! ("save {}", base.display ())]);
+    let copy = || ledger (&[&format! ("load {}", base.display ())]);
+    let mut theirs = copy ();
+    for com in ["payment b a 2", "interest 1% monthly", "set currency EUR", "dues 5/month all", "invoice set name club"]
+    {
+        theirs.command (&format! ("{com}\n")).unwrap ();
+    }
+    let pdf = temp_file ("merge.pdf");
+    theirs.command (&format! ("invoice a {}\n", pdf.display ())).unwrap ();
+    std::fs::remove_file (&pdf).ok ();
+    theirs.command (&format! ("save {}\n", file.display ())).unwrap ();
+    let mut ours = copy ();
+    std::fs::remove_file (&base).ok ();
+    ours.command ("payment b a 2\n").unwrap ();
+    let merged = ours.command (&format! ("load --merge {}\n", file.display ()));
+    std::fs::remove_file (&file).ok ();
+    assert_eq! (merged, Ok (()));
+    let json = saved (&mut ours, "merged");
+    assert_eq! (json["transfers"].as_array ().unwrap ().len (), 3);
+    assert_eq! (json["participants"]["a"]["sum"], 0.0);
+    assert! (json["interest"].is_object ());
+    assert! (json["dues"].is_object ());
+    assert_eq! (json["settings"]["currency"], "EUR");
+    assert_eq! (json["invoicing"]["name"], "club");
+    assert_eq! (json["invoicing"]["issued"], 1);
+}
+
+#[test]
+fn merging_checks_every_kind_of_name_before_changing_anything ()
+{
+    let recent = temp_file ("recent");
+    let file = temp_file ("theirs.json");
+    let mut theirs = ledger (&["add a b", "pay a x 10", "part x b", "pay a fam 4"]);
+    theirs.command (&format! ("save {}\n", file.display ())).unwrap ();
+    // x was archived here, and fam is a group
+    let mut ours = ledger (&["add a b c", "pay a x 10", "part x b", "archive x", "group fam b c"]);
+    ours.set_recent_file (Some (recent.clone ()));
+    let before = saved (&mut ours, "before-clash");
+    let merged = ours.command (&format! ("load --merge {}\n", file.display ()));
+    assert_eq! (merged, Err (String::from ("fam is a task in the file but a group here")));
+    ours.command ("group fam none\n").unwrap ();
+    let merged = ours.command (&format! ("load --merge {}\n", file.display ()));
+    std::fs::remove_file (&file).ok ();
+    assert_eq! (merged, Err (String::from ("x is a task in the file but a archived task here")));
+    ours.command ("group fam b c\n").unwrap ();
+    assert_eq! (saved (&mut ours, "after-clash"), before);
+    assert! (!ours.recent_files ().iter ().any (|f| f.ends_with ("theirs.json")));
+    std::fs::remove_file (&recent).ok ();
+}
+
+#[test]
+fn merging_refuses_settings_that_disagree ()
+{
+    let file = temp_file ("settings.json");
+    let mut theirs = ledger (&["add a", "set rounding owner", "limit tasks 5"]);
+    theirs.command (&format! ("save {}\n", file.display ())).unwrap ();
+    let mut ours = ledger (&["add a", "set rounding largest"]);
+    let merged = ours.command (&format! ("load --merge {}\n", file.display ()));
+    assert_eq! (merged, Err (String::from ("The file and the current ledger have different settings")));
+    let mut ours = ledger (&["add a", "double-entry on"]);
+    let merged = ours.command (&format! ("load --merge {}\n", file.display ()));
+    std::fs::remove_file (&file).ok ();
+    assert_eq! (merged, Err (String::from ("The file and the current ledger don't both use double entry")));
+}
+
+#[test]
+fn explain_adds_up_to_the_balance_with_interest ()
+{
+    use payments::clock::FixedClock;
+    use std::sync::Arc;
+
+    let mut pay = Payment::new ();
+    // 2026-04-01, three months after the task
+    pay.set_clock (Arc::new (FixedClock::new (1775001600)));
+    for com in ["add a b", "pay a x 10 --date 2026-01-01", "part x b", "interest 10% monthly"]
+    {
+        pay.command (&format! ("{com}\n")).unwrap ();
+    }
+    for (name, owes, total) in [("b", "b owes 6.50", "  = 6.50"), ("a", "a owes -6.50", "  = -6.50")]
+    {
+        let explained = output (&mut pay, &format! ("explain {name}"));
+        let lines = explained.lines ().collect::<Vec<&str>> ();
+        assert_eq! (lines.first (), Some (&owes), "{explained}");
+        assert_eq! (lines.last (), Some (&total), "{explained}");
+    }
+}
+
+#[test]
+fn compact_folds_only_tasks_of_the_same_day_and_keeps_their_names ()
+{
+    use payments::clock::FixedClock;
+    use std::sync::Arc;
+
+    let mut pay = Payment::new ();
+    // 2024-04-01
+    pay.set_clock (Arc::new (FixedClock::new (1711929600)));
+    for com in ["add a b", "pay a r1 10 --date 2024-01-01", "part r1 b", "pay a r2 20 --date 2024-01-01", "part r2 b"
+                , "pay a r3 30 --date 2024-03-01", "part r3 b", "interest 1% monthly"]
+    {
+        pay.command (&format! ("{com}\n")).unwrap ();
+    }
+    // everything but the number of tasks stays the same
+    let balances = |pay: &mut Payment| output (pay, "print").replace ("unsettled tasks 3", "unsettled tasks 2");
+    let before = balances (&mut pay);
+    pay.command ("compact\n").unwrap ();
+    assert_eq! (balances (&mut pay), before);
+    let tasks = saved (&mut pay, "compact-days")["tasks"].clone ();
+    assert_eq! (tasks.as_object ().unwrap ().keys ().collect::<Vec<_>> (), ["r1", "r3"]);
+    assert_eq! (tasks["r1"]["folded"], serde_json::json! ({ "r2": 2000 }));
+    assert! (output (&mut pay, "explain r2").starts_with ("r2 cost 20.00 and was folded into r1 by compact\n"));
+    pay.command ("remove r2\n").unwrap ();
+    assert! (output (&mut pay, "explain r1").starts_with ("a paid 10.00 for r1\n"));
+    assert! (pay.command ("explain r2\n").is_err ());
+}
+
+#[test]
+fn idempotency_keys_survive_a_restart_and_refuse_other_commands ()
+{
+    use payments::shared::{Reply, SharedLedger};
+
+    let file = temp_file ("outcomes.json");
+    let shared = SharedLedger::new (ledger (&["add a b"]));
+    let paid = shared.command_once ("k1", "pay a x 10").unwrap ();
+    assert_eq! (shared.command_once ("k1", "pay a x 10\n"), Ok (paid.clone ()));
+    assert_eq! (shared.command_once ("k1", "pay a x 20")
+                , Err (String::from ("k1 was already used for a different command: pay a x 10")));
+    // failures are remembered too
+    let failed = shared.command_once ("k2", "pay a");
+    assert! (failed.is_err ());
+    shared.command (&format! ("save {}", file.display ())).unwrap ();
+    // as if the server was started again
+    let shared = SharedLedger::new (ledger (&[&format! ("load {}", file.display ())]));
+    std::fs::remove_file (&file).ok ();
+    assert_eq! (shared.command_once ("k1", "pay a x 10"), Ok (paid));
+    assert_eq! (shared.command_once ("k2", "pay a"), failed);
+    assert_eq! (shared.command ("print").unwrap (), Reply { seq: 0, output: String::from ("a owes 0.00\nb owes 0.00\ntotal spent 10.00, outstanding 0.00, unsettled tasks 0\n") });
+}
+
+#[test]
+fn only_the_latest_idempotency_keys_are_kept ()
+{
+    use payments::payments::Outcome;
+
+    let mut pay = Payment::new ();
+    for i in 0..1001
+    {
+        pay.record_outcome (Outcome { key: format! ("k{i}"), command: String::from ("print"), result: Ok ((0, String::new ())) });
+    }
+    assert! (pay.outcome ("k0").is_none ());
+    assert! (pay.outcome ("k1").is_some ());
+    assert! (pay.outcome ("k1000").is_some ());
+}
+
+#[test]
+fn anonymizing_keeps_the_whole_ledger_but_none_of_its_words ()
+{
+    let file = temp_file ("anonymized.json");
+    let mut pay = ledger (&["add alice bob carol:email=carol@example.com", "field project", "group family bob carol"
+                            , "pay alice rent 30 --field project=secret", "part rent family", "tag rent housing"
+                            , "note rent private", "item rent bedding 10 bob", "pay bob boat 12", "part boat alice"
+                            , "archive boat", "pay carol gym 20", "recur gym monthly", "dues 5/month bob carol"
+                            , "cashbox in 3 --from carol birthday", "payment bob alice 4 lunch", "rule max-cost 100 except housing"
+                            , "rule include-all rent", "interest 2% monthly", "set currency EUR", "invoice set name alice's club"
+                            , "in trip add dave", "in trip pay dave hotel 50"]);
+    pay.command (&format! ("export --anonymize {}\n", file.display ())).unwrap ();
+    let text = std::fs::read_to_string (&file).unwrap ();
+    std::fs::remove_file (&file).ok ();
+    for word in ["alice", "bob", "carol", "dave", "example", "family", "project", "secret", "rent", "housing"
+                 , "private", "bedding", "boat", "gym", "birthday", "lunch", "club", "trip", "hotel"]
+    {
+        assert! (!text.contains (word), "{word} is in {text}");
+    }
+    let json: serde_json::Value = serde_json::from_str (&text).unwrap ();
+    for key in ["groups", "archived", "recurring", "dues", "cashbox", "rules", "interest", "settings", "subledgers", "fields"]
+    {
+        assert! (json.get (key).is_some (), "{key} is missing from {text}");
+    }
+    assert_eq! (json["groups"]["group1"], serde_json::json! (["person2", "person3"]));
+    assert_eq! (json["tasks"]["task4"]["tags"], serde_json::json! (["tag1"]));
+    assert_eq! (json["rules"][0]["except"], serde_json::json! (["tag1"]));
+    assert_eq! (json["rules"][1]["task"], "task4");
+}
+
+#[test]
+fn perturbing_keeps_items_within_the_cost ()
+{
+    let file = temp_file ("perturbed.json");
+    for seed in 0..50
+    {
+        let mut pay = ledger (&["add a b c", "pay a x 0.03", "item x i 0.01 b", "item x j 0.01 c", "item x k 0.01 a"]);
+        pay.set_id_source (std::sync::Arc::new (payments::ids::SeededIds::new (seed)));
+        pay.command (&format! ("export --anonymize --perturb {}\n", file.display ())).unwrap ();
+        let json: serde_json::Value = serde_json::from_str (&std::fs::read_to_string (&file).unwrap ()).unwrap ();
+        let task = &json["tasks"]["task1"];
+        let items = task["items"].as_object ().unwrap ().values ().map (|i| i["cost"].as_i64 ().unwrap ()).sum::<i64> ();
+        assert! (items <= task["cost"].as_i64 ().unwrap (), "{task}");
+    }
+    std::fs::remove_file (&file).ok ();
+}
+
+#[test]
+fn add_checks_every_name_before_adding_any ()
+{
+    let mut pay = ledger (&["add carol"]);
+    assert_eq! (pay.command ("add alice bob alice\n"), Err (String::from ("Nobody was added:\n  alice was given more than once")));
+    assert! (pay.command ("add dave carol\n").is_err ());
+    let json = saved (&mut pay, "atomic-add");
+    assert_eq! (json["participants"].as_object ().unwrap ().keys ().collect::<Vec<&String>> (), ["carol"]);
+}
+
+#[test]
+fn amounts_can_be_written_the_way_receipts_show_them ()
+{
+    use payments::money::Money;
+    assert_eq! (Money::input ("$1,234.50"), Ok (Money::from_cents (123450)));
+    assert_eq! (Money::input ("17.50+2*3.25"), Ok (Money::from_cents (2400)));
+    assert_eq! (Money::eval ("10/3*3"), Ok (Money::from_cents (1000)));
+    assert! (Money::parse ("1,2,3.4.5").is_err ());
+    let mut pay = ledger (&["add a b", "pay a dinner $1,234.50", "part dinner b", "pay b taxi 17.50+2*3.25", "part taxi a"]);
+    assert! (pay.command ("pay a lunch 1+\n").is_err ());
+    assert! (pay.command ("pay a lunch abc\n").is_err ());
+    let json = saved (&mut pay, "receipt-amounts");
+    assert_eq! ((&json["tasks"]["dinner"]["cost"], &json["tasks"]["taxi"]["cost"]), (&serde_json::json! (123450), &serde_json::json! (2400)));
+    assert! (json["tasks"].get ("lunch").is_none ());
+    assert_eq! (output (&mut pay, "print"), "a owes -605.25\nb owes 605.25\ntotal spent 1258.50, outstanding 605.25, unsettled tasks 2\n");
+}
+
+#[test]
+fn weights_and_percents_split_the_cost_by_them ()
+{
+    let mut pay = ledger (&["add a b c", "pay a x 100", "part x b:3 c"]);
+    assert_eq! (output (&mut pay, "print"), "a owes -80.00\nb owes 60.00\nc owes 20.00\ntotal spent 100.00, outstanding 80.00, unsettled tasks 1\n");
+    pay.command ("pay a y 100\n").unwrap ();
+    assert_eq! (pay.command ("part y a=50% b=30%\n"), Err (String::from ("The percentages for y add up to 80%, not 100%")));
+    pay.command ("part y a=50% b=30% c=20%\n").unwrap ();
+    assert_eq! (output (&mut pay, "print"), "a owes -130.00\nb owes 90.00\nc owes 40.00\ntotal spent 200.00, outstanding 130.00, unsettled tasks 2\n");
+}
+
+#[test]
+fn uneven_charges_everyone_exactly_what_they_had ()
+{
+    let mut pay = ledger (&["add a b c", "uneven lunch a=12.50 b=9 c=15.25 --payer b"]);
+    assert_eq! (output (&mut pay, "print lunch").lines ().skip (2).collect::<Vec<&str>> (), ["    a 12.50", "    b 9.00", "    c 15.25"]);
+    assert_eq! (output (&mut pay, "print"), "a owes 12.50\nb owes -27.75\nc owes 15.25\ntotal spent 36.75, outstanding 27.75, unsettled tasks 1\n");
+    // nothing is added when one of the amounts is wrong
+    assert! (pay.command ("uneven dinner a=5 b=oops --payer a\n").is_err ());
+    assert! (saved (&mut pay, "uneven")["tasks"].get ("dinner").is_none ());
+}
+
+#[test]
+fn groups_take_part_as_all_of_their_members ()
+{
+    let mut pay = ledger (&["add a b c", "group kids b c", "pay a z 30", "part z kids", "rename b bee"]);
+    assert_eq! (output (&mut pay, "print"), "a owes -20.00\nbee owes 10.00\nc owes 10.00\ntotal spent 30.00, outstanding 20.00, unsettled tasks 1\n");
+    assert_eq! (output (&mut pay, "groups"), "kids: bee, c\n");
+    assert! (pay.command ("pay kids w 10\n").is_err ());
+}
+
+#[test]
+fn commands_can_be_shortened_while_only_one_fits ()
+{
+    let mut pay = ledger (&["add a b", "pay a x 10", "part x b"]);
+    let print = output (&mut pay, "print");
+    assert_eq! (output (&mut pay, "pri"), print);
+    assert_eq! (pay.command ("pr\n"), Err (String::from ("pr is ambiguous, it could be print, prune")));
+    assert_eq! (pay.command ("pa\n"), Err (String::from ("pa is ambiguous, it could be parse, part, paths, pay, payment")));
+    pay.set_shortcut ("p", "print").unwrap ();
+    assert_eq! (output (&mut pay, "p"), print);
+    assert! (pay.set_shortcut ("q", "nonsense").is_err ());
+}
+
+#[test]
+fn print_shows_tasks_by_name_and_refuses_names_it_doesnt_know ()
+{
+    let mut pay = ledger (&["add alice bob", "pay alice dinner 20 --date 2024-05-01", "part dinner bob"]);
+    assert_eq! (output (&mut pay, "print dinner"), "alice paid 20.00 for dinner on 2024-05-01\n  participants: 2\n    alice 10.00\n    bob 10.00\n");
+    assert_eq! (pay.command ("print dinner nobody\n"), Err (String::from ("Not a task or participant: nobody")));
+}
+
+#[test]
+fn prune_keeps_balances_in_an_opening_task ()
+{
+    let setup = ["add a b c d", "pay a x 30 --date 2022-05-01", "part x b c", "pay b y 60 --date 2022-06-01", "part y c d"
+                 , "payment c a 5 --date 2022-07-01", "pay c z 9 --date 2023-03-01", "part z a b"];
+    let mut pay = ledger (&setup);
+    let print = output (&mut pay, "print");
+    pay.command ("prune --before 2023-01-01 --keep-balances\n").unwrap ();
+    let json = saved (&mut pay, "pruned");
+    assert_eq! (json["tasks"].as_object ().unwrap ().keys ().collect::<Vec<&String>> (), ["opening-2023-01-01", "z"]);
+    assert! (json.get ("transfers").is_none (), "{json}");
+    // only the spending changes, since the opening task costs less
+    assert_eq! (output (&mut pay, "print").lines ().take (4).collect::<Vec<&str>> (), print.lines ().take (4).collect::<Vec<&str>> ());
+    assert_eq! (pay.command ("prune --before 2023-01-01\n"), Err (String::from ("Nothing was paid before 2023-01-01")));
+    // without keeping them the balances go with what was dropped
+    let mut pay = ledger (&setup);
+    pay.command ("prune --before 2023-01-01\n").unwrap ();
+    assert_eq! (output (&mut pay, "print"), "a owes 3.00\nb owes 3.00\nc owes -6.00\nd owes 0.00\ntotal spent 9.00, outstanding 6.00, unsettled tasks 1\n");
+    let mut pay = ledger (&setup);
+    pay.command ("interest 1% monthly\n").unwrap ();
+    assert! (pay.command ("prune --before 2023-01-01 --keep-balances\n").is_err ());
+}
+
+#[test]
+fn dates_are_typed_and_shown_in_the_ledgers_format ()
+{
+    let mut pay = ledger (&["set dates day-first", "add a b", "pay a x 10 --date 3.4.2024", "pay a y 10 --date 2024-04-05"]);
+    assert_eq! (pay.command ("pay a z 10 --date 04/31/2024\n"), Err (String::from ("04/31/2024 is not a date like 31.05.2024")));
+    assert! (output (&mut pay, "print x").starts_with ("a paid 10.00 for x on 03.04.2024\n"));
+    // saved the same way whatever the format
+    assert_eq! (saved (&mut pay, "day-first")["tasks"]["x"]["date"], "2024-04-03");
+    pay.command ("set dates month-first\n").unwrap ();
+    pay.command ("pay a w 1 --date 12/25/2024\n").unwrap ();
+    assert! (output (&mut pay, "print w").starts_with ("a paid 1.00 for w on 12/25/2024\n"));
+    pay.command ("set dates iso\n").unwrap ();
+    assert! (output (&mut pay, "print y").starts_with ("a paid 10.00 for y on 2024-04-05\n"));
+}
+
+#[test]
+fn dates_can_be_given_from_today ()
+{
+    use payments::clock::FixedClock;
+    use std::sync::Arc;
+    let mut pay = ledger (&["add a"]);
+    // a friday
+    pay.set_clock (Arc::new (FixedClock::new (1717156800)));
+    for (date, day) in [("today", "2024-05-31"), ("yesterday", "2024-05-30"), ("3 days ago", "2024-05-28"), ("friday", "2024-05-31")
+                        , ("last friday", "2024-05-24"), ("sat", "2024-05-25"), ("last monday", "2024-05-27"), ("2024-01-02", "2024-01-02")]
+    {
+        pay.command (&format! ("pay a x 10 --date {date}\n")).unwrap ();
+        assert_eq! (saved (&mut pay, "relative")["tasks"]["x"]["date"], day, "{date}");
+    }
+    pay.command ("add b\n").unwrap ();
+    pay.command ("payment a b 1 taxi --date 2 days ago\n").unwrap ();
+    assert_eq! (saved (&mut pay, "relative-payment")["transfers"][0]["date"], "2024-05-29");
+    assert_eq! (pay.command ("pay a x 10 --date someday\n"), Err (String::from ("someday is not a date like 2024-05-31")));
+    assert_eq! (pay.command ("pay a x 10 --date last week\n"), Err (String::from ("week is not a day of the week")));
+}
+
+#[test]
+fn statements_are_written_for_everyone_with_an_email ()
+{
+    let dir = temp_file ("statements");
+    let mut pay = ledger (&["add alice bob:email=bob@example.com carol:email=carol@example.com", "pay alice rent 30", "part rent bob"]);
+    assert_eq! (pay.command (&format! ("notify statements --due 2024-06-30 {}\n", dir.display ())), Ok (()));
+    let read = |name: &str| std::fs::read_to_string (dir.join (format! ("statement-{name}.eml"))).unwrap ();
+    let (bob, carol) = (read ("bob"), read ("carol"));
+    std::fs::remove_dir_all (&dir).ok ();
+    assert! (bob.contains ("To: bob <bob@example.com>\r\n"));
+    // a PDF starts with %PDF
+    assert! (bob.contains ("filename=\"statement.pdf\"\r\n\r\nJVBERi"));
+    assert! (bob.contains ("filename=\"settle.ics\""));
+    assert! (carol.contains ("filename=\"statement.pdf\""));
+    assert! (!carol.contains ("settle.ics"));
+    assert_eq! (pay.take_warnings (), vec! [String::from ("alice has no email, no statement was written for them")]);
+    assert! (pay.command ("notify statements\n").is_err ());
+}
+
+#[test]
+fn signed_reports_verify_until_they_are_changed ()
+{
+    let (key, template) = (temp_file ("signing-key"), temp_file ("report.tmpl"));
+    let (json, empty, pdf) = (temp_file ("signed.json"), temp_file ("empty.json"), temp_file ("signed.pdf"));
+    let mut pay = ledger (&["add alice bob", "pay alice rent 30"]);
+    pay.set_key_file (Some (key.clone ()));
+    std::fs::write (&template, "{\"spent\": \"{{ spent }}\"}\n").unwrap ();
+    let run = |pay: &mut Payment, com: String| pay.command (&format! ("{com}\n"));
+    assert_eq! (run (&mut pay, format! ("report --template {} {} --sign", template.display (), json.display ())), Ok (()));
+    std::fs::write (&template, "{}").unwrap ();
+    assert_eq! (run (&mut pay, format! ("report --template {} {} --sign", template.display (), empty.display ())), Ok (()));
+    assert_eq! (run (&mut pay, format! ("invoice bob {} --sign", pdf.display ())), Ok (()));
+    let signed = std::fs::read_to_string (&json).unwrap ();
+    // the signature is a field, so the file is still JSON
+    let value = serde_json::from_str::<serde_json::Value> (&signed).unwrap ();
+    let public = value["payments-signature"].as_str ().unwrap ().split (' ').nth (1).unwrap ().to_string ();
+    let results = [&json, &empty, &pdf].map (|f| run (&mut pay, format! ("verify {}", f.display ())));
+    let theirs = run (&mut pay, format! ("verify {} {}", json.display (), "0".repeat (64)));
+    std::fs::write (&json, signed.replace ("30.00", "3.00")).unwrap ();
+    let changed = run (&mut pay, format! ("verify {}", json.display ()));
+    let unsigned = run (&mut pay, format! ("verify {}", template.display ()));
+    for file in [&key, &template, &json, &empty, &pdf]
+    {
+        std::fs::remove_file (file).ok ();
+    }
+    assert_eq! (value["spent"], "30.00");
+    assert_eq! (results, [Ok (()), Ok (()), Ok (())]);
+    assert_eq! (theirs, Err (format! ("{} wasn't signed with {}, it was signed with {public}", json.display (), "0".repeat (64))));
+    assert_eq! (changed, Err (format! ("{} was changed since it was signed", json.display ())));
+    assert_eq! (unsigned, Err (format! ("{} isn't signed", template.display ())));
+    assert! (pay.command ("report --template x.tmpl --sign\n").is_err ());
+}